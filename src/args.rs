@@ -1,24 +1,316 @@
 use clap::{Clap, IntoApp};
 use clap_generate::{generate, generators::*};
+use std::time::Duration;
 
 // in line with the [[bin]] name in Cargo.toml
 static BIN_NAME: &str = "rkl";
 #[derive(Clap, Clone, PartialEq, Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Command {
     /// Show description of a pod
-    DESCRIBE {name: String},
+    DESCRIBE {
+        name: String,
+        /// Only print the given section of the description
+        #[clap(long, short, arg_enum)]
+        section: Option<Section>,
+    },
     /// Delete a pod
-    DELETE {name: String},
+    DELETE {
+        #[clap(required_unless_present_any = &["selector", "stdin"])]
+        name: Option<String>,
+        /// Delete every pod matching this label selector instead of a pod named like `name`
+        #[clap(long, short = 'l', requires = "all", conflicts_with = "name")]
+        selector: Option<String>,
+        /// Confirm that all pods matching the selector are deleted
+        #[clap(long, requires = "selector")]
+        all: bool,
+        /// Delete immediately with --grace-period=0 --force, for pods stuck in Terminating
+        #[clap(long, short)]
+        force: bool,
+        /// Print how long deleting took, kubectl blocking until the pod is gone within --timeout
+        #[clap(long, short)]
+        wait: bool,
+        /// Delete the pod named like each line read from stdin, without asking, and report on every line
+        #[clap(long, conflicts_with_all = &["name", "selector", "force"])]
+        stdin: bool,
+    },
     /// Show image of a pod
     IMAGE {name: String},
-    /// Show docker container id within a pod
+    /// Show containers of a pod with their images, states and restarts
     CONTAINER {name: String},
+    /// Show IP, node, QoS class, service account, owner and start time of a pod
+    INFO {name: String},
     /// Show log
-    LOGS {name: String},
+    LOGS {
+        name: String,
+        /// With several pods, interleave their lines by timestamp instead of one pod after another
+        #[clap(long)]
+        merge_by_time: bool,
+        /// Color whole lines of merged logs with the color of their pod, not only the pod name
+        #[clap(long, requires = "merge-by-time")]
+        color_lines: bool,
+        /// Keep streaming the log, moving on to the pod replacing it once it is recreated
+        #[clap(long, short, conflicts_with = "merge-by-time")]
+        follow: bool,
+        /// With --follow, stream every pod named like `name`, pods showing up later included, each line prefixed with its pod
+        #[clap(long, requires = "follow")]
+        include_new: bool,
+        /// Fetch the logs of every container, init containers included, each line prefixed with its container
+        #[clap(long)]
+        all_containers: bool,
+        /// Container to fetch the logs of, the default container the pod names if not given
+        #[clap(long, short, conflicts_with = "all-containers")]
+        container: Option<String>,
+        /// Reformat JSON log lines as time, colored level, message and the remaining fields
+        #[clap(long, conflicts_with = "follow")]
+        pretty_json: bool,
+        /// With --pretty-json, only show these comma separated fields next to the message
+        #[clap(long, requires = "pretty-json")]
+        json_fields: Option<String>,
+    },
     /// Execute a command in a container
-    EXEC {name: String},
+    EXEC {
+        name: String,
+        /// Run the command on every pod named like `name` at once
+        #[clap(long, short)]
+        all: bool,
+        /// Container to run in, the default container the pod names if not given
+        #[clap(long, short)]
+        container: Option<String>,
+        /// Stop the session after this long, like 30m or 1h30m, warning a minute before
+        #[clap(long, value_name = "DURATION", parse(try_from_str = parse_duration), conflicts_with = "all")]
+        max_duration: Option<Duration>,
+        /// Command to run instead of an interactive session, given after --
+        #[clap(last = true)]
+        command: Vec<String>,
+    },
+    /// Show resource requests and limits of containers next to their usage
+    RESOURCES {name: String},
+    /// List containers recently killed for running out of memory
+    OOM {name: Option<String>},
+    /// Show restart counts of pods, alerting when they restart with --watch
+    RESTARTS {
+        name: Option<String>,
+        /// Keep polling and alert when a pod restarts
+        #[clap(long, short)]
+        watch: bool,
+        /// Alert when the restart count of a pod exceeds this number
+        #[clap(long, short)]
+        threshold: Option<u32>,
+        /// Seconds between two polls
+        #[clap(long, short, default_value = "10")]
+        interval: u64,
+    },
+    /// List the images in use and how many pods run each of them
+    IMAGES {name: Option<String>},
+    /// Delete evicted, completed or failed pods, all of them unless one is chosen
+    CLEANUP {
+        name: Option<String>,
+        /// Delete evicted pods
+        #[clap(long)]
+        evicted: bool,
+        /// Delete completed pods
+        #[clap(long)]
+        completed: bool,
+        /// Delete failed pods
+        #[clap(long)]
+        failed: bool,
+    },
+    /// Roll a deployment back, found by its name or the name of one of its pods
+    ROLLBACK {
+        name: String,
+        /// Revision to roll back to, asked for after showing the history if not given
+        #[clap(long)]
+        to_revision: Option<u32>,
+        /// Roll back without asking for confirmation
+        #[clap(long, short)]
+        yes: bool,
+    },
+    /// Show the revisions of a deployment with their images and change causes
+    #[clap(name = "history-rollout")]
+    HISTORYROLLOUT {name: String},
+    /// Run a local script inside a pod, streaming its output
+    #[clap(name = "run-script")]
+    RUNSCRIPT {
+        name: String,
+        /// Path of the local script
+        script: String,
+        /// Shell running the script in the pod
+        #[clap(long, default_value = "sh")]
+        shell: String,
+        /// Arguments passed to the script, given after --
+        #[clap(last = true)]
+        args: Vec<String>,
+    },
+    /// Run any kubectl command on a pod, given after -- with {pod} and {ns} standing for its name and namespace
+    RAW {
+        name: String,
+        /// The kubectl arguments, like `logs {pod} --previous`
+        #[clap(last = true, required = true)]
+        args: Vec<String>,
+    },
+    /// Show pod counts and restarts per node
+    #[clap(name = "nodes-load")]
+    NODESLOAD,
+    /// Print the full name of the pod named like `name`
+    RESOLVE {name: String},
+    /// Open a dashboard URL of a pod in the browser, built from a template with {pod}, {namespace} and {node}
+    OPEN {
+        name: String,
+        /// URL template, defaults to the env RKL_OPEN_URL
+        #[clap(long, short)]
+        template: Option<String>,
+    },
+    /// Manage the config file
+    CONFIG {
+        #[clap(subcommand)]
+        action: ConfigAction,
+    },
+    /// Print the current context and namespace for a shell prompt, quietly
+    PROMPT {
+        /// Template with {context} and {namespace}
+        #[clap(long, short, default_value = "{context}/{namespace}")]
+        format: String,
+    },
+    /// Summarize pods named like `name`: counts by status, top restarters, pending pods and recent warnings
+    REPORT {name: Option<String>},
+    /// Show cpu and memory usage of pods against their limits, the heaviest first
+    TOP {
+        name: Option<String>,
+        /// Resource to sort by, cpu if not given
+        #[clap(long, arg_enum)]
+        sort_by: Option<SortBy>,
+        /// Only show pods using at least this share of their limit of the sorted resource, like 80%
+        #[clap(long)]
+        above: Option<String>,
+    },
+    /// Show cpu and memory usage of nodes against their allocatable resources, the busiest first
+    #[clap(name = "top-nodes")]
+    TOPNODES {
+        /// Resource to sort by, cpu if not given
+        #[clap(long, arg_enum)]
+        sort_by: Option<SortBy>,
+    },
+    /// Ssh to the node hosting a pod
+    #[clap(name = "node-ssh")]
+    NODESSH {
+        name: String,
+        /// Ssh command template with {address} and {node}, defaults to the env RKL_SSH_COMMAND or "ssh {address}"
+        #[clap(long, short)]
+        template: Option<String>,
+    },
+    /// List the containers of a pod as the runtime of its node sees them, with crictl over ssh
+    #[clap(name = "node-containers")]
+    NODECONTAINERS {
+        name: String,
+        /// Ssh command template with {address} and {node}, defaults to the env RKL_SSH_COMMAND or "ssh {address}"
+        #[clap(long, short)]
+        template: Option<String>,
+        /// Print the full crictl inspect of each container instead
+        #[clap(long)]
+        inspect: bool,
+    },
+    /// Forward a local port to a pod, or to each of several pods on free local ports
+    #[clap(name = "port-forward")]
+    PORTFORWARD {
+        name: String,
+        /// Remote port, or local:remote for a single pod
+        port: String,
+    },
+    /// Explain why a pod is pending from its scheduling events
+    #[clap(name = "why-pending")]
+    WHYPENDING {name: String},
+    /// List pods which are neither running nor completed, most restarted first
+    CRASHING {
+        name: Option<String>,
+        /// Pick one of the crashing pods and run this command on it
+        #[clap(long, short, arg_enum)]
+        action: Option<Action>,
+    },
+    /// Show a diff of the specs of two pods, like their images, env vars, resources and node
+    DIFF {first: String, second: String},
+    /// Show how the spec of a pod drifted from the pod template of its workload, e.g. by webhooks or edits
+    DRIFT {name: String},
+    /// Print the yaml of a pod without its status and the fields the cluster filled in, to apply elsewhere
+    EXPORT {
+        name: String,
+        /// Export a resource of this kind named like `name` instead of a pod, e.g. deployment or cm
+        #[clap(long, short)]
+        kind: Option<String>,
+    },
+    /// Apply the manifests of a file or directory, showing the resources created or updated first
+    APPLY {
+        path: String,
+        /// Only check the manifests with a server side dry run, changing nothing
+        #[clap(long)]
+        dry_run: bool,
+        /// Apply without asking for confirmation
+        #[clap(long, short)]
+        yes: bool,
+    },
+    /// Time listing, parsing and ranking pods and reading the caches, to tune rkl for a large cluster
+    BENCH {
+        /// Name to rank the pods against
+        #[clap(default_value = "kg")]
+        name: String,
+        /// Synthetic pod names ranked on top of the pods of the cluster
+        #[clap(long, value_name = "N", default_value = "10000")]
+        names: usize,
+    },
+    /// Show the versions of rkl, kubectl and the cluster, warning when kubectl is too far from the cluster
+    VERSION,
+    /// Tell which namespace, or with --all-contexts which cluster, pods named like `name` live in
+    FIND {
+        name: String,
+        /// Search every context of the kubeconfig at once instead of the current one
+        #[clap(long)]
+        all_contexts: bool,
+    },
 }
+
+#[derive(Clap, Clone, PartialEq, Debug)]
+pub enum Action {
+    Describe,
+    Delete,
+    Image,
+    Container,
+    Logs,
+    Exec,
+    Resources,
+}
+#[derive(Clap, Clone, Copy, PartialEq, Debug)]
+pub enum CandidatesFormat {
+    Tsv,
+    Json,
+}
+
+#[derive(Clap, Clone, PartialEq, Debug)]
+pub enum Section {
+    Events,
+    Conditions,
+    Volumes,
+    Containers,
+}
+
 #[derive(Clap, Clone, PartialEq, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum ConfigAction {
+    /// Write a starter config file, asking for kubectl, context, namespace and defaults
+    INIT,
+    /// Check the config file and the env overrides: keys, kubectl command, certificate files, names
+    VALIDATE,
+    /// Print every setting with its effective value and where it comes from
+    SHOW,
+}
+
+#[derive(Clap, Clone, PartialEq, Debug)]
+pub enum SortBy {
+    Cpu,
+    Memory,
+}
+
+#[derive(Clap, Clone, PartialEq, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Shell {
     Bash,
     Zsh,
@@ -27,7 +319,7 @@ pub enum Shell {
     Elvish,
 }
 
-#[derive(Clap, Clone, PartialEq, Debug)]
+#[derive(Clap, Clone, PartialEq, Debug, Default)]
 #[clap(version = "0.1", author = "luyi666 <ly921225@gmail.com>")]
 pub struct Args {
     /// Generate a SHELL completion script and print to stdout
@@ -42,6 +334,174 @@ pub struct Args {
     /// Set your own `kubectl` command, alias kubectl
     #[clap(long, short)]
     pub kubectl: Option<String>,
+    /// Show the newest warning event of each candidate pod
+    #[clap(long)]
+    pub with_events: bool,
+    /// Show every column of candidate pods, including nominated node and readiness gates
+    #[clap(long, conflicts_with = "compact")]
+    pub wide: bool,
+    /// Show only name, status and age of candidate pods
+    #[clap(long)]
+    pub compact: bool,
+    /// Forget the pod picked last time for this name
+    #[clap(long)]
+    pub forget: bool,
+    /// Pick the top ranked candidate without asking when a name matches several
+    #[clap(long, conflicts_with = "all-matches")]
+    pub first: bool,
+    /// Pick every candidate without asking when a name matches several pods
+    #[clap(long)]
+    pub all_matches: bool,
+    /// Pick the candidate with this number, letter or name without asking when a name matches several pods
+    #[clap(long, value_name = "CHOICE", conflicts_with_all = &["first", "all-matches"])]
+    pub select: Option<String>,
+    /// Print the ranked candidates for the name with their score, status and namespace, and exit
+    #[clap(long)]
+    pub list_candidates_only: bool,
+    /// Format of the candidates of --list-candidates-only, tsv if not given
+    #[clap(long, arg_enum, value_name = "FORMAT", requires = "list-candidates-only")]
+    pub candidates_format: Option<CandidatesFormat>,
+    /// Run this command too on the resolved pods afterwards, can be repeated
+    #[clap(long, arg_enum, value_name = "ACTION", number_of_values = 1, global = true)]
+    pub then: Vec<Action>,
+    /// Pick pods from the list cached by the last run, contacting the cluster only to run the command
+    #[clap(long)]
+    pub offline: bool,
+    /// Show and use sidecar containers like istio-proxy, skipped by logs, exec and image otherwise
+    #[clap(long)]
+    pub include_sidecars: bool,
+    /// Mask the values of keys like PASSWORD, TOKEN or KEY in the output of describe and logs
+    #[clap(long)]
+    pub redact: bool,
+    /// Copy the name of the resolved pod to the clipboard
+    #[clap(long)]
+    pub copy: bool,
+    /// Namespace of the pods, the one of the current context if not given
+    #[clap(long, short)]
+    pub namespace: Option<String>,
+    /// Kubeconfig context to use
+    #[clap(long)]
+    pub context: Option<String>,
+    /// Kubeconfig files to merge, separated like KUBECONFIG, used instead of KUBECONFIG
+    #[clap(long)]
+    pub kubeconfig: Option<String>,
+    /// File holding a bearer token to authenticate with, read each time kubectl runs
+    #[clap(long)]
+    pub token_file: Option<String>,
+    /// User to impersonate in every kubectl command
+    #[clap(long = "as", value_name = "USER")]
+    pub as_user: Option<String>,
+    /// Group to impersonate, can be repeated
+    #[clap(long, value_name = "GROUP", number_of_values = 1, requires = "as-user")]
+    pub as_group: Vec<String>,
+    /// Give up on kubectl commands running longer than SECONDS, interactive ones aside
+    #[clap(long, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+    /// Most kubectl processes run at once by commands working on many pods [default: 8]
+    #[clap(long, value_name = "N")]
+    pub concurrency: Option<usize>,
+    /// Save the kubectl commands run and their output to fixture files in DIR
+    #[clap(long, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<String>,
+    /// Answer kubectl commands with the output recorded in DIR instead of running them
+    #[clap(long, value_name = "DIR")]
+    pub replay: Option<String>,
+    /// Write the result to FILE instead of stdout, printing only how much was written
+    #[clap(long, value_name = "FILE", global = true)]
+    pub output_file: Option<String>,
+    /// Append the result to the file of --output-file instead of replacing it
+    #[clap(long, requires = "output-file", global = true)]
+    pub append: bool,
+    /// Why pods are deleted, recorded in the audit log, asked for when the config sets require_reason
+    #[clap(long, value_name = "REASON", global = true)]
+    pub reason: Option<String>,
+    /// Refuse commands changing the cluster, like delete, exec, rollback or apply
+    #[clap(long, global = true)]
+    pub read_only: bool,
+    /// Ask kubectl auth can-i first, failing before running anything the user may not do
+    #[clap(long, global = true)]
+    pub check_access: bool,
+    /// Send a desktop notification, or ring the bell, when the command finishes
+    #[clap(long, global = true)]
+    pub notify: bool,
+    /// Arguments given after `--`, appended to the kubectl commands run on the resolved pods
+    #[clap(skip)]
+    pub kubectl_args: Vec<String>,
+}
+
+impl Action {
+    /// The command running this action on the pod `name`
+    pub fn to_command(&self, name: String) -> Command {
+        match self {
+            Action::Describe => Command::DESCRIBE {name, section: None},
+            Action::Delete => Command::DELETE {name: Some(name), selector: None, all: false, force: false, wait: false, stdin: false},
+            Action::Image => Command::IMAGE {name},
+            Action::Container => Command::CONTAINER {name},
+            Action::Logs => Command::LOGS {name, merge_by_time: false, color_lines: false, follow: false, include_new: false, all_containers: false, container: None, pretty_json: false, json_fields: None},
+            Action::Exec => Command::EXEC {name, all: false, container: None, max_duration: None, command: Vec::new()},
+            Action::Resources => Command::RESOURCES {name},
+        }
+    }
+}
+
+impl Command {
+    /// Whether the command runs on the pods it resolves the way the actions of
+    /// --then do, the others having a flow of their own which --then cannot follow
+    pub fn chains(&self) -> bool {
+        matches!(
+            self,
+            Command::DESCRIBE {..} | Command::IMAGE {..} | Command::CONTAINER {..} | Command::INFO {..} | Command::RESOURCES {..}
+                | Command::WHYPENDING {..} | Command::RUNSCRIPT {..} | Command::RAW {..} | Command::EXPORT {kind: None, ..}
+                | Command::DELETE {selector: None, stdin: false, ..} | Command::LOGS {follow: false, ..} | Command::EXEC {all: false, ..}
+        )
+    }
+}
+
+#[test]
+fn test_chains() {
+    let command = |argv: &[&str]| Args::parse_from(argv).cmd.unwrap();
+    assert!(command(&["rkl", "logs", "kg"]).chains());
+    assert!(command(&["rkl", "delete", "kg"]).chains());
+    assert!(!command(&["rkl", "logs", "kg", "--follow"]).chains());
+    assert!(!command(&["rkl", "delete", "-l", "app=kg", "--all"]).chains());
+    assert!(!command(&["rkl", "crashing", "--action", "logs"]).chains());
+}
+
+impl Section {
+    /// Top level headers of `kubectl describe po` output belonging to this section
+    pub fn headers(&self) -> &[&str] {
+        match self {
+            Section::Events => &["Events"],
+            Section::Conditions => &["Conditions"],
+            Section::Volumes => &["Volumes"],
+            Section::Containers => &["Init Containers", "Containers"],
+        }
+    }
+}
+
+impl Args {
+    /// Parse the command line, taking the arguments after `--` as extra
+    /// arguments to kubectl, but for the subcommands taking them themselves.
+    pub fn parse_with_kubectl_args(argv: Vec<String>) -> Args {
+        let args = Args::parse_kubectl_args(argv);
+        if !args.then.is_empty() && args.cmd.as_ref().is_some_and(|cmd| !cmd.chains()) {
+            let message = "--then follows commands running on the pods they resolve, like describe, logs, exec or delete by name".to_string();
+            clap::Error::with_description(message, clap::ErrorKind::ArgumentConflict).exit();
+        }
+        args
+    }
+
+    fn parse_kubectl_args(argv: Vec<String>) -> Args {
+        if let Some(separator) = argv.iter().position(|arg| arg == "--") {
+            if let Ok(mut args) = Args::try_parse_from(&argv[..separator]) {
+                if !matches!(args.cmd, Some(Command::EXEC {..}) | Some(Command::RUNSCRIPT {..}) | Some(Command::RAW {..})) {
+                    args.kubectl_args = argv[separator + 1..].to_vec();
+                    return args;
+                }
+            }
+        }
+        Args::parse_from(argv)
+    }
 }
 
 impl Shell {
@@ -58,6 +518,36 @@ impl Shell {
     }
 }
 
+/// A duration given as hours, minutes and seconds, like `1h30m` or `90s`.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in text.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' | 's' if !number.is_empty() => {
+                let unit = match c { 'h' => 3600, 'm' => 60, _ => 1 };
+                seconds += number.parse::<u64>().map_err(|e| e.to_string())? * unit;
+                number.clear();
+            }
+            _ => return Err(format!("{} is not a duration like 30m or 1h30m", text)),
+        }
+    }
+    if !number.is_empty() || seconds == 0 {
+        return Err(format!("{} is not a duration like 30m or 1h30m", text));
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+    assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+    assert!(parse_duration("30").is_err());
+    assert!(parse_duration("m").is_err());
+    assert!(parse_duration("0s").is_err());
+}
+
 #[test]
 fn test_command() {
     assert_eq!(
@@ -65,9 +555,155 @@ fn test_command() {
             completion: None,
             middle: None,
             kubectl: None,
-            cmd: Some(Command::DELETE {name: "sophon".to_string()}),
+            cmd: Some(Command::DELETE {name: Some("sophon".to_string()), selector: None, all: false, force: false, wait: false, stdin: false}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "delete", "sophon"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::DELETE {name: Some("sophon".to_string()), selector: None, all: false, force: true, wait: true, stdin: false}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "delete", "sophon", "--force", "--wait"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::DELETE {name: Some("kg".to_string()), selector: None, all: false, force: true, wait: false, stdin: false}),
+            reason: Some("stuck in Terminating".to_string()),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "delete", "kg", "--force", "--reason", "stuck in Terminating"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::DELETE {name: None, selector: Some("app=kg".to_string()), all: true, force: false, wait: false, stdin: false}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "delete", "-l", "app=kg", "--all"])
+    );
+    assert_eq!(
+        Args {
+            all_matches: true,
+            cmd: Some(Command::DELETE {name: Some("kg".to_string()), selector: None, all: false, force: false, wait: false, stdin: false}),
+            ..Default::default()
         },
-        Args::parse_from(&["rkl", "delete", "sophon"])
+        Args::parse_from(["rkl", "--all-matches", "delete", "kg"])
+    );
+    assert_eq!(
+        Args {
+            then: vec![Action::Logs, Action::Image],
+            cmd: Some(Command::DESCRIBE {name: "gw".to_string(), section: None}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "describe", "gw", "--then", "logs", "--then", "image"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::DELETE {name: None, selector: None, all: false, force: false, wait: true, stdin: true}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "delete", "--stdin", "--wait"])
+    );
+    assert!(Args::try_parse_from(["rkl", "delete", "-l", "app=kg"]).is_err());
+    assert!(Args::try_parse_from(["rkl", "delete"]).is_err());
+    assert_eq!(
+        Args {
+            cmd: Some(Command::CLEANUP {name: Some("kg".to_string()), evicted: true, completed: false, failed: true}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "cleanup", "kg", "--evicted", "--failed"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::ROLLBACK {name: "kg".to_string(), to_revision: Some(3), yes: true}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "rollback", "kg", "--to-revision", "3", "-y"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::EXEC {name: "web".to_string(), all: true, container: None, max_duration: None, command: vec!["ls".to_string(), "-l".to_string()]}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "exec", "web", "--all", "--", "ls", "-l"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::EXEC {name: "web".to_string(), all: false, container: Some("nginx".to_string()), max_duration: None, command: vec!["sh".to_string()]}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "exec", "web", "-c", "nginx", "--", "sh"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::EXEC {name: "web".to_string(), all: false, container: None, max_duration: Some(Duration::from_secs(1800)), command: Vec::new()}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "exec", "web", "--max-duration", "30m"])
+    );
+    assert!(Args::try_parse_from(["rkl", "exec", "web", "--all", "--max-duration", "30m", "--", "ls"]).is_err());
+    let argv = |line: &str| line.split(' ').map(str::to_string).collect::<Vec<String>>();
+    assert_eq!(
+        Args {
+            cmd: Some(Command::DESCRIBE {name: "gw".to_string(), section: None}),
+            kubectl_args: vec!["--show-events=false".to_string()],
+            ..Default::default()
+        },
+        Args::parse_with_kubectl_args(argv("rkl describe gw -- --show-events=false"))
+    );
+    assert_eq!(Args::parse_with_kubectl_args(argv("rkl exec web -- ls")), Args::parse_from(argv("rkl exec web -- ls")));
+    assert_eq!(
+        Args {
+            cmd: Some(Command::RAW {name: "kg".to_string(), args: argv("logs {pod} --previous")}),
+            ..Default::default()
+        },
+        Args::parse_with_kubectl_args(argv("rkl raw kg -- logs {pod} --previous"))
+    );
+    assert!(Args::try_parse_from(["rkl", "raw", "kg"]).is_err());
+    assert_eq!(
+        Args {
+            cmd: Some(Command::RUNSCRIPT {
+                name: "kg".to_string(),
+                script: "./diag.sh".to_string(),
+                shell: "bash".to_string(),
+                args: vec!["-v".to_string()],
+            }),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "run-script", "kg", "./diag.sh", "--shell", "bash", "--", "-v"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::LOGS {name: "sophon".to_string(), merge_by_time: true, color_lines: true, follow: false, include_new: false, all_containers: false, container: None, pretty_json: false, json_fields: None}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "logs", "sophon", "--merge-by-time", "--color-lines"])
+    );
+    assert!(Args::try_parse_from(["rkl", "logs", "sophon", "--color-lines"]).is_err());
+    assert_eq!(
+        Args {
+            cmd: Some(Command::LOGS {
+                name: "sophon".to_string(), merge_by_time: false, color_lines: false, follow: false, include_new: false, all_containers: false, container: None,
+                pretty_json: true, json_fields: Some("user,ms".to_string()),
+            }),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "logs", "sophon", "--pretty-json", "--json-fields", "user,ms"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::LOGS {name: "sophon".to_string(), merge_by_time: false, color_lines: false, follow: true, include_new: false, all_containers: true, container: None, pretty_json: false, json_fields: None}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "logs", "sophon", "-f", "--all-containers"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::HISTORYROLLOUT {name: "kg".to_string()}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "history-rollout", "kg"])
     );
     assert_eq!(
         Args {
@@ -75,17 +711,261 @@ fn test_command() {
             middle: None,
             kubectl: None,
             cmd: Some(Command::IMAGE {name: "sophon".to_string()}),
+            ..Default::default()
         },
-        Args::parse_from(&["rkl", "image", "sophon"])
+        Args::parse_from(["rkl", "image", "sophon"])
     );
     assert_eq!(
         Args {
             completion: None,
             middle: None,
             kubectl: None,
-            cmd: Some(Command::DESCRIBE {name: "sophon".to_string()}),
+            cmd: Some(Command::DESCRIBE {name: "sophon".to_string(), section: None}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "describe", "sophon"])
+    );
+    assert_eq!(
+        Args {
+            completion: None,
+            middle: None,
+            kubectl: None,
+            cmd: Some(Command::DESCRIBE {name: "sophon".to_string(), section: Some(Section::Events)}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "describe", "sophon", "--section", "events"])
+    );
+    assert_eq!(
+        Args {
+            completion: None,
+            middle: None,
+            kubectl: None,
+            cmd: Some(Command::LOGS {name: "sophon".to_string(), merge_by_time: false, color_lines: false, follow: false, include_new: false, all_containers: false, container: None, pretty_json: false, json_fields: None}),
+            with_events: true,
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "--with-events", "logs", "sophon"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::CRASHING {name: None, action: None}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "crashing"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::CRASHING {name: Some("kg".to_string()), action: Some(Action::Logs)}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "crashing", "kg", "--action", "logs"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::DIFF {first: "kg-1".to_string(), second: "kg-2".to_string()}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "diff", "kg-1", "kg-2"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::DRIFT {name: "kg".to_string()}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "drift", "kg"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::EXPORT {name: "kg".to_string(), kind: Some("deploy".to_string())}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "export", "kg", "--kind", "deploy"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::APPLY {path: "deploy/".to_string(), dry_run: false, yes: true}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "apply", "deploy/", "-y"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::BENCH {name: "kg".to_string(), names: 500}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "bench", "--names", "500"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::VERSION),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "version"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::OOM {name: Some("kg".to_string())}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "oom", "kg"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::RESTARTS {name: Some("kg".to_string()), watch: true, threshold: Some(3), interval: 10}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "restarts", "kg", "--watch", "--threshold", "3"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::NODESLOAD),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "nodes-load"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::TOP {name: Some("kg".to_string()), sort_by: Some(SortBy::Memory), above: Some("80%".to_string())}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "top", "kg", "--sort-by", "memory", "--above", "80%"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::CONFIG {action: ConfigAction::INIT}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "config", "init"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::CONFIG {action: ConfigAction::VALIDATE}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "config", "validate"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::CONFIG {action: ConfigAction::SHOW}),
+            kubeconfig: Some("/etc/kube/a:/etc/kube/b".to_string()),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "--kubeconfig", "/etc/kube/a:/etc/kube/b", "config", "show"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::IMAGE {name: "kg".to_string()}),
+            as_user: Some("jane".to_string()),
+            as_group: vec!["dev".to_string(), "ops".to_string()],
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "--as", "jane", "--as-group", "dev", "--as-group", "ops", "image", "kg"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::PROMPT {format: "{context}/{namespace}".to_string()}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "prompt"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::REPORT {name: None}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "report"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::TOPNODES {sort_by: Some(SortBy::Memory)}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "top-nodes", "--sort-by", "memory"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::PORTFORWARD {name: "kg".to_string(), port: "9000:8080".to_string()}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "port-forward", "kg", "9000:8080"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::OPEN {name: "kg".to_string(), template: Some("http://grafana/{pod}".to_string())}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "open", "kg", "-t", "http://grafana/{pod}"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::INFO {name: "kg".to_string()}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "info", "kg"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::NODESSH {name: "kg".to_string(), template: Some("ssh root@{address}".to_string())}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "node-ssh", "kg", "--template", "ssh root@{address}"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::NODECONTAINERS {name: "kg".to_string(), template: None, inspect: true}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "node-containers", "kg", "--inspect"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::FIND {name: "kg".to_string(), all_contexts: true}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "find", "kg", "--all-contexts"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::RESOLVE {name: "kg".to_string()}),
+            copy: true,
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "--copy", "resolve", "kg"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::IMAGES {name: None}),
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "images"])
+    );
+    assert_eq!(
+        Args {
+            cmd: Some(Command::LOGS {name: "sophon".to_string(), merge_by_time: false, color_lines: false, follow: false, include_new: false, all_containers: false, container: None, pretty_json: false, json_fields: None}),
+            compact: true,
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "--compact", "logs", "sophon"])
+    );
+    assert!(Args::try_parse_from(["rkl", "--compact", "--wide", "logs", "sophon"]).is_err());
+    assert_eq!(
+        Args {
+            cmd: Some(Command::DESCRIBE {name: "kg".to_string(), section: None}),
+            output_file: Some("kg.txt".to_string()),
+            append: true,
+            ..Default::default()
+        },
+        Args::parse_from(["rkl", "describe", "kg", "--output-file", "kg.txt", "--append"])
+    );
+    assert!(Args::try_parse_from(["rkl", "describe", "kg", "--append"]).is_err());
+    assert_eq!(
+        Args {
+            cmd: Some(Command::IMAGE {name: "kg".to_string()}),
+            list_candidates_only: true,
+            candidates_format: Some(CandidatesFormat::Json),
+            ..Default::default()
         },
-        Args::parse_from(&["rkl", "describe", "sophon"])
+        Args::parse_from(["rkl", "--list-candidates-only", "--candidates-format", "json", "image", "kg"])
     );
     assert_eq!(
         Args {
@@ -93,7 +973,8 @@ fn test_command() {
             middle: None,
             kubectl: None,
             cmd: Some(Command::CONTAINER {name: "sophon".to_string()}),
+            ..Default::default()
         },
-        Args::parse_from(&["rkl", "container", "sophon"])
+        Args::parse_from(["rkl", "container", "sophon"])
     );
 }
\ No newline at end of file
@@ -0,0 +1,65 @@
+// This module keeps the audit log of rkl, `audit` in the data directory, with
+// a tab separated line per destructive command run with a reason: when it
+// ran, who ran it, the command line, the pods it deleted and the reason, e.g.
+//   2026-10-15T09:30:00Z	luyi	rkl delete kg --force	sophon-kg-sophon2-bf9769d97-fgpnn	stuck in Terminating since the node was lost
+// An entry is written once the pods are resolved and confirmed, right before they are deleted.
+// Reasons are asked for when the config sets `require_reason`, or given with --reason.
+
+use crate::paths;
+use crate::pod;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+
+// a time in seconds since the epoch like `2026-10-15T09:30:00Z`
+fn timestamp(seconds: u64) -> String {
+    // the civil date of the days since the epoch, counting years from March
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    let time = seconds % 86400;
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+#[test]
+fn test_timestamp() {
+    assert_eq!(timestamp(0), "1970-01-01T00:00:00Z");
+    assert_eq!(timestamp(1614593100), "2021-03-01T10:05:00Z");
+    assert_eq!(timestamp(1709251199), "2024-02-29T23:59:59Z");
+}
+
+// a line of the audit log, the pods separated by commas, tabs and line breaks of its fields turned into spaces
+fn entry(time: u64, user: &str, command_line: &str, pods: &[String], reason: &str) -> String {
+    let field = |text: &str| text.trim().replace(['\t', '\n', '\r'], " ");
+    format!("{}\t{}\t{}\t{}\t{}", timestamp(time), field(user), field(command_line), field(&pods.join(",")), field(reason))
+}
+
+#[test]
+fn test_entry() {
+    let pods = ["kg-1".to_string(), "kg-2".to_string()];
+    assert_eq!(
+        entry(1614593100, "luyi", "rkl delete kg --force", &pods, "stuck in\tTerminating\n"),
+        "2021-03-01T10:05:00Z\tluyi\trkl delete kg --force\tkg-1,kg-2\tstuck in Terminating"
+    );
+}
+
+/// Append the command line of rkl with the pods it deletes and the reason given for it to the audit log.
+pub fn record(pods: &[String], reason: &str) -> io::Result<()> {
+    let path = match paths::audit_file() {
+        Some(path) => path,
+        None => return Err(io::Error::new(io::ErrorKind::NotFound, "HOME is not set")),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let user = env::var("USER").or_else(|_| env::var("USERNAME")).unwrap_or_default();
+    let command_line = format!("rkl {}", env::args().skip(1).collect::<Vec<String>>().join(" "));
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", entry(pod::now(), &user, &command_line, pods, reason))
+}
@@ -0,0 +1,55 @@
+// This module helps tune rkl for a large cluster: `rkl bench` times each step
+// of resolving a name, from listing and parsing the pods to ranking them and
+// reading the caches, ranking synthetic names on top of the real pods to see
+// how matching scales with the size of a cluster.
+
+use crate::table;
+use std::time::Duration;
+
+// components of the synthetic names, like those of a platform deployed many times
+static COMPONENTS: [&str; 12] = [
+    "apimanager", "approval", "audit", "base", "gateway", "jobmanager", "kg", "notebook", "resource", "retrieve", "session", "user",
+];
+
+/// `count` distinct pod names shaped like those of deployments, the same every run.
+pub fn synthetic_names(count: usize) -> Vec<String> {
+    // a linear congruential generator, enough for hashes which look random
+    let mut seed: u64 = 42;
+    let mut next = move || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        seed >> 24
+    };
+    (0..count)
+        .map(|i| {
+            let component = COMPONENTS[i % COMPONENTS.len()];
+            let release = i / COMPONENTS.len();
+            format!("sophon-{}-sophon{}-{:09x}-{:05x}", component, release, next() & 0xf_ffff_ffff, next() & 0xf_ffff)
+        })
+        .collect()
+}
+
+#[test]
+fn test_synthetic_names() {
+    let names = synthetic_names(100);
+    assert_eq!(names.len(), 100);
+    assert!(names[0].starts_with("sophon-apimanager-sophon0-"));
+    assert!(names[13].starts_with("sophon-approval-sophon1-"));
+    assert_eq!(names, synthetic_names(100));
+    assert_eq!(names.iter().collect::<std::collections::HashSet<_>>().len(), 100);
+}
+
+/// How long a step took, with what it went through.
+pub struct Timing {
+    pub step: &'static str,
+    pub took: Duration,
+    pub detail: String,
+}
+
+/// The timings as a table, one step per row.
+pub fn report(timings: &[Timing]) -> String {
+    let mut rows = vec![vec!["STEP".to_string(), "TIME".to_string(), "DETAIL".to_string()]];
+    for timing in timings {
+        rows.push(vec![timing.step.to_string(), format!("{:.1}ms", timing.took.as_secs_f64() * 1000.0), timing.detail.clone()]);
+    }
+    table::format_table(&rows)
+}
@@ -0,0 +1,157 @@
+// This module warns about the certificates the kubectl command names, like
+// those of the default command on a master node, before they expire: kubectl
+// only tells about an expired one with a failed TLS handshake. The expiry is
+// read from the DER inside the PEM files, without needing openssl.
+
+use crate::pod;
+use std::fs;
+
+/// Days before a certificate expires from which rkl warns about it.
+pub static DEFAULT_WARN_DAYS: u64 = 30;
+
+// flags of kubectl naming certificates, with what each one is
+static CERT_FLAGS: [(&str, &str); 2] = [("--certificate-authority=", "CA"), ("--client-certificate=", "client certificate")];
+
+// the base64 content of each certificate of a PEM file, a CA bundle holding several
+fn pem_blocks(pem: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut block: Option<String> = None;
+    for line in pem.lines().map(str::trim) {
+        if line == "-----BEGIN CERTIFICATE-----" {
+            block = Some(String::new());
+        } else if line == "-----END CERTIFICATE-----" {
+            blocks.extend(block.take());
+        } else if let Some(block) = &mut block {
+            block.push_str(line);
+        }
+    }
+    blocks
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    // bits decoded but not output yet, and how many
+    let (mut bits, mut count) = (0u32, 0);
+    for c in text.bytes().filter(|&c| c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        bits = (bits << 6) | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+            bits &= (1 << count) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+// the tag and the content of the DER element at the start of `der`, and what follows it
+fn der_element(der: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = der.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (length, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let size = (first & 0x7f) as usize;
+        if size > 4 || rest.len() < size {
+            return None;
+        }
+        (rest[..size].iter().fold(0, |length, &byte| length << 8 | byte as usize), &rest[size..])
+    };
+    if rest.len() < length {
+        return None;
+    }
+    Some((tag, &rest[..length], &rest[length..]))
+}
+
+// seconds since the epoch of a UTCTime like 361012113512Z or a GeneralizedTime like 20361012113512Z
+fn parse_time(tag: u8, time: &str) -> Option<u64> {
+    let digits = time.strip_suffix('Z').filter(|digits| digits.bytes().all(|c| c.is_ascii_digit()))?;
+    let digits = match (tag, digits.len()) {
+        // two digit years are from 1950 to 2049
+        (0x17, 12) => format!("{}{}", if digits < "50" { "20" } else { "19" }, digits),
+        (0x18, 14) => digits.to_string(),
+        _ => return None,
+    };
+    pod::parse_timestamp(&format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &digits[..4], &digits[4..6], &digits[6..8], &digits[8..10], &digits[10..12], &digits[12..]
+    ))
+}
+
+// the notAfter of the validity of a DER certificate, in seconds since the epoch
+fn not_after(der: &[u8]) -> Option<u64> {
+    let (_, certificate, _) = der_element(der)?;
+    let (_, mut fields, _) = der_element(certificate)?;
+    // the version is an explicit [0] before the serial number, missing from v1 certificates
+    let (tag, _, rest) = der_element(fields)?;
+    if tag == 0xa0 {
+        fields = rest;
+    }
+    // skip the serial number, the signature algorithm and the issuer
+    for _ in 0..3 {
+        fields = der_element(fields)?.2;
+    }
+    let (_, validity, _) = der_element(fields)?;
+    let (tag, time, _) = der_element(der_element(validity)?.2)?;
+    parse_time(tag, std::str::from_utf8(time).ok()?)
+}
+
+/// When the first of the certificates of a PEM file expires, in seconds since the epoch.
+pub fn expiry(pem: &str) -> Option<u64> {
+    pem_blocks(pem).iter().filter_map(|block| base64_decode(block)).filter_map(|der| not_after(&der)).min()
+}
+
+// a warning about a certificate which expired or expires within `window` seconds
+fn expiry_warning(what: &str, file: &str, expiry: u64, now: u64, window: u64) -> Option<String> {
+    if expiry <= now {
+        Some(format!("{} {} expired {} ago, kubectl cannot connect with it", what, file, pod::human_duration(now - expiry)))
+    } else if expiry - now <= window {
+        Some(format!("{} {} expires in {}", what, file, pod::human_duration(expiry - now)))
+    } else {
+        None
+    }
+}
+
+/// Warnings about the certificates named by the flags of the kubectl command
+/// which expired or expire within `warn_days`. Files which cannot be read are
+/// left to kubectl, and to `rkl config validate`.
+pub fn expiry_warnings(kub: &str, warn_days: u64) -> Vec<String> {
+    let now = pod::now();
+    kub.split_whitespace()
+        .filter_map(|word| CERT_FLAGS.iter().find_map(|(flag, what)| Some((*what, word.strip_prefix(flag)?))))
+        .filter_map(|(what, file)| expiry_warning(what, file, expiry(&fs::read_to_string(file).ok()?)?, now, warn_days * 86400))
+        .collect()
+}
+
+#[test]
+fn test_expiry() {
+    let pem = "-----BEGIN CERTIFICATE-----\n\
+               MIIBezCCASGgAwIBAgIUaFn67MTEQKrp/SzE5TDoHQxEv9wwCgYIKoZIzj0EAwIw\n\
+               EzERMA8GA1UEAwwIcmtsLXRlc3QwHhcNMjYxMDE1MTEzNTEyWhcNMzYxMDEyMTEz\n\
+               NTEyWjATMREwDwYDVQQDDAhya2wtdGVzdDBZMBMGByqGSM49AgEGCCqGSM49AwEH\n\
+               A0IABL7OFT2SfnPVmBtv4Hz5O1ThmtyV3tfKtlMLZVBGK2nh3o00rGG2aX3owlBs\n\
+               a5L3ICflL2FvbbxqPx1ZYqMnjvqjUzBRMB0GA1UdDgQWBBQs5dN0qylHySgK3jwj\n\
+               z3CWgel+vzAfBgNVHSMEGDAWgBQs5dN0qylHySgK3jwjz3CWgel+vzAPBgNVHRMB\n\
+               Af8EBTADAQH/MAoGCCqGSM49BAMCA0gAMEUCIFDNVAZYclOd9pCUsW8zvZHHqWY/\n\
+               Tj3ZMJvsHN6N8EDYAiEAvXOIlvXA9ZanEG43FgKBh6nbALTGgjv2tiAkTkBZMtQ=\n\
+               -----END CERTIFICATE-----\n";
+    let expires = expiry(pem).unwrap();
+    assert_eq!(Some(expires), pod::parse_timestamp("2036-10-12T11:35:12Z"));
+    assert_eq!(parse_time(0x18, "20361012113512Z"), Some(expires));
+    assert_eq!(parse_time(0x17, "991231235959Z"), pod::parse_timestamp("1999-12-31T23:59:59Z"));
+    assert_eq!(expiry_warning("CA", "ca.pem", expires, expires - 40 * 86400, 30 * 86400), None);
+    assert_eq!(expiry_warning("CA", "ca.pem", expires, expires - 5 * 86400, 30 * 86400).as_deref(), Some("CA ca.pem expires in 5d"));
+    assert_eq!(
+        expiry_warning("client certificate", "admin.pem", expires, expires + 3600, 30 * 86400).as_deref(),
+        Some("client certificate admin.pem expired 60m ago, kubectl cannot connect with it")
+    );
+    assert_eq!(expiry("not a certificate"), None);
+}
@@ -0,0 +1,458 @@
+// This module loads the settings of rkl. They are read, from the lowest to
+// the highest precedence, from
+//   1. built-in defaults,
+//   2. the config file, one `key = value` line each, `#` starts a comment,
+//   3. the env RKL_<KEY>, e.g. RKL_NAMESPACE for `namespace`,
+//   4. the command line flags.
+// The first three are merged here, flags are applied by the manager.
+
+use crate::paths;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Keys of the config file, each overridden by the env RKL_<KEY in upper case>.
+pub static CONFIG_KEYS: [&str; 19] = [
+    "namespace", "context", "kubeconfig", "token_file", "kubectl", "kubectl_path", "extra_args", "timeout", "retries",
+    "retry_backoff", "concurrency", "candidate_size", "middle", "sidecars", "cert_warn_days", "redact_keys", "require_reason",
+    "read_only", "check_access",
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    pub namespace: Option<String>,
+    pub context: Option<String>,
+    pub kubeconfig: Option<String>,
+    pub token_file: Option<String>,
+    pub kubectl: Option<String>,
+    /// The kubectl binary run by the default command, when no kubectl command is set.
+    pub kubectl_path: Option<String>,
+    /// Flags added to every kubectl command, like `--request-timeout=10s`.
+    pub extra_args: Option<String>,
+    pub timeout: Option<u64>,
+    pub retries: Option<u32>,
+    /// Milliseconds before the first retry.
+    pub retry_backoff: Option<u64>,
+    pub concurrency: Option<usize>,
+    pub candidate_size: Option<usize>,
+    pub middle: Option<String>,
+    /// Names of the sidecar containers skipped by `logs`, `exec` and `image`.
+    pub sidecars: Option<Vec<String>>,
+    /// Days before the certificates of the kubectl command expire from which rkl warns.
+    pub cert_warn_days: Option<u64>,
+    /// Words of the keys whose values `--redact` masks.
+    pub redact_keys: Option<Vec<String>>,
+    /// Whether deleting pods needs a reason, recorded in the audit log.
+    pub require_reason: Option<bool>,
+    /// Whether commands changing the cluster are refused, like with `--read-only`.
+    pub read_only: Option<bool>,
+    /// Whether `kubectl auth can-i` is asked before running commands, like with `--check-access`.
+    pub check_access: Option<bool>,
+}
+
+/// The env overriding a config key.
+pub fn env_name(key: &str) -> String {
+    format!("RKL_{}", key.to_uppercase())
+}
+
+/// The value of the env overriding a config key.
+pub fn env_value(key: &str) -> Option<String> {
+    env::var(env_name(key)).ok()
+}
+
+/// The content of the config file, empty if it does not exist.
+pub fn read_file() -> String {
+    paths::config_file().and_then(|path| fs::read_to_string(path).ok()).unwrap_or_default()
+}
+
+impl Config {
+    /// The config file merged with the env overrides.
+    pub fn load() -> Config {
+        let mut config = Config::parse(&read_file());
+        config.override_with(env_value);
+        config
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim_matches('"').to_string();
+        match key {
+            "namespace" => self.namespace = Some(value),
+            "context" => self.context = Some(value),
+            "kubeconfig" => self.kubeconfig = Some(value),
+            "token_file" => self.token_file = Some(value),
+            "kubectl" => self.kubectl = Some(value),
+            "kubectl_path" => self.kubectl_path = Some(value),
+            "extra_args" => self.extra_args = Some(value),
+            "middle" => self.middle = Some(value),
+            "sidecars" => self.sidecars = Some(value.split(',').map(str::trim).filter(|name| !name.is_empty()).map(str::to_string).collect()),
+            "redact_keys" => self.redact_keys = Some(value.split(',').map(str::trim).filter(|key| !key.is_empty()).map(str::to_string).collect()),
+            "timeout" => match value.parse() {
+                Ok(timeout) => self.timeout = Some(timeout),
+                Err(_) => return Err(format!("timeout {}, expected seconds", value)),
+            },
+            "retries" => match value.parse() {
+                Ok(retries) => self.retries = Some(retries),
+                Err(_) => return Err(format!("retries {}, expected a number", value)),
+            },
+            "retry_backoff" => match value.parse() {
+                Ok(backoff) => self.retry_backoff = Some(backoff),
+                Err(_) => return Err(format!("retry_backoff {}, expected milliseconds", value)),
+            },
+            "concurrency" => match value.parse() {
+                Ok(concurrency) => self.concurrency = Some(concurrency),
+                Err(_) => return Err(format!("concurrency {}, expected a number", value)),
+            },
+            "candidate_size" => match value.parse() {
+                Ok(size) => self.candidate_size = Some(size),
+                Err(_) => return Err(format!("candidate_size {}, expected a number", value)),
+            },
+            "require_reason" => match value.parse() {
+                Ok(require) => self.require_reason = Some(require),
+                Err(_) => return Err(format!("require_reason {}, expected true or false", value)),
+            },
+            "read_only" => match value.parse() {
+                Ok(read_only) => self.read_only = Some(read_only),
+                Err(_) => return Err(format!("read_only {}, expected true or false", value)),
+            },
+            "check_access" => match value.parse() {
+                Ok(check) => self.check_access = Some(check),
+                Err(_) => return Err(format!("check_access {}, expected true or false", value)),
+            },
+            "cert_warn_days" => match value.parse() {
+                Ok(days) => self.cert_warn_days = Some(days),
+                Err(_) => return Err(format!("cert_warn_days {}, expected days", value)),
+            },
+            _ => return Err(format!("unknown key {}", key)),
+        }
+        Ok(())
+    }
+
+    /// Parse the content of a config file, warning about lines it cannot use.
+    pub fn parse(content: &str) -> Config {
+        let (config, problems) = Config::parse_checked(content);
+        for problem in problems {
+            log::warn!("ignoring {}", problem);
+        }
+        config
+    }
+
+    /// Parse the content of a config file, along with the lines it cannot use.
+    pub fn parse_checked(content: &str) -> (Config, Vec<String>) {
+        let mut config = Config::default();
+        let mut problems = Vec::new();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let result = match line.split_once('=') {
+                Some((key, value)) => config.set(key.trim(), value.trim()),
+                None => Err(format!("{}, expected key = value", line)),
+            };
+            if let Err(problem) = result {
+                problems.push(format!("line {}: {}", number + 1, problem));
+            }
+        }
+        (config, problems)
+    }
+
+    /// Override the settings with the values `lookup` finds for their keys.
+    pub fn override_with(&mut self, lookup: impl Fn(&str) -> Option<String>) {
+        for problem in self.override_checked(lookup) {
+            log::warn!("ignoring {}", problem);
+        }
+    }
+
+    /// Override the settings like `override_with`, returning the values it cannot use.
+    pub fn override_checked(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Vec<String> {
+        let mut problems = Vec::new();
+        for key in CONFIG_KEYS.iter() {
+            if let Some(value) = lookup(key) {
+                if let Err(problem) = self.set(key, value.trim()) {
+                    problems.push(format!("{}: {}", env_name(key), problem));
+                }
+            }
+        }
+        problems
+    }
+
+    // the value of every key, in the order of CONFIG_KEYS
+    fn values(&self) -> Vec<(&'static str, Option<String>)> {
+        vec![
+            ("namespace", self.namespace.clone()),
+            ("context", self.context.clone()),
+            ("kubeconfig", self.kubeconfig.clone()),
+            ("token_file", self.token_file.clone()),
+            ("kubectl", self.kubectl.clone()),
+            ("kubectl_path", self.kubectl_path.clone()),
+            ("extra_args", self.extra_args.clone()),
+            ("timeout", self.timeout.map(|timeout| timeout.to_string())),
+            ("retries", self.retries.map(|retries| retries.to_string())),
+            ("retry_backoff", self.retry_backoff.map(|backoff| backoff.to_string())),
+            ("concurrency", self.concurrency.map(|concurrency| concurrency.to_string())),
+            ("candidate_size", self.candidate_size.map(|size| size.to_string())),
+            ("middle", self.middle.clone()),
+            ("sidecars", self.sidecars.as_ref().map(|sidecars| sidecars.join(","))),
+            ("cert_warn_days", self.cert_warn_days.map(|days| days.to_string())),
+            ("redact_keys", self.redact_keys.as_ref().map(|keys| keys.join(","))),
+            ("require_reason", self.require_reason.map(|require| require.to_string())),
+            ("read_only", self.read_only.map(|read_only| read_only.to_string())),
+            ("check_access", self.check_access.map(|check| check.to_string())),
+        ]
+    }
+
+    /// The settings as the content of a config file.
+    pub fn render(&self) -> String {
+        let mut content = String::from("# settings of rkl, each overridden by the env RKL_<KEY>\n");
+        for (key, value) in self.values() {
+            if let Some(value) = value {
+                content.push_str(&format!("{} = {}\n", key, value));
+            }
+        }
+        content
+    }
+
+    /// Problems with the values themselves: a kubectl command or credential
+    /// plugin which cannot be found, certificates, tokens or kubeconfig files
+    /// which cannot be read, invalid names.
+    pub fn check(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Some(kubectl) = &self.kubectl {
+            let mut words = kubectl.split_whitespace();
+            match words.next() {
+                Some(program) if !program_exists(program) => problems.push(format!("kubectl: {} not found", program)),
+                None => problems.push("kubectl: empty command".to_string()),
+                _ => {}
+            }
+            for word in words {
+                let file = CERT_FLAGS.iter().find_map(|flag| word.strip_prefix(flag));
+                if let Some(file) = file {
+                    if let Err(e) = fs::File::open(file) {
+                        problems.push(format!("kubectl: cannot read {}: {}", file, e));
+                    }
+                }
+            }
+        }
+        if let Some(kubectl_path) = &self.kubectl_path {
+            match kubectl_path.split_whitespace().next() {
+                Some(program) if !program_exists(program) => problems.push(format!("kubectl_path: {} not found", program)),
+                None => problems.push("kubectl_path: empty path".to_string()),
+                _ => {}
+            }
+        }
+        if let Some(kubeconfig) = &self.kubeconfig {
+            // kubectl skips missing files of the list, but a typo is more likely than intent here
+            for file in env::split_paths(kubeconfig).filter(|file| !file.as_os_str().is_empty()) {
+                if let Err(e) = fs::File::open(&file) {
+                    problems.push(format!("kubeconfig: cannot read {}: {}", file.display(), e));
+                }
+            }
+        }
+        for file in self.kubeconfig_files() {
+            let content = fs::read_to_string(&file).unwrap_or_default();
+            for program in exec_commands(&content).into_iter().filter(|program| !program_exists(program)) {
+                problems.push(format!("kubeconfig: credential plugin {} of {} not found", program, file.display()));
+            }
+        }
+        if let Some(token_file) = &self.token_file {
+            if let Err(e) = fs::File::open(token_file) {
+                problems.push(format!("token_file: cannot read {}: {}", token_file, e));
+            }
+        }
+        if let Some(namespace) = &self.namespace {
+            let valid = namespace.len() <= 63
+                && !namespace.starts_with('-')
+                && !namespace.ends_with('-')
+                && namespace.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+            if !valid {
+                problems.push(format!("namespace: {} is not a valid namespace name", namespace));
+            }
+        }
+        if self.timeout == Some(0) {
+            problems.push("timeout: must be at least 1 second".to_string());
+        }
+        if self.concurrency == Some(0) {
+            problems.push("concurrency: must be at least 1".to_string());
+        }
+        if self.candidate_size == Some(0) {
+            problems.push("candidate_size: must be at least 1".to_string());
+        }
+        problems
+    }
+
+    // the kubeconfig files kubectl reads: the ones set, of KUBECONFIG, or ~/.kube/config
+    fn kubeconfig_files(&self) -> Vec<PathBuf> {
+        match self.kubeconfig.clone().or_else(|| env::var("KUBECONFIG").ok()) {
+            Some(kubeconfig) => env::split_paths(&kubeconfig).filter(|file| !file.as_os_str().is_empty()).collect(),
+            None => env::var_os("HOME").map(|home| Path::new(&home).join(".kube/config")).into_iter().collect(),
+        }
+    }
+}
+
+/// Programs run by the `exec` credential plugins of a kubeconfig, like
+/// `aws` or `gke-gcloud-auth-plugin`, found by indentation rather than by
+/// parsing the YAML.
+pub fn exec_commands(kubeconfig: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    // indentation of the `exec:` line whose block is being read
+    let mut exec_indent = None;
+    for line in kubeconfig.lines() {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if exec_indent.is_some_and(|exec_indent| indent <= exec_indent) {
+            exec_indent = None;
+        }
+        if line.trim_start_matches("- ") == "exec:" {
+            exec_indent = Some(indent);
+        } else if let Some(command) = line.strip_prefix("command:").filter(|_| exec_indent.is_some()) {
+            commands.push(command.trim().trim_matches(|c| c == '"' || c == '\'').to_string());
+        }
+    }
+    commands
+}
+
+#[test]
+fn test_exec_commands() {
+    let kubeconfig = "users:\n\
+                      - name: eks\n  \
+                        user:\n    \
+                          exec:\n      \
+                            apiVersion: client.authentication.k8s.io/v1beta1\n      \
+                            command: aws\n      \
+                            args: [\"eks\", \"get-token\"]\n\
+                      - name: admin\n  \
+                        user:\n    \
+                          command: not-a-plugin\n    \
+                          token: abc\n";
+    assert_eq!(exec_commands(kubeconfig), vec!["aws"]);
+}
+
+// flags of kubectl naming files it reads
+static CERT_FLAGS: [&str; 4] = ["--certificate-authority=", "--client-certificate=", "--client-key=", "--kubeconfig="];
+
+/// Commands compatible with kubectl, from the most to the least likely: the
+/// OpenShift CLI and the kubectl bundled with MicroK8s and with k3s.
+pub static FRONTENDS: [&str; 4] = ["kubectl", "oc", "microk8s kubectl", "k3s kubectl"];
+
+/// The first of `FRONTENDS` installed.
+pub fn installed_frontend() -> Option<&'static str> {
+    first_installed(program_exists)
+}
+
+// the first of FRONTENDS whose program `exists` says is installed
+fn first_installed(exists: impl Fn(&str) -> bool) -> Option<&'static str> {
+    FRONTENDS.iter().copied().find(|frontend| frontend.split(' ').next().is_some_and(&exists))
+}
+
+#[test]
+fn test_first_installed() {
+    assert_eq!(first_installed(|program| program == "kubectl" || program == "oc"), Some("kubectl"));
+    assert_eq!(first_installed(|program| program == "k3s"), Some("k3s kubectl"));
+    assert_eq!(first_installed(|_| false), None);
+}
+
+// a path to the program, or a program found in PATH
+fn program_exists(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+    env::var_os("PATH")
+        .map(|path| env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// Every key with its effective value and where the value comes from.
+pub fn show(content: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let from_file = Config::parse(content);
+    let mut effective = from_file.clone();
+    effective.override_with(&lookup);
+    from_file.values().into_iter()
+        .zip(effective.values())
+        .map(|((key, file_value), (_, value))| {
+            let source = if lookup(key).is_some() {
+                format!("env {}", env_name(key))
+            } else if file_value.is_some() {
+                "config file".to_string()
+            } else {
+                "default".to_string()
+            };
+            format!("{} = {}  # {}", key, value.unwrap_or_else(|| "<unset>".to_string()), source)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[test]
+fn test_config() {
+    let mut config = Config::parse(
+        "# defaults of the sophon cluster\n\
+         namespace = sophon\n\
+         middle = \"-sophon\"  # kg2 -> kg-sophon2\n\
+         candidate_size = many\n\
+         sidecars = istio-proxy, envoy\n\
+         colour = blue\n",
+    );
+    assert_eq!(config.namespace.as_deref(), Some("sophon"));
+    assert_eq!(config.middle.as_deref(), Some("-sophon"));
+    assert_eq!(config.candidate_size, None);
+    assert_eq!(config.sidecars, Some(vec!["istio-proxy".to_string(), "envoy".to_string()]));
+    // the env wins over the file
+    config.override_with(|key| match key {
+        "namespace" => Some("default".to_string()),
+        "candidate_size" => Some("10".to_string()),
+        _ => None,
+    });
+    assert_eq!(config.namespace.as_deref(), Some("default"));
+    assert_eq!(config.middle.as_deref(), Some("-sophon"));
+    assert_eq!(config.candidate_size, Some(10));
+    assert_eq!(env_name("candidate_size"), "RKL_CANDIDATE_SIZE");
+    assert_eq!(Config::parse(&config.render()), config);
+}
+
+#[test]
+fn test_check_config() {
+    let (config, problems) = Config::parse_checked(
+        "namespace = Sophon\n\
+         kubectl = sh --client-key=/nonexistent/key.pem\n\
+         kubectl_path = /nonexistent/kubectl\n\
+         kubeconfig = /dev/null:/nonexistent/config\n\
+         colour\n",
+    );
+    assert_eq!(problems, vec!["line 5: colour, expected key = value"]);
+    let problems = config.check();
+    assert_eq!(problems.len(), 4);
+    assert!(problems[0].starts_with("kubectl: cannot read /nonexistent/key.pem"));
+    assert_eq!(problems[1], "kubectl_path: /nonexistent/kubectl not found");
+    assert!(problems[2].starts_with("kubeconfig: cannot read /nonexistent/config"));
+    assert_eq!(problems[3], "namespace: Sophon is not a valid namespace name");
+}
+
+#[test]
+fn test_show_config() {
+    let lookup = |key: &str| if key == "namespace" { Some("default".to_string()) } else { None };
+    assert_eq!(
+        show("namespace = sophon\nmiddle = -sophon\n", lookup),
+        "namespace = default  # env RKL_NAMESPACE\n\
+         context = <unset>  # default\n\
+         kubeconfig = <unset>  # default\n\
+         token_file = <unset>  # default\n\
+         kubectl = <unset>  # default\n\
+         kubectl_path = <unset>  # default\n\
+         extra_args = <unset>  # default\n\
+         timeout = <unset>  # default\n\
+         retries = <unset>  # default\n\
+         retry_backoff = <unset>  # default\n\
+         concurrency = <unset>  # default\n\
+         candidate_size = <unset>  # default\n\
+         middle = -sophon  # config file\n\
+         sidecars = <unset>  # default\n\
+         cert_warn_days = <unset>  # default\n\
+         redact_keys = <unset>  # default\n\
+         require_reason = <unset>  # default\n\
+         read_only = <unset>  # default\n\
+         check_access = <unset>  # default"
+    );
+}
@@ -0,0 +1,473 @@
+// This module deals with the containers of pods. Instead of grepping
+// the output of `kubectl describe`, we ask kubectl for a jsonpath template
+// which prints one tab separated line per container and parse it here.
+
+use anyhow::{bail, Result};
+use itertools::Itertools;
+use crate::table::{format_table, header};
+use std::collections::HashMap;
+use std::fmt;
+
+/// jsonpath fields printed for every container status, in this order:
+/// name, image, ready, restartCount, waiting reason, terminated reason,
+/// last terminated reason and container id.
+static CONTAINER_FIELDS: &str = r#"{.name}{"\t"}{.image}{"\t"}{.ready}{"\t"}{.restartCount}{"\t"}{.state.waiting.reason}{"\t"}{.state.terminated.reason}{"\t"}{.lastState.terminated.reason}{"\t"}{.containerID}{"\n"}"#;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContainerKind {
+    Init,
+    App,
+}
+
+impl fmt::Display for ContainerKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContainerKind::Init => write!(f, "init"),
+            ContainerKind::App => write!(f, "app"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerInfo {
+    pub kind: ContainerKind,
+    pub name: String,
+    pub image: String,
+    pub ready: bool,
+    pub restarts: u32,
+    pub state: String,
+    pub last_reason: String,
+    pub id: String,
+}
+
+/// Build the `-o jsonpath=...` argument listing init containers first,
+/// followed by app containers. Every line starts with the container kind.
+pub fn container_jsonpath() -> String {
+    format!(
+        "-o jsonpath='{{range .status.initContainerStatuses[*]}}{}{{\"\\t\"}}{}{{end}}{{range .status.containerStatuses[*]}}{}{{\"\\t\"}}{}{{end}}'",
+        ContainerKind::Init, CONTAINER_FIELDS, ContainerKind::App, CONTAINER_FIELDS
+    )
+}
+
+/// Build the `-o jsonpath=...` argument printing one line per pod: the pod
+/// name, a tab, then `container:restarts:last reason` entries separated by commas.
+/// Used with several pod names, so kubectl answers with a list.
+pub fn restarts_jsonpath() -> String {
+    r#"-o jsonpath='{range .items[*]}{.metadata.name}{"\t"}{range .status.containerStatuses[*]}{.name}{":"}{.restartCount}{":"}{.lastState.terminated.reason}{","}{end}{"\n"}{end}'"#.to_string()
+}
+
+/// Restart count and last termination reason of a container, shown next to
+/// candidate pods so the broken replica stands out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerRestarts {
+    pub name: String,
+    pub restarts: u32,
+    pub last_reason: String,
+}
+
+impl fmt::Display for ContainerRestarts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.last_reason.is_empty() {
+            write!(f, "{}({} restarts)", self.name, self.restarts)
+        } else {
+            write!(f, "{}({} restarts, last {})", self.name, self.restarts, self.last_reason)
+        }
+    }
+}
+
+/// Parse the output of the jsonpath template built by `restarts_jsonpath`,
+/// keyed by pod name. Malformed entries are skipped, this is only a hint.
+pub fn parse_restarts(output: &str) -> HashMap<String, Vec<ContainerRestarts>> {
+    let mut restarts = HashMap::new();
+    for line in output.lines() {
+        let mut parts = line.trim().splitn(2, '\t');
+        let pod_name = match parts.next() {
+            Some(pod_name) if !pod_name.is_empty() => pod_name,
+            _ => continue,
+        };
+        let containers = parts
+            .next()
+            .unwrap_or("")
+            .split(',')
+            .filter_map(|entry| {
+                let fields: Vec<&str> = entry.split(':').collect();
+                if fields.len() != 3 {
+                    return None;
+                }
+                Some(ContainerRestarts {
+                    name: fields[0].to_string(),
+                    restarts: fields[1].parse().ok()?,
+                    last_reason: fields[2].to_string(),
+                })
+            })
+            .collect();
+        restarts.insert(pod_name.to_string(), containers);
+    }
+    restarts
+}
+
+/// Printed between the resources spec and the `kubectl top` output.
+pub static USAGE_SEPARATOR: &str = "---";
+
+/// Build the `-o jsonpath=...` argument printing one line per app container:
+/// name, cpu request, memory request, cpu limit and memory limit.
+pub fn resources_jsonpath() -> String {
+    r#"-o jsonpath='{range .spec.containers[*]}{.name}{"\t"}{.resources.requests.cpu}{"\t"}{.resources.requests.memory}{"\t"}{.resources.limits.cpu}{"\t"}{.resources.limits.memory}{"\n"}{end}'"#.to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ContainerResources {
+    pub name: String,
+    pub cpu_request: String,
+    pub memory_request: String,
+    pub cpu_limit: String,
+    pub memory_limit: String,
+    pub cpu_usage: String,
+    pub memory_usage: String,
+}
+
+impl ContainerResources {
+    /// A container without cpu or memory limit can starve its neighbours
+    pub fn missing_limits(&self) -> bool {
+        self.cpu_limit.is_empty() || self.memory_limit.is_empty()
+    }
+}
+
+/// Parse the resources spec followed by `USAGE_SEPARATOR` and the output of
+/// `kubectl top pod --containers --no-headers`. Usage stays empty when
+/// metrics are not available.
+pub fn parse_resources(output: &str) -> Vec<ContainerResources> {
+    let mut lines = output.lines();
+    let mut resources: Vec<ContainerResources> = lines
+        .by_ref()
+        .take_while(|line| line.trim() != USAGE_SEPARATOR)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 5 || fields[0].is_empty() {
+                return None;
+            }
+            Some(ContainerResources {
+                name: fields[0].to_string(),
+                cpu_request: fields[1].to_string(),
+                memory_request: fields[2].to_string(),
+                cpu_limit: fields[3].to_string(),
+                memory_limit: fields[4].to_string(),
+                ..Default::default()
+            })
+        })
+        .collect();
+    for line in lines {
+        // POD NAME CPU(cores) MEMORY(bytes)
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 4 {
+            continue;
+        }
+        if let Some(r) = resources.iter_mut().find(|r| r.name == fields[1]) {
+            r.cpu_usage = fields[2].to_string();
+            r.memory_usage = fields[3].to_string();
+        }
+    }
+    resources
+}
+
+/// Render resources as an aligned table, flagging containers without limits.
+/// Usage columns are left out when metrics are not available.
+pub fn format_resources(resources: &[ContainerResources], with_usage: bool) -> String {
+    let or_none = |s: &String| if s.is_empty() { "<none>".to_string() } else { s.clone() };
+    let mut rows = vec![header(&["NAME", "CPU REQ", "CPU LIM", "CPU USED", "MEM REQ", "MEM LIM", "MEM USED", ""])];
+    for r in resources {
+        rows.push(vec![
+            r.name.clone(),
+            or_none(&r.cpu_request),
+            or_none(&r.cpu_limit),
+            or_none(&r.cpu_usage),
+            or_none(&r.memory_request),
+            or_none(&r.memory_limit),
+            or_none(&r.memory_usage),
+            if r.missing_limits() { "! no limits".to_string() } else { String::new() },
+        ]);
+    }
+    if !with_usage {
+        for row in rows.iter_mut() {
+            row.remove(6);
+            row.remove(3);
+        }
+    }
+    format_table(&rows)
+}
+
+/// Build the `-o jsonpath=...` argument printing one line per pod: the pod
+/// name, then `container;last terminated reason;finished at` entries and
+/// `container;memory limit` entries, both separated by commas.
+pub fn oom_jsonpath() -> String {
+    r#"-o jsonpath='{range .items[*]}{.metadata.name}{"\t"}{range .status.containerStatuses[*]}{.name}{";"}{.lastState.terminated.reason}{.state.terminated.reason}{";"}{.lastState.terminated.finishedAt}{.state.terminated.finishedAt}{","}{end}{"\t"}{range .spec.containers[*]}{.name}{";"}{.resources.limits.memory}{","}{end}{"\n"}{end}'"#.to_string()
+}
+
+/// A container which was killed for running out of memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OomKilled {
+    pub pod: String,
+    pub container: String,
+    pub finished_at: String,
+    pub memory_limit: String,
+}
+
+/// Parse the output of the jsonpath template built by `oom_jsonpath`,
+/// most recently killed first.
+pub fn parse_oom_killed(output: &str) -> Vec<OomKilled> {
+    let mut killed = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.trim().split('\t').collect();
+        if fields.len() != 3 {
+            continue;
+        }
+        let limits: HashMap<&str, &str> = fields[2]
+            .split(',')
+            .filter_map(|entry| entry.splitn(2, ';').collect_tuple())
+            .collect();
+        for entry in fields[1].split(',') {
+            let (container, reason, finished_at) = match entry.splitn(3, ';').collect_tuple() {
+                Some(entry) => entry,
+                None => continue,
+            };
+            // the reason of the current and of the last state may be concatenated
+            if reason.contains("OOMKilled") {
+                killed.push(OomKilled {
+                    pod: fields[0].to_string(),
+                    container: container.to_string(),
+                    finished_at: finished_at.to_string(),
+                    memory_limit: limits.get(container).unwrap_or(&"").to_string(),
+                });
+            }
+        }
+    }
+    killed.sort_by(|a, b| b.finished_at.cmp(&a.finished_at));
+    killed
+}
+
+/// Render OOMKilled containers as an aligned table.
+pub fn format_oom_killed(killed: &[OomKilled]) -> String {
+    let mut rows = vec![header(&["POD", "CONTAINER", "KILLED AT", "MEM LIM"])];
+    for k in killed {
+        rows.push(vec![
+            k.pod.clone(),
+            k.container.clone(),
+            k.finished_at.clone(),
+            if k.memory_limit.is_empty() { "<none>".to_string() } else { k.memory_limit.clone() },
+        ]);
+    }
+    format_table(&rows)
+}
+
+/// Build the `-o jsonpath=...` argument printing one line per pod: the pod
+/// name, a tab, then the images of its app containers separated by spaces.
+pub fn images_jsonpath() -> String {
+    r#"-o jsonpath='{range .items[*]}{.metadata.name}{"\t"}{range .spec.containers[*]}{.image}{" "}{end}{"\n"}{end}'"#.to_string()
+}
+
+/// Count the pods using every image among pods whose name contains the slice,
+/// parsed from the output of `images_jsonpath`. Most used images come first.
+pub fn count_images(output: &str, pod_name_slice: &str) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for line in output.lines() {
+        let (pod_name, images) = match line.trim().splitn(2, '\t').collect_tuple() {
+            Some(fields) => fields,
+            None => continue,
+        };
+        if !pod_name.contains(pod_name_slice) {
+            continue;
+        }
+        // a pod running the same image in two containers is counted once
+        for image in images.split_whitespace().unique() {
+            *counts.entry(image.to_string()).or_insert(0) += 1;
+        }
+    }
+    counts.into_iter().sorted_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0))).collect()
+}
+
+/// Containers injected next to the app by service meshes and secret stores,
+/// skipped by `logs`, `exec` and `image` unless sidecars are included.
+pub static DEFAULT_SIDECARS: [&str; 3] = ["istio-proxy", "linkerd-proxy", "vault-agent"];
+
+/// The containers of a pod which are not sidecars, or all of them if every
+/// one is, in the order of the pod spec.
+pub fn app_containers<'a>(containers: &'a [String], sidecars: &[String]) -> Vec<&'a String> {
+    let apps: Vec<&String> = containers.iter().filter(|container| !sidecars.contains(container)).collect();
+    if apps.is_empty() { containers.iter().collect() } else { apps }
+}
+
+#[test]
+fn test_app_containers() {
+    let sidecars: Vec<String> = DEFAULT_SIDECARS.iter().map(|sidecar| sidecar.to_string()).collect();
+    let containers = vec!["istio-proxy".to_string(), "kg".to_string(), "vault-agent".to_string()];
+    assert_eq!(app_containers(&containers, &sidecars), vec!["kg"]);
+    assert_eq!(app_containers(&containers[..1], &sidecars), vec!["istio-proxy"]);
+    assert_eq!(app_containers(&containers, &[]).len(), 3);
+}
+
+/// Build the `-o jsonpath=...` argument printing an `Image:` line for each
+/// of the named containers of a pod, like `describe` does for all of them.
+pub fn images_of_jsonpath(containers: &[&String]) -> String {
+    let ranges: String = containers.iter()
+        .map(|container| format!(r#"{{range .spec.containers[?(@.name=="{}")]}}{{"Image:\t"}}{{.image}}{{"\n"}}{{end}}"#, container))
+        .collect();
+    format!("-o jsonpath='{}'", ranges)
+}
+
+/// Parse the output of the jsonpath template built by `container_jsonpath`.
+pub fn parse_containers(output: &str) -> Result<Vec<ContainerInfo>> {
+    let mut containers = Vec::new();
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 9 {
+            bail!("unexpected container line: {}", line);
+        }
+        let kind = match fields[0] {
+            "init" => ContainerKind::Init,
+            "app" => ContainerKind::App,
+            other => bail!("unknown container kind {} in line: {}", other, line),
+        };
+        // a container is running unless it is waiting or terminated
+        let state = if !fields[5].is_empty() {
+            fields[5].to_string()
+        } else if !fields[6].is_empty() {
+            format!("Terminated: {}", fields[6])
+        } else {
+            "Running".to_string()
+        };
+        containers.push(ContainerInfo {
+            kind,
+            name: fields[1].to_string(),
+            image: fields[2].to_string(),
+            ready: fields[3] == "true",
+            restarts: fields[4].parse().unwrap_or(0),
+            state,
+            last_reason: fields[7].to_string(),
+            id: fields[8].to_string(),
+        });
+    }
+    Ok(containers)
+}
+
+/// Render containers as an aligned table with a header line.
+pub fn format_containers(containers: &[ContainerInfo]) -> String {
+    let mut rows = vec![header(&["KIND", "NAME", "IMAGE", "READY", "RESTARTS", "STATE", "LAST REASON", "CONTAINER ID"])];
+    for c in containers {
+        rows.push(vec![
+            c.kind.to_string(),
+            c.name.clone(),
+            c.image.clone(),
+            c.ready.to_string(),
+            c.restarts.to_string(),
+            c.state.clone(),
+            if c.last_reason.is_empty() { "<none>".to_string() } else { c.last_reason.clone() },
+            c.id.clone(),
+        ]);
+    }
+    format_table(&rows)
+}
+
+#[test]
+fn test_parse_containers() {
+    let output = "init\tinit-db\tbusybox:1.32\tfalse\t0\t\tCompleted\t\tdocker://0a1b\n\
+                  app\tkg\ttranswarp/sophon-kg:sophon-3.0\ttrue\t4\t\t\tOOMKilled\tdocker://2c3d\n\
+                  app\tsidecar\tnginx:1.19\tfalse\t12\tCrashLoopBackOff\t\tError\tdocker://4e5f\n";
+    let containers = parse_containers(output).unwrap();
+    assert_eq!(containers.len(), 3);
+    assert_eq!(containers[0].kind, ContainerKind::Init);
+    assert_eq!(containers[0].state, "Terminated: Completed");
+    assert_eq!(containers[1].kind, ContainerKind::App);
+    assert_eq!(containers[1].restarts, 4);
+    assert!(containers[1].ready);
+    assert_eq!(containers[1].state, "Running");
+    assert_eq!(containers[1].last_reason, "OOMKilled");
+    assert_eq!(containers[2].state, "CrashLoopBackOff");
+    assert_eq!(containers[2].id, "docker://4e5f");
+    assert!(parse_containers("app\tbroken\n").is_err());
+    assert!(parse_containers("").unwrap().is_empty());
+}
+
+#[test]
+fn test_parse_restarts() {
+    let output = "sophon-kg-sophon2-bf9769d97-4hqgv\tkg:4:OOMKilled,sidecar:0:,\n\
+                  sophon-kg-sophon2-bf9769d97-fgpnn\tkg:0:,\n";
+    let restarts = parse_restarts(output);
+    assert_eq!(restarts.len(), 2);
+    let broken = &restarts["sophon-kg-sophon2-bf9769d97-4hqgv"];
+    assert_eq!(broken.len(), 2);
+    assert_eq!(broken[0].to_string(), "kg(4 restarts, last OOMKilled)");
+    assert_eq!(broken[1].to_string(), "sidecar(0 restarts)");
+    assert_eq!(restarts["sophon-kg-sophon2-bf9769d97-fgpnn"][0].restarts, 0);
+    assert!(parse_restarts("").is_empty());
+}
+
+#[test]
+fn test_parse_resources() {
+    let output = "kg\t500m\t1Gi\t2\t4Gi\n\
+                  sidecar\t100m\t\t\t\n\
+                  ---\n\
+                  sophon-kg-sophon2-bf9769d97-4hqgv   kg        12m   1800Mi\n\
+                  sophon-kg-sophon2-bf9769d97-4hqgv   sidecar   1m    20Mi\n";
+    let resources = parse_resources(output);
+    assert_eq!(resources.len(), 2);
+    assert_eq!(resources[0].cpu_limit, "2");
+    assert_eq!(resources[0].memory_usage, "1800Mi");
+    assert!(!resources[0].missing_limits());
+    assert_eq!(resources[1].cpu_usage, "1m");
+    assert!(resources[1].missing_limits());
+    // metrics-server absent, usage is left empty
+    let resources = parse_resources("kg\t500m\t1Gi\t2\t4Gi\n---\n");
+    assert_eq!(resources[0].cpu_usage, "");
+    assert_eq!(
+        format_resources(&resources, true),
+        "NAME  CPU REQ  CPU LIM  CPU USED  MEM REQ  MEM LIM  MEM USED\n\
+         kg    500m     2        <none>    1Gi      4Gi      <none>"
+    );
+    assert_eq!(
+        format_resources(&resources, false),
+        "NAME  CPU REQ  CPU LIM  MEM REQ  MEM LIM\n\
+         kg    500m     2        1Gi      4Gi"
+    );
+}
+
+#[test]
+fn test_parse_oom_killed() {
+    let output = "sophon-kg-sophon2-bf9769d97-4hqgv\tkg;OOMKilled;2021-03-01T10:00:00Z,sidecar;;,\tkg;4Gi,sidecar;,\n\
+                  sophon-ui-sophon2-79c997dd8c-vkths\tui;Error;2021-03-01T12:00:00Z,\tui;512Mi,\n\
+                  sophon-kg-sophon2-bf9769d97-fgpnn\tkg;OOMKilled;2021-03-01T11:00:00Z,\tkg;,\n";
+    let killed = parse_oom_killed(output);
+    assert_eq!(killed.len(), 2);
+    assert_eq!(killed[0].pod, "sophon-kg-sophon2-bf9769d97-fgpnn");
+    assert_eq!(killed[0].memory_limit, "");
+    assert_eq!(killed[1].container, "kg");
+    assert_eq!(killed[1].memory_limit, "4Gi");
+    assert_eq!(
+        format_oom_killed(&killed[1..]),
+        "POD                                CONTAINER  KILLED AT             MEM LIM\n\
+         sophon-kg-sophon2-bf9769d97-4hqgv  kg         2021-03-01T10:00:00Z  4Gi"
+    );
+}
+
+#[test]
+fn test_count_images() {
+    let output = "sophon-kg-sophon2-bf9769d97-4hqgv\ttranswarp/sophon-kg:sophon-3.0 nginx:1.19 \n\
+                  sophon-kg-sophon2-bf9769d97-fgpnn\ttranswarp/sophon-kg:sophon-2.9 nginx:1.19 nginx:1.19 \n\
+                  sophon-ui-sophon2-79c997dd8c-vkths\ttranswarp/sophon-ui:sophon-3.0 nginx:1.19 \n";
+    assert_eq!(count_images(output, "kg"), vec![
+        ("nginx:1.19".to_string(), 2),
+        ("transwarp/sophon-kg:sophon-2.9".to_string(), 1),
+        ("transwarp/sophon-kg:sophon-3.0".to_string(), 1),
+    ]);
+    assert_eq!(count_images(output, "")[0], ("nginx:1.19".to_string(), 3));
+    assert!(count_images(output, "notebook").is_empty());
+}
+
+#[test]
+fn test_format_containers() {
+    let containers = parse_containers("app\tkg\tkg:3.0\ttrue\t0\t\t\t\tdocker://2c3d\n").unwrap();
+    assert_eq!(
+        format_containers(&containers),
+        "KIND  NAME  IMAGE   READY  RESTARTS  STATE    LAST REASON  CONTAINER ID\n\
+         app   kg    kg:3.0  true   0         Running  <none>       docker://2c3d"
+    );
+}
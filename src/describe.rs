@@ -0,0 +1,53 @@
+// This module picks sections out of `kubectl describe` output, which can
+// easily run to hundreds of lines. A section starts with an unindented
+// `Header:` line and spans every indented line below it.
+
+/// Keep only the sections whose header is in `headers`, in output order.
+pub fn extract_sections(output: &str, headers: &[&str]) -> String {
+    let mut kept = Vec::new();
+    let mut in_section = false;
+    for line in output.lines() {
+        if !line.is_empty() && !line.starts_with(char::is_whitespace) {
+            in_section = match line.split(':').next() {
+                Some(header) => headers.contains(&header),
+                None => false,
+            };
+        }
+        if in_section {
+            kept.push(line);
+        }
+    }
+    kept.join("\n")
+}
+
+#[test]
+fn test_extract_sections() {
+    let output = "Name:         sophon-kg-sophon2-bf9769d97-4hqgv
+Namespace:    default
+Init Containers:
+  init-db:
+    Image:      busybox:1.32
+Containers:
+  kg:
+    Image:      transwarp/sophon-kg:sophon-3.0
+Conditions:
+  Type              Status
+  Ready             True
+Volumes:
+  data:
+    Type:       EmptyDir (a temporary directory that shares a pod's lifetime)
+Events:
+  Type     Reason   Age  From     Message
+  ----     ------   ---  ----     -------
+  Warning  BackOff  1m   kubelet  Back-off restarting failed container";
+    assert_eq!(
+        extract_sections(output, &["Conditions"]),
+        "Conditions:\n  Type              Status\n  Ready             True"
+    );
+    assert_eq!(
+        extract_sections(output, &["Init Containers", "Containers"]),
+        "Init Containers:\n  init-db:\n    Image:      busybox:1.32\nContainers:\n  kg:\n    Image:      transwarp/sophon-kg:sophon-3.0"
+    );
+    assert!(extract_sections(output, &["Events"]).ends_with("Back-off restarting failed container"));
+    assert_eq!(extract_sections(output, &["Tolerations"]), "");
+}
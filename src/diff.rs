@@ -0,0 +1,148 @@
+// This module compares the specs of two pods, e.g. two replicas of which one
+// behaves differently, or the spec of a pod with the pod template of its
+// workload, to find what admission webhooks or manual edits changed. The spec
+// is taken from `kubectl get -o yaml`, the metadata and status around it
+// differing between any two pods anyway, and the two specs are printed as a
+// unified diff like `diff -u` does.
+
+use crate::table::paint;
+
+// unchanged lines shown around each change
+static CONTEXT: usize = 3;
+
+/// The `spec:` section of the yaml of a pod.
+pub fn spec_section(yaml: &str) -> String {
+    let mut kept = Vec::new();
+    let mut in_spec = false;
+    for line in yaml.lines() {
+        if !line.is_empty() && !line.starts_with(char::is_whitespace) {
+            in_spec = line == "spec:";
+        }
+        if in_spec {
+            kept.push(line);
+        }
+    }
+    kept.join("\n")
+}
+
+/// The spec of the pod template in the yaml of a workload, indented like the
+/// spec of a pod.
+pub fn template_spec_section(yaml: &str) -> String {
+    let mut kept = vec!["spec:".to_string()];
+    let (mut in_spec, mut in_template, mut in_template_spec) = (false, false, false);
+    for line in yaml.lines() {
+        let indent = line.len() - line.trim_start().len();
+        if !line.trim().is_empty() && indent == 0 {
+            in_spec = line == "spec:";
+        }
+        if in_spec && indent == 2 && !line.trim().is_empty() {
+            in_template = line == "  template:";
+        }
+        if in_template && indent == 4 && !line.trim().is_empty() {
+            in_template_spec = line == "    spec:";
+            continue;
+        }
+        if in_template_spec && indent > 4 {
+            kept.push(line[4..].to_string());
+        }
+    }
+    kept.join("\n")
+}
+
+#[test]
+fn test_template_spec_section() {
+    let yaml = "kind: Deployment\nspec:\n  replicas: 2\n  template:\n    metadata:\n      labels:\n        app: kg\n    spec:\n      containers:\n      - image: kg:3.0\n        name: kg\n  strategy: {}\nstatus:\n  replicas: 2\n";
+    assert_eq!(template_spec_section(yaml), "spec:\n  containers:\n  - image: kg:3.0\n    name: kg");
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+// the edits turning a into b, keeping their longest common subsequence
+fn edits<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<Edit<'a>> {
+    // common[i][j] is the length of the longest common subsequence of a[i..] and b[j..]
+    let mut common = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i][j] = if a[i] == b[j] { common[i + 1][j + 1] + 1 } else { common[i + 1][j].max(common[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut edits = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            edits.push(Edit::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && common[i + 1][j] >= common[i][j + 1]) {
+            edits.push(Edit::Removed(a[i]));
+            i += 1;
+        } else {
+            edits.push(Edit::Added(b[j]));
+            j += 1;
+        }
+    }
+    edits
+}
+
+/// A unified diff from `a` to `b`, empty when they are the same.
+pub fn unified(a_label: &str, b_label: &str, a: &str, b: &str, color: bool) -> String {
+    let (a_lines, b_lines): (Vec<&str>, Vec<&str>) = (a.lines().collect(), b.lines().collect());
+    let edits = edits(&a_lines, &b_lines);
+    let changed: Vec<usize> = (0..edits.len()).filter(|&i| !matches!(edits[i], Edit::Same(_))).collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+    // ranges of edits shown, changes closer than twice the context share one hunk
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let (start, end) = (i.saturating_sub(CONTEXT), (i + CONTEXT + 1).min(edits.len()));
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    let paint_if = |line: String, code: u8| if color { paint(&line, code) } else { line };
+    let mut lines = vec![paint_if(format!("--- {}", a_label), 1), paint_if(format!("+++ {}", b_label), 1)];
+    for (start, end) in hunks {
+        // line numbers in a and b where the hunk starts
+        let a_start = edits[..start].iter().filter(|edit| !matches!(edit, Edit::Added(_))).count();
+        let b_start = edits[..start].iter().filter(|edit| !matches!(edit, Edit::Removed(_))).count();
+        let a_count = edits[start..end].iter().filter(|edit| !matches!(edit, Edit::Added(_))).count();
+        let b_count = edits[start..end].iter().filter(|edit| !matches!(edit, Edit::Removed(_))).count();
+        lines.push(paint_if(format!("@@ -{},{} +{},{} @@", a_start + 1, a_count, b_start + 1, b_count), 36));
+        for edit in &edits[start..end] {
+            lines.push(match edit {
+                Edit::Same(line) => format!(" {}", line),
+                Edit::Removed(line) => paint_if(format!("-{}", line), 31),
+                Edit::Added(line) => paint_if(format!("+{}", line), 32),
+            });
+        }
+    }
+    lines.join("\n")
+}
+
+#[test]
+fn test_unified() {
+    let yaml = |image: &str, node: &str| format!(
+        "apiVersion: v1\nkind: Pod\nspec:\n  containers:\n  - env:\n    - name: MODE\n      value: fast\n    image: {}\n    name: kg\n  nodeName: {}\n  restartPolicy: Always\nstatus:\n  phase: Running\n",
+        image, node
+    );
+    let (a, b) = (spec_section(&yaml("kg:3.0", "kg-node43")), spec_section(&yaml("kg:3.1", "kg-node44")));
+    assert!(a.starts_with("spec:") && a.ends_with("restartPolicy: Always"));
+    assert_eq!(unified("kg-1", "kg-2", &a, &a, false), "");
+    assert_eq!(unified("kg-1", "kg-2", &a, &b, false), "--- kg-1\n+++ kg-2\n@@ -3,7 +3,7 @@
+   - env:
+     - name: MODE
+       value: fast
+-    image: kg:3.0
++    image: kg:3.1
+     name: kg
+-  nodeName: kg-node43
++  nodeName: kg-node44
+   restartPolicy: Always");
+}
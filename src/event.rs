@@ -0,0 +1,87 @@
+// This module reads warning events, which tell apart the unhealthy replica
+// among several pods matching the same name.
+
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub pod: String,
+    pub timestamp: String,
+    pub reason: String,
+    pub message: String,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}: {}", self.timestamp, self.reason, self.message)
+    }
+}
+
+// one tab separated line per event: pod name, last timestamp, event time, reason and message
+static EVENT_JSONPATH: &str = r#"-o jsonpath='{range .items[*]}{.involvedObject.name}{"\t"}{.lastTimestamp}{"\t"}{.eventTime}{"\t"}{.reason}{"\t"}{.message}{"\n"}{end}'"#;
+
+/// Arguments to `kubectl get events` printing the warnings of every pod.
+pub fn warning_events_args() -> String {
+    format!("--field-selector type=Warning,involvedObject.kind=Pod {}", EVENT_JSONPATH)
+}
+
+/// Arguments to `kubectl get events` printing every event of a pod.
+pub fn pod_events_args(pod_name: &str) -> String {
+    format!("--field-selector involvedObject.name={} {}", pod_name, EVENT_JSONPATH)
+}
+
+/// Parse events printed with the arguments above, newest first.
+/// Timestamps are RFC 3339 in UTC, so comparing them as strings orders them in time.
+pub fn parse_events(output: &str) -> Vec<Event> {
+    let mut events: Vec<Event> = output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(5, '\t').collect();
+            if fields.len() != 5 || fields[0].is_empty() {
+                return None;
+            }
+            // events recorded by newer components only fill in eventTime
+            let timestamp = if fields[1].is_empty() { fields[2] } else { fields[1] };
+            Some(Event {
+                pod: fields[0].to_string(),
+                timestamp: timestamp.to_string(),
+                reason: fields[3].to_string(),
+                message: fields[4].trim().to_string(),
+            })
+        })
+        .collect();
+    events.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    events
+}
+
+/// Keep the newest event of every pod.
+pub fn newest_by_pod(output: &str) -> HashMap<String, Event> {
+    let mut events: HashMap<String, Event> = HashMap::new();
+    for event in parse_events(output) {
+        match events.get(&event.pod) {
+            Some(newest) if newest.timestamp >= event.timestamp => {}
+            _ => {
+                events.insert(event.pod.clone(), event);
+            }
+        }
+    }
+    events
+}
+
+#[test]
+fn test_newest_by_pod() {
+    let output = "sophon-kg-sophon2-bf9769d97-4hqgv\t2021-03-01T10:00:00Z\t\tBackOff\tBack-off restarting failed container\n\
+                  sophon-kg-sophon2-bf9769d97-4hqgv\t2021-03-01T11:00:00Z\t\tUnhealthy\tReadiness probe failed: connection refused\n\
+                  sophon-kg-sophon2-bf9769d97-4hqgv\t2021-03-01T09:00:00Z\t\tFailed\tError: ImagePullBackOff\n\
+                  sophon-ui-sophon2-79c997dd8c-vkths\t\t2021-03-01T08:00:00.000000Z\tFailedMount\tMountVolume.SetUp failed\n\
+                  broken line\n";
+    let events = newest_by_pod(output);
+    assert_eq!(events.len(), 2);
+    let kg = &events["sophon-kg-sophon2-bf9769d97-4hqgv"];
+    assert_eq!(kg.reason, "Unhealthy");
+    assert_eq!(kg.to_string(), "2021-03-01T11:00:00Z Unhealthy: Readiness probe failed: connection refused");
+    assert_eq!(events["sophon-ui-sophon2-79c997dd8c-vkths"].timestamp, "2021-03-01T08:00:00.000000Z");
+    let reasons: Vec<String> = parse_events(output).into_iter().map(|e| e.reason).collect();
+    assert_eq!(reasons, vec!["Unhealthy", "BackOff", "Failed", "FailedMount"]);
+}
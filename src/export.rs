@@ -0,0 +1,91 @@
+// This module strips the fields the cluster fills in from the yaml of a pod,
+// printed by `kubectl get po -o yaml`, so the rest can be applied to another
+// cluster. Fields are dropped line by line: a field spans the lines below it
+// which are indented deeper, or start a list item at its own indentation.
+
+// fields of the metadata set by the apiserver, or tied to objects of this cluster
+static METADATA_FIELDS: [&str; 7] = ["uid", "resourceVersion", "creationTimestamp", "managedFields", "selfLink", "generation", "ownerReferences"];
+
+// fields of the spec set by the scheduler, naming a node of this cluster
+static SPEC_FIELDS: [&str; 1] = ["nodeName"];
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+// the key of a `key:` or `key: value` line
+fn key(line: &str) -> &str {
+    line.trim_start().split(':').next().unwrap_or("")
+}
+
+/// The yaml of a pod without its status and the fields the cluster filled in.
+pub fn sanitize(yaml: &str) -> String {
+    let mut kept = Vec::new();
+    let mut section = "";
+    // the indentation of the field being dropped
+    let mut dropping: Option<usize> = None;
+    for line in yaml.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let indent = indent(line);
+        if let Some(dropped_indent) = dropping {
+            if indent > dropped_indent || (indent == dropped_indent && line.trim_start().starts_with("- ")) {
+                continue;
+            }
+            dropping = None;
+        }
+        if indent == 0 {
+            section = key(line);
+        }
+        let dropped = match (indent, section) {
+            (0, "status") => true,
+            (2, "metadata") => METADATA_FIELDS.contains(&key(line)),
+            (2, "spec") => SPEC_FIELDS.contains(&key(line)),
+            _ => false,
+        };
+        if dropped {
+            dropping = Some(indent);
+            continue;
+        }
+        kept.push(line);
+    }
+    kept.join("\n")
+}
+
+#[test]
+fn test_sanitize() {
+    let yaml = "apiVersion: v1
+kind: Pod
+metadata:
+  creationTimestamp: \"2021-03-01T10:00:00Z\"
+  labels:
+    app: kg
+  managedFields:
+  - apiVersion: v1
+    fieldsType: FieldsV1
+  name: sophon-kg-sophon2-bf9769d97-4hqgv
+  ownerReferences:
+  - kind: ReplicaSet
+    uid: 5b1c
+  resourceVersion: \"123\"
+  uid: 9f2a
+spec:
+  containers:
+  - image: kg:3.0
+    name: kg
+  nodeName: kg-node43
+status:
+  phase: Running
+";
+    assert_eq!(sanitize(yaml), "apiVersion: v1
+kind: Pod
+metadata:
+  labels:
+    app: kg
+  name: sophon-kg-sophon2-bf9769d97-4hqgv
+spec:
+  containers:
+  - image: kg:3.0
+    name: kg");
+}
@@ -0,0 +1,120 @@
+// This module records the kubectl commands rkl captures, with their output,
+// to fixture files and replays them later without a cluster, to reproduce a
+// bug report or run rkl end to end in tests. A command run several times,
+// like one polled by `logs --follow --include-new`, gets a file for each run,
+// replayed in the same order.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output};
+use std::sync::Mutex;
+
+pub struct Fixtures {
+    dir: PathBuf,
+    replaying: bool,
+    // runs of each command so far
+    runs: Mutex<HashMap<String, usize>>,
+}
+
+impl Fixtures {
+    /// Record commands to files in `dir`, created if missing.
+    pub fn record(dir: &Path) -> std::io::Result<Fixtures> {
+        fs::create_dir_all(dir)?;
+        Ok(Fixtures {dir: dir.to_path_buf(), replaying: false, runs: Mutex::new(HashMap::new())})
+    }
+
+    /// Replay commands recorded in `dir`.
+    pub fn replay(dir: &Path) -> Fixtures {
+        Fixtures {dir: dir.to_path_buf(), replaying: true, runs: Mutex::new(HashMap::new())}
+    }
+
+    pub fn replaying(&self) -> bool {
+        self.replaying
+    }
+
+    // the file of the next run of a command
+    fn next_file(&self, cmd: &str) -> PathBuf {
+        let mut runs = self.runs.lock().unwrap();
+        let run = runs.entry(cmd.to_string()).or_insert(0);
+        *run += 1;
+        self.dir.join(format!("{:016x}-{}", fnv1a(cmd), run))
+    }
+
+    /// Save the output of the next run of a command.
+    pub fn save(&self, cmd: &str, output: &Output) -> std::io::Result<()> {
+        fs::write(self.next_file(cmd), encode(cmd, output))
+    }
+
+    /// The output recorded for the next run of a command.
+    pub fn load(&self, cmd: &str) -> Result<Output> {
+        let file = self.next_file(cmd);
+        let content = fs::read(&file).map_err(|_| anyhow!("no recorded output in {} for {}", self.dir.display(), cmd))?;
+        match decode(&content) {
+            Some((recorded, output)) if recorded == cmd => Ok(output),
+            Some((recorded, _)) => Err(anyhow!("{} was recorded for {}, not {}", file.display(), recorded, cmd)),
+            None => Err(anyhow!("{} is not a recorded command", file.display())),
+        }
+    }
+}
+
+// a hash of the command naming its files, the same with every build unlike
+// the one of std
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(unix)]
+fn exit_status(code: i32) -> ExitStatus {
+    std::os::unix::process::ExitStatusExt::from_raw(code << 8)
+}
+
+#[cfg(windows)]
+fn exit_status(code: i32) -> ExitStatus {
+    std::os::windows::process::ExitStatusExt::from_raw(code as u32)
+}
+
+// the command and its exit code on a line each, then stdout and stderr each
+// after a line with their length, readable enough to edit by hand
+fn encode(cmd: &str, output: &Output) -> Vec<u8> {
+    let mut content = format!("command: {}\nstatus: {}\n", cmd, output.status.code().unwrap_or(-1)).into_bytes();
+    for (name, stream) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+        content.extend(format!("{}: {}\n", name, stream.len()).bytes());
+        content.extend(stream);
+        content.push(b'\n');
+    }
+    content
+}
+
+// the value of the `name: value` line starting content, moving past it
+fn header(content: &mut &[u8], name: &str) -> Option<String> {
+    let end = content.iter().position(|&byte| byte == b'\n')?;
+    let line = std::str::from_utf8(&content[..end]).ok()?;
+    let value = line.strip_prefix(name)?.strip_prefix(": ")?.to_string();
+    *content = &content[end + 1..];
+    Some(value)
+}
+
+fn decode(mut content: &[u8]) -> Option<(String, Output)> {
+    let cmd = header(&mut content, "command")?;
+    let status = exit_status(header(&mut content, "status")?.parse().ok()?);
+    let mut streams = Vec::new();
+    for name in &["stdout", "stderr"] {
+        let len: usize = header(&mut content, name)?.parse().ok()?;
+        streams.push(content.get(..len)?.to_vec());
+        content = content.get(len + 1..)?;
+    }
+    let stderr = streams.pop()?;
+    let stdout = streams.pop()?;
+    Some((cmd, Output {status, stdout, stderr}))
+}
+
+#[test]
+fn test_decode() {
+    let output = Output {status: exit_status(1), stdout: b"kg-1   1/1   Running\n".to_vec(), stderr: b"stderr: oops".to_vec()};
+    let (cmd, decoded) = decode(&encode("kubectl get po", &output)).unwrap();
+    assert_eq!(cmd, "kubectl get po");
+    assert_eq!(decoded, output);
+    assert_eq!(decode(b"command: kubectl get po\nstatus: 0\nstdout: 10\nshort\n"), None);
+}
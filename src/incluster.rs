@@ -0,0 +1,45 @@
+// This module recognizes runs inside a pod, e.g. from a debug image. The pod
+// has the token and the CA of its service account mounted, and kubectl falls
+// back to them when it has no kubeconfig, so rkl then runs plain `kubectl`
+// rather than its default command, and lists pods in the namespace of the pod.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// where the service account of a pod is mounted
+static SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+/// The kubectl command inside a pod, using the service account.
+pub static IN_CLUSTER_KUBECTL_CMD: &str = "kubectl";
+
+// whether the pod of the service account directory can reach the API server
+fn in_cluster_at(dir: &Path, service_host: Option<String>) -> bool {
+    service_host.is_some_and(|host| !host.is_empty()) && dir.join("token").is_file() && dir.join("ca.crt").is_file()
+}
+
+/// Whether rkl runs inside a pod with a service account.
+pub fn in_cluster() -> bool {
+    in_cluster_at(Path::new(SERVICE_ACCOUNT_DIR), env::var("KUBERNETES_SERVICE_HOST").ok())
+}
+
+/// The namespace of the pod rkl runs in, if it runs in one.
+pub fn namespace() -> Option<String> {
+    if !in_cluster() {
+        return None;
+    }
+    let namespace = fs::read_to_string(Path::new(SERVICE_ACCOUNT_DIR).join("namespace")).ok()?;
+    Some(namespace.trim().to_string()).filter(|namespace| !namespace.is_empty())
+}
+
+#[test]
+fn test_in_cluster_at() {
+    let dir = env::temp_dir().join(format!("rkl-serviceaccount-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("token"), "token").unwrap();
+    assert!(!in_cluster_at(&dir, Some("10.0.0.1".to_string())));
+    fs::write(dir.join("ca.crt"), "ca").unwrap();
+    assert!(in_cluster_at(&dir, Some("10.0.0.1".to_string())));
+    assert!(!in_cluster_at(&dir, None));
+    fs::remove_dir_all(&dir).unwrap();
+}
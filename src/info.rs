@@ -0,0 +1,96 @@
+// This module prints a short summary of a pod, the fields people otherwise
+// grep out of `kubectl describe`. kubectl prints them as one tab separated
+// line through a jsonpath template.
+
+use anyhow::{bail, Result};
+use crate::table::format_table;
+use itertools::Itertools;
+
+/// Build the `-o jsonpath=...` argument printing, in this order: name,
+/// namespace, IP, node, QoS class, service account, owner kind, owner name
+/// and start time.
+pub fn info_jsonpath() -> String {
+    r#"-o jsonpath='{.metadata.name}{"\t"}{.metadata.namespace}{"\t"}{.status.podIP}{"\t"}{.spec.nodeName}{"\t"}{.status.qosClass}{"\t"}{.spec.serviceAccountName}{"\t"}{.metadata.ownerReferences[0].kind}{"\t"}{.metadata.ownerReferences[0].name}{"\t"}{.status.startTime}'"#.to_string()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PodSummary {
+    pub name: String,
+    pub namespace: String,
+    pub ip: String,
+    pub node: String,
+    pub qos_class: String,
+    pub service_account: String,
+    pub owner: String,
+    pub start_time: String,
+}
+
+/// The workload owning the pod. Pods of a deployment are owned by one of its
+/// replica sets, named `<deployment>-<hash>`, so the deployment is shown too.
+fn owner(kind: &str, name: &str) -> String {
+    match (kind, name.rsplit_once('-')) {
+        ("", _) => "<none>".to_string(),
+        ("ReplicaSet", Some((deployment, _))) => format!("Deployment/{} (ReplicaSet/{})", deployment, name),
+        _ => format!("{}/{}", kind, name),
+    }
+}
+
+/// Parse the output of the jsonpath template built by `info_jsonpath`.
+pub fn parse_info(output: &str) -> Result<PodSummary> {
+    let (name, namespace, ip, node, qos_class, service_account, owner_kind, owner_name, start_time) =
+        match output.trim_end_matches('\n').split('\t').collect_tuple() {
+            Some(fields) => fields,
+            None => bail!("unexpected pod info: {}", output),
+        };
+    Ok(PodSummary {
+        name: name.to_string(),
+        namespace: namespace.to_string(),
+        ip: ip.to_string(),
+        node: node.to_string(),
+        qos_class: qos_class.to_string(),
+        service_account: service_account.to_string(),
+        owner: owner(owner_kind, owner_name),
+        start_time: start_time.to_string(),
+    })
+}
+
+/// Render the summary as aligned `field: value` lines.
+pub fn format_info(summary: &PodSummary) -> String {
+    let fields = [
+        ("Name", &summary.name),
+        ("Namespace", &summary.namespace),
+        ("IP", &summary.ip),
+        ("Node", &summary.node),
+        ("QoS Class", &summary.qos_class),
+        ("Service Account", &summary.service_account),
+        ("Owner", &summary.owner),
+        ("Start Time", &summary.start_time),
+    ];
+    let rows: Vec<Vec<String>> = fields
+        .iter()
+        .map(|(field, value)| {
+            let value = if value.is_empty() { "<none>".to_string() } else { value.to_string() };
+            vec![format!("{}:", field), value]
+        })
+        .collect();
+    format_table(&rows)
+}
+
+#[test]
+fn test_parse_info() {
+    let output = "kg-sophon2-bf9769d97-4hqgv\tsophon\t10.0.3.7\tkg-node43\tBurstable\tkg\tReplicaSet\tkg-sophon2-bf9769d97\t2021-03-01T10:00:00Z";
+    let summary = parse_info(output).unwrap();
+    assert_eq!(summary.owner, "Deployment/kg-sophon2 (ReplicaSet/kg-sophon2-bf9769d97)");
+    assert_eq!(
+        format_info(&summary),
+        "Name:             kg-sophon2-bf9769d97-4hqgv\n\
+         Namespace:        sophon\n\
+         IP:               10.0.3.7\n\
+         Node:             kg-node43\n\
+         QoS Class:        Burstable\n\
+         Service Account:  kg\n\
+         Owner:            Deployment/kg-sophon2 (ReplicaSet/kg-sophon2-bf9769d97)\n\
+         Start Time:       2021-03-01T10:00:00Z"
+    );
+    assert!(parse_info("kg\tsophon").is_err());
+}
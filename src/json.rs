@@ -0,0 +1,149 @@
+// This module reads the flat JSON objects services write as structured log
+// lines. Only the top level fields are split out: strings are unescaped,
+// every other value, nested objects included, is kept as its raw text.
+// Strings are escaped the other way round for the JSON rkl prints.
+
+/// Parse a JSON object into its fields in order, `None` if `text` is not one.
+pub fn parse_object(text: &str) -> Option<Vec<(String, String)>> {
+    let mut parser = Parser { text, pos: 0 };
+    parser.skip_whitespace();
+    parser.expect('{')?;
+    let mut fields = Vec::new();
+    parser.skip_whitespace();
+    if parser.peek() == Some('}') {
+        parser.pos += 1;
+    } else {
+        loop {
+            parser.skip_whitespace();
+            let key = parser.string()?;
+            parser.skip_whitespace();
+            parser.expect(':')?;
+            parser.skip_whitespace();
+            let value = if parser.peek() == Some('"') { parser.string()? } else { parser.raw_value()? };
+            fields.push((key, value));
+            parser.skip_whitespace();
+            match parser.next()? {
+                ',' => continue,
+                '}' => break,
+                _ => return None,
+            }
+        }
+    }
+    parser.skip_whitespace();
+    if parser.pos == text.len() { Some(fields) } else { None }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Option<()> {
+        if self.next()? == expected { Some(()) } else { None }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.next()? {
+                '"' => return Some(value),
+                '\\' => match self.next()? {
+                    'n' => value.push('\n'),
+                    't' => value.push('\t'),
+                    'r' => value.push('\r'),
+                    'b' => value.push('\u{8}'),
+                    'f' => value.push('\u{c}'),
+                    'u' => {
+                        let code = self.text.get(self.pos..self.pos + 4)?;
+                        self.pos += 4;
+                        value.push(u32::from_str_radix(code, 16).ok().and_then(char::from_u32).unwrap_or('\u{fffd}'));
+                    }
+                    c => value.push(c),
+                },
+                c => value.push(c),
+            }
+        }
+    }
+
+    // a number, literal, array or object, up to the comma or brace ending it
+    fn raw_value(&mut self) -> Option<String> {
+        let start = self.pos;
+        let mut depth = 0;
+        loop {
+            match self.peek()? {
+                '"' => {
+                    self.string()?;
+                    continue;
+                }
+                '{' | '[' => depth += 1,
+                '}' | ']' if depth > 0 => depth -= 1,
+                ',' | '}' if depth == 0 => break,
+                _ => {}
+            }
+            self.next();
+        }
+        let value = self.text[start..self.pos].trim();
+        if value.is_empty() { None } else { Some(value.to_string()) }
+    }
+}
+
+#[test]
+fn test_parse_object() {
+    assert_eq!(
+        parse_object(r#"{"level":"info", "msg":"say \"hi\"\n", "n": 3, "ctx": {"a": [1, "}"]}}"#),
+        Some(vec![
+            ("level".to_string(), "info".to_string()),
+            ("msg".to_string(), "say \"hi\"\n".to_string()),
+            ("n".to_string(), "3".to_string()),
+            ("ctx".to_string(), r#"{"a": [1, "}"]}"#.to_string()),
+        ])
+    );
+    assert_eq!(parse_object("{}"), Some(vec![]));
+    assert_eq!(parse_object("started on port 8080"), None);
+    assert_eq!(parse_object(r#"{"msg": "cut"#), None);
+}
+
+/// `value` as a JSON string, quoted and escaped.
+pub fn string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[test]
+fn test_string() {
+    assert_eq!(string("kg"), r#""kg""#);
+    assert_eq!(string("say \"hi\"\\\n\u{1}"), r#""say \"hi\"\\\n\u0001""#);
+    let escaped = string("a\"b\\c\td");
+    assert_eq!(parse_object(&format!("{{\"v\": {}}}", escaped)), Some(vec![("v".to_string(), "a\"b\\c\td".to_string())]));
+}
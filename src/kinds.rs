@@ -0,0 +1,102 @@
+// This module checks resource kinds against those the cluster serves, as
+// listed by `kubectl api-resources`, and suggests the closest one for a typo
+// like `deplyoment`. The list rarely changes, so it is cached in the data
+// directory for an hour, one file per context.
+
+use crate::paths;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use str_distance::{DamerauLevenshtein, DistanceMetric, DistanceValue};
+
+/// How long the cached list of kinds is used before asking the cluster again.
+pub static CACHE_MAX_AGE: Duration = Duration::from_secs(3600);
+
+/// Arguments to `kubectl api-resources` printing one line per kind:
+/// name, short names if any, api version, whether it is namespaced, kind.
+pub static API_RESOURCES_ARGS: &str = "--no-headers";
+
+// typos further than this many edits from every kind get no suggestion
+static MAX_TYPO_DISTANCE: usize = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiResource {
+    /// Plural name, like `deployments`.
+    pub name: String,
+    pub short_names: Vec<String>,
+    pub kind: String,
+    pub namespaced: bool,
+}
+
+/// Parse the output of `kubectl api-resources`, whose short names column may be empty.
+pub fn parse_api_resources(output: &str) -> Vec<ApiResource> {
+    output.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let short_names = match fields.len() {
+                4 => Vec::new(),
+                5 => fields[1].split(',').map(str::to_string).collect(),
+                _ => return None,
+            };
+            Some(ApiResource {
+                name: fields[0].to_string(),
+                short_names,
+                kind: fields[fields.len() - 1].to_string(),
+                namespaced: fields[fields.len() - 2] == "true",
+            })
+        })
+        .collect()
+}
+
+impl ApiResource {
+    // whether a kind given by the user means this resource, the way kubectl
+    // takes kinds: the kind, its plural or short name, in any case
+    fn is_named(&self, kind: &str) -> bool {
+        let kind = kind.to_lowercase();
+        let singular = self.kind.to_lowercase();
+        kind == singular || kind == self.name || self.short_names.contains(&kind)
+    }
+}
+
+/// The resource of a kind, or the kind it was probably mistyped from.
+pub fn resolve_kind<'a>(kind: &str, resources: &'a [ApiResource]) -> Result<&'a ApiResource, Option<String>> {
+    if let Some(resource) = resources.iter().find(|resource| resource.is_named(kind)) {
+        return Ok(resource);
+    }
+    let kind = kind.to_lowercase();
+    let suggestion = resources.iter()
+        .filter_map(|resource| {
+            match DamerauLevenshtein::with_max_distance(MAX_TYPO_DISTANCE).str_distance(&kind, resource.kind.to_lowercase()) {
+                DistanceValue::Exact(distance) => Some((distance, &resource.kind)),
+                DistanceValue::Exceeded(_) => None,
+            }
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, kind)| kind.to_lowercase());
+    Err(suggestion)
+}
+
+#[test]
+fn test_resolve_kind() {
+    let resources = parse_api_resources("configmaps          cm       v1                 true    ConfigMap
+namespaces          ns       v1                 false   Namespace
+deployments         deploy   apps/v1            true    Deployment
+poddisruptionbudgets  pdb    policy/v1          true    PodDisruptionBudget
+clusterroles                 rbac.authorization.k8s.io/v1   false   ClusterRole
+");
+    assert_eq!(resources.len(), 5);
+    assert_eq!(resources[4].short_names, Vec::<String>::new());
+    assert_eq!(resolve_kind("Deployment", &resources).map(|r| &r.name[..]), Ok("deployments"));
+    assert_eq!(resolve_kind("deploy", &resources).map(|r| &r.name[..]), Ok("deployments"));
+    assert_eq!(resolve_kind("clusterroles", &resources).map(|r| r.namespaced), Ok(false));
+    assert_eq!(resolve_kind("deplyoment", &resources), Err(Some("deployment".to_string())));
+    assert_eq!(resolve_kind("secret", &resources), Err(None));
+}
+
+/// The content of a cache file younger than `max_age`.
+pub fn read_cache(path: &Path, max_age: Duration) -> Option<String> {
+    if paths::age(path)? > max_age {
+        return None;
+    }
+    fs::read_to_string(path).ok().filter(|content| !content.is_empty())
+}
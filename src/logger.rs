@@ -3,6 +3,7 @@
 // print to stderr. We therefore avoid bringing in extra dependencies just
 // for this functionality.
 
+use crate::spinner;
 use log::{self, Log};
 
 /// The simplest possible logger that logs to stderr.
@@ -12,7 +13,7 @@ use log::{self, Log};
 #[derive(Debug)]
 pub struct Logger(());
 
-const LOGGER: &'static Logger = &Logger(());
+const LOGGER: &Logger = &Logger(());
 
 impl Logger {
     /// Create a new logger that logs to stderr and initialize it as the
@@ -31,6 +32,8 @@ impl Log for Logger {
     }
 
     fn log(&self, record: &log::Record) {
+        // a spinner spinning meanwhile would leave half its line before the message
+        spinner::clear();
         match (record.file(), record.line()) {
             (Some(_), Some(_)) => {
                 eprintln!(
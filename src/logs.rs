@@ -0,0 +1,226 @@
+// This module interleaves the logs of several pods. Every pod's log is
+// fetched with `kubectl logs --timestamps` and its lines are prefixed with
+// `[pod name] `, so a line looks like
+//   [sophon-kg-sophon2-bf9769d97-4hqgv] 2021-03-01T10:00:00.123456789Z started
+// Lines without a timestamp, like the rest of a stack trace, stay glued to
+// the line above them.
+
+use crate::json;
+use itertools::Itertools;
+
+// foreground colors given to pods, bright ones after the regular ones
+static POD_COLORS: [u8; 12] = [31, 32, 33, 34, 35, 36, 91, 92, 93, 94, 95, 96];
+
+// fields holding the time, level and message of structured log lines
+static TIME_FIELDS: [&str; 4] = ["ts", "time", "timestamp", "@timestamp"];
+static LEVEL_FIELDS: [&str; 3] = ["level", "lvl", "severity"];
+static MESSAGE_FIELDS: [&str; 2] = ["msg", "message"];
+
+// a log line and the lines following it without timestamps
+struct Entry {
+    key: String,
+    text: String,
+}
+
+/// Split a prefixed line into the pod prefix, the timestamp and the message.
+fn split_line(line: &str) -> Option<(&str, &str, &str)> {
+    let rest = line.strip_prefix('[')?;
+    let prefix_end = rest.find("] ")?;
+    let (prefix, rest) = (&rest[..prefix_end], &rest[prefix_end + 2..]);
+    let (timestamp, message) = match rest.find(' ') {
+        Some(i) => (&rest[..i], &rest[i + 1..]),
+        None => (rest, ""),
+    };
+    sort_key(timestamp)?;
+    Some((prefix, timestamp, message))
+}
+
+/// RFC 3339 timestamps as printed by kubectl drop trailing zeros of the
+/// fraction, pad it back to nanoseconds so keys compare in time order.
+fn sort_key(timestamp: &str) -> Option<String> {
+    let seconds = timestamp.strip_suffix('Z')?;
+    if seconds.len() < 19 || seconds.as_bytes()[10] != b'T' {
+        return None;
+    }
+    let (whole, fraction) = match seconds.find('.') {
+        Some(i) => (&seconds[..i], &seconds[i + 1..]),
+        None => (seconds, ""),
+    };
+    if !fraction.chars().all(|c| c.is_ascii_digit()) || fraction.len() > 9 {
+        return None;
+    }
+    Some(format!("{}.{:0<9}", whole, fraction))
+}
+
+/// Interleave the prefixed, timestamped logs of several pods in time order.
+pub fn merge_by_time(outputs: &[String]) -> String {
+    let mut entries: Vec<Entry> = Vec::new();
+    for output in outputs {
+        let mut last_key = String::new();
+        for line in output.lines() {
+            match split_line(line).and_then(|(_, timestamp, _)| sort_key(timestamp)) {
+                Some(key) => {
+                    last_key = key.clone();
+                    entries.push(Entry { key, text: line.to_string() });
+                }
+                None => match entries.last_mut() {
+                    // a continuation of the previous line of the same output
+                    Some(entry) if !last_key.is_empty() => {
+                        entry.text.push('\n');
+                        entry.text.push_str(line);
+                    }
+                    _ => entries.push(Entry { key: String::new(), text: line.to_string() }),
+                },
+            }
+        }
+    }
+    // sort_by is stable, lines with the same timestamp keep their order
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    entries.into_iter().map(|entry| entry.text).join("\n")
+}
+
+fn level_color(level: &str) -> Option<u8> {
+    match &level.to_lowercase()[..] {
+        "error" | "fatal" | "panic" | "critical" => Some(31),
+        "warn" | "warning" => Some(33),
+        "info" => Some(32),
+        "debug" | "trace" => Some(36),
+        _ => None,
+    }
+}
+
+/// Rewrite JSON log lines as `time LEVEL message key=value...`, keeping only
+/// the given extra fields if any. Other lines and any prefix before the JSON,
+/// like the pod name or kubectl timestamps, are left untouched.
+pub fn pretty_json(output: &str, fields: Option<&[&str]>, color: bool) -> String {
+    output
+        .lines()
+        .map(|line| {
+            let start = match line.find('{') {
+                Some(start) => start,
+                None => return line.to_string(),
+            };
+            let mut object = match json::parse_object(&line[start..]) {
+                Some(object) => object,
+                None => return line.to_string(),
+            };
+            let mut take = |names: &[&str]| {
+                let i = object.iter().position(|(key, _)| names.contains(&&key[..]))?;
+                Some(object.remove(i).1)
+            };
+            let time = take(&TIME_FIELDS);
+            let level = take(&LEVEL_FIELDS);
+            let message = take(&MESSAGE_FIELDS);
+            let mut parts = Vec::new();
+            parts.extend(time);
+            if let Some(level) = level {
+                let upper = format!("{:5}", level.to_uppercase());
+                parts.push(match level_color(&level) {
+                    Some(code) if color => format!("\x1b[{}m{}\x1b[0m", code, upper),
+                    _ => upper,
+                });
+            }
+            parts.extend(message);
+            parts.extend(
+                object
+                    .into_iter()
+                    .filter(|(key, _)| fields.is_none_or(|fields| fields.contains(&&key[..])))
+                    .map(|(key, value)| format!("{}={}", key, value)),
+            );
+            format!("{}{}", &line[..start], parts.join(" "))
+        })
+        .join("\n")
+}
+
+#[test]
+fn test_pretty_json() {
+    let output = "[kg-a] {\"ts\":\"10:00:00\",\"level\":\"warn\",\"msg\":\"slow query\",\"ms\":812,\"user\":\"bob\"}\nplain line";
+    assert_eq!(pretty_json(output, None, false), "[kg-a] 10:00:00 WARN  slow query ms=812 user=bob\nplain line");
+    assert_eq!(pretty_json(output, Some(&["user"]), false), "[kg-a] 10:00:00 WARN  slow query user=bob\nplain line");
+    assert_eq!(pretty_json("{\"level\":\"error\"}", None, true), "\x1b[31mERROR\x1b[0m");
+}
+
+/// The color of a pod, the same one on every run.
+fn pod_color(pod_name: &str) -> u8 {
+    let hash = pod_name.bytes().fold(0usize, |hash, b| hash.wrapping_mul(31).wrapping_add(b as usize));
+    POD_COLORS[hash % POD_COLORS.len()]
+}
+
+/// The `[pod name]` prefix of streamed log lines, in the color of the pod if wanted.
+pub fn prefix(pod_name: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m[{}]\x1b[0m", pod_color(pod_name), pod_name)
+    } else {
+        format!("[{}]", pod_name)
+    }
+}
+
+/// Color the `[pod name]` prefix of merged log lines, and with `color_lines`
+/// the rest of the line and the lines glued to it as well.
+pub fn colorize(merged: &str, color_lines: bool) -> String {
+    let mut color = None;
+    merged
+        .lines()
+        .map(|line| match split_line(line) {
+            Some((prefix, _, _)) => {
+                let pod_color = pod_color(prefix);
+                color = Some(pod_color);
+                let rest = &line[prefix.len() + 2..];
+                if color_lines {
+                    format!("\x1b[{}m[{}]{}\x1b[0m", pod_color, prefix, rest)
+                } else {
+                    format!("\x1b[{}m[{}]\x1b[0m{}", pod_color, prefix, rest)
+                }
+            }
+            None => match color {
+                Some(pod_color) if color_lines => format!("\x1b[{}m{}\x1b[0m", pod_color, line),
+                _ => line.to_string(),
+            },
+        })
+        .join("\n")
+}
+
+#[test]
+fn test_colorize() {
+    let merged = "[kg-a] 2021-03-01T10:00:00Z exception\n\tat Foo.bar(Foo.java:1)";
+    let color = pod_color("kg-a");
+    assert_eq!(pod_color("kg-a"), color);
+    assert_eq!(
+        colorize(merged, false),
+        format!("\x1b[{}m[kg-a]\x1b[0m 2021-03-01T10:00:00Z exception\n\tat Foo.bar(Foo.java:1)", color)
+    );
+    assert_eq!(
+        colorize(merged, true),
+        format!("\x1b[{0}m[kg-a] 2021-03-01T10:00:00Z exception\x1b[0m\n\x1b[{0}m\tat Foo.bar(Foo.java:1)\x1b[0m", color)
+    );
+}
+
+#[test]
+fn test_sort_key() {
+    assert_eq!(sort_key("2021-03-01T10:00:00.1Z"), Some("2021-03-01T10:00:00.100000000".to_string()));
+    assert_eq!(sort_key("2021-03-01T10:00:00Z"), Some("2021-03-01T10:00:00.000000000".to_string()));
+    assert!(sort_key("2021-03-01T10:00:00.1Z") > sort_key("2021-03-01T10:00:00.099Z"));
+    assert_eq!(sort_key("started"), None);
+    assert_eq!(sort_key("2021-03-01 10:00:00Z"), None);
+}
+
+#[test]
+fn test_merge_by_time() {
+    let outputs = vec![
+        "[kg-a] 2021-03-01T10:00:00.1Z first of a\n\
+         [kg-a] 2021-03-01T10:00:02Z exception in a\n\
+         \tat Foo.bar(Foo.java:1)\n"
+            .to_string(),
+        "[kg-b] 2021-03-01T10:00:00.05Z first of b\n\
+         [kg-b] 2021-03-01T10:00:01.5Z second of b\n"
+            .to_string(),
+    ];
+    assert_eq!(
+        merge_by_time(&outputs),
+        "[kg-b] 2021-03-01T10:00:00.05Z first of b\n\
+         [kg-a] 2021-03-01T10:00:00.1Z first of a\n\
+         [kg-b] 2021-03-01T10:00:01.5Z second of b\n\
+         [kg-a] 2021-03-01T10:00:02Z exception in a\n\
+         \tat Foo.bar(Foo.java:1)"
+    );
+}
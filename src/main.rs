@@ -1,31 +1,102 @@
 mod manager;
 mod args;
+mod audit;
+mod bench;
+mod cert;
+mod config;
 mod logger;
+mod logs;
+mod manifest;
+mod metrics;
+mod node;
+mod notify;
+mod container;
+mod describe;
+mod diff;
+mod event;
+mod export;
+mod fixture;
+mod incluster;
+mod info;
+mod json;
+mod kinds;
+mod paths;
+mod pending;
+mod pod;
+mod redact;
+mod report;
+mod retry;
+mod selection;
+mod semaphore;
+mod signals;
+mod spinner;
+mod table;
+mod top;
+mod version;
+mod workload;
 
 use std::process;
-use log::info;
-use manager::Manager;
-use clap::Clap;
+use log::{error, info};
+use std::fs::OpenOptions;
+use std::io::Write;
+use manager::{Exit, Manager};
 use logger::Logger;
 
 fn main() {
     // assume Logger::init is Ok
     Logger::init().unwrap();
     log::set_max_level(log::LevelFilter::Info);
-    let args = args::Args::parse();
+    let args = args::Args::parse_with_kubectl_args(std::env::args().collect());
     if let Some(shell) = args.completion {
         shell.generate();
         process::exit(0);
     }
-    if let None = args.cmd {
+    if args.cmd.is_none() {
         info!("please provide a command, see details with --help");
         process::exit(0);
     }
+    signals::install();
+    let output_file = args.output_file.clone();
+    let append = args.append;
+    if args.notify {
+        notify::enable();
+    }
     let manager = Manager::new(args);
-    let result_message = manager.run();
-    // print result message to stdout if successful so that you redirect the message, otherwise print to stderr
+    // a command exiting with a code of its own still has its output shown or saved
+    let (result_message, code) = match manager.run() {
+        Ok(message) => (Ok(message), 0),
+        Err(e) => match e.downcast::<Exit>() {
+            Ok(exit) => (Ok(exit.output), exit.code),
+            Err(e) => (Err(e), 1),
+        },
+    };
+    notify::finished(code == 0);
+    if let (Some(path), Ok(message)) = (&output_file, &result_message) {
+        if let Err(e) = save_output(path, message, append) {
+            error!("failed to write {}: {}", path, e);
+            process::exit(1);
+        }
+        let lines = message.lines().count();
+        info!("{} line{} written to {}", lines, if lines == 1 { "" } else { "s" }, path);
+        process::exit(code);
+    }
+    // only results go to stdout, so redirecting or piping it never captures
+    // prompts, candidates or progress, which the logger sends to stderr
     match result_message {
+       Ok(message) if message.is_empty() => {}
        Ok(message) => println!("{}", message),
        Err(e) => eprintln!("{}", e),
     }
-}
\ No newline at end of file
+    if code != 0 {
+        process::exit(code);
+    }
+}
+
+// write the result of a command to a file, after what it holds already if appending
+fn save_output(path: &str, message: &str, append: bool) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create(true).append(append).truncate(!append).open(path)?;
+    if !message.is_empty() {
+        writeln!(file, "{}", message)?;
+    }
+    Ok(())
+}
@@ -1,29 +1,125 @@
 use crate::args::Args;
-use crate::args::Command;
+use crate::args::{Action, CandidatesFormat, Command, ConfigAction, SortBy};
+use crate::audit;
+use crate::bench::{self, Timing};
+use crate::cert;
+use crate::config::{self, Config};
+use crate::container::{self, ContainerRestarts, OomKilled};
+use crate::describe;
+use crate::diff;
+use crate::event::{self, Event};
+use crate::export;
+use crate::fixture::Fixtures;
+use crate::incluster;
+use crate::info;
+use crate::json;
+use crate::kinds::{self, ApiResource};
+use crate::logs;
+use crate::manifest::{self, Manifest};
+use crate::metrics;
+use crate::node;
+use crate::paths;
+use crate::pending;
+use crate::pod;
+use crate::redact::{self, Redactor};
+use crate::report;
+use crate::retry::{self, RetryPolicy};
+use crate::selection;
+use crate::semaphore::Semaphore;
+use crate::signals;
+use crate::spinner::Spinner;
+use crate::table;
+use crate::top;
+use crate::version::{self, Version};
+use crate::workload;
 use std::process;
 use itertools::Itertools;
-use std::convert::From;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use io::stdin;
 use std::io;
+use std::io::{BufRead, Write};
 use std::fmt;
 use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use std::path::Path;
 use str_distance::{DistanceMetric, Jaccard};
 use regex::Regex;
 
 pub struct Manager {
     args: Args,
     kub: String,
+    candidate_size: usize,
+    // kubectl processes run at once by bulk operations
+    concurrency: usize,
+    // containers skipped by logs, exec and image, none with --include-sidecars
+    sidecars: Vec<String>,
+    // masks secrets in the output of describe and logs, with --redact
+    redactor: Option<Redactor>,
+    // whether deleting pods needs a reason, set by require_reason
+    require_reason: bool,
+    // whether commands changing the cluster are refused, with --read-only or read_only
+    read_only: bool,
+    // whether kubectl auth can-i is asked before running commands, with --check-access or check_access
+    check_access: bool,
 }
 
-static MAX_CANDIDATE_SIZE: usize = 25;
+/// The end of a command exiting with a code of its own, like that of a remote
+/// command which failed, after the output it has to show.
+#[derive(Debug)]
+pub struct Exit {
+    pub output: String,
+    pub code: i32,
+}
+
+impl fmt::Display for Exit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.output)
+    }
+}
+
+impl std::error::Error for Exit {}
+
+static MAX_CANDIDATE_SIZE: usize = 100;
+// letters a to y pick the first candidates of a page, z applies to all
+static MAX_LETTER_CHOICES: usize = 25;
 static DEFAULT_CANDIDATE_SIZE: usize = 5;
+static DEFAULT_CONCURRENCY: usize = 8;
+// number of recent events shown when previewing a candidate
+static PREVIEW_EVENTS: usize = 3;
+// seconds to wait for a followed pod, or its replacement, to run again
+static FOLLOW_RETRY_INTERVAL: u64 = 2;
+// longest wait in seconds before restarting a port-forward which stopped
+static MAX_RECONNECT_BACKOFF: u64 = 60;
+// env holding the URL template of the open command
+static OPEN_URL_ENV: &str = "RKL_OPEN_URL";
+// env holding the command template of node-ssh
+static SSH_COMMAND_ENV: &str = "RKL_SSH_COMMAND";
+static DEFAULT_SSH_COMMAND: &str = "ssh {address}";
+// shell of an exec session, bash where the image has it
+static SESSION_SHELL: &str = "command -v bash >/dev/null && exec bash || exec sh";
+// warning before an exec session reaches its --max-duration, halfway for short ones
+static SESSION_NOTICE: Duration = Duration::from_secs(60);
+// env making rkl ask questions even though stdin is no terminal, to script the answers
+static INTERACTIVE_ENV: &str = "RKL_INTERACTIVE";
+// longest run of a captured command, set with --timeout
+static COMMAND_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+// how read-only commands failing transiently are run again
+static RETRY_POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+// where captured commands are recorded to or replayed from, set with --record or --replay
+static FIXTURES: OnceLock<Fixtures> = OnceLock::new();
 static DEFAULT_KUBECTL_CMD: &str = "kubectl -s https://127.0.0.1:6443 --certificate-authority=/srv/kubernetes/ca.pem --client-certificate=/srv/kubernetes/admin.pem  --client-key=/srv/kubernetes/admin-key.pem";
 
-// PodInfo with kubectl get po -owide
+// PodInfo with kubectl get po -owide, built with PodInfo::new. It derives
+// no Serialize or Deserialize since serde is not a dependency: the JSON of
+// the candidates is written by format_candidates, and the cached listing is
+// the output of kubectl itself
 #[derive(Debug, Clone)]
 pub struct PodInfo {
     name: String,
+    // empty for pods not listed by kubectl
+    namespace: String,
     ready: String,
     status: String,
     restarts: String,
@@ -32,201 +128,2850 @@ pub struct PodInfo {
     node: String,
     nominated_node: String,
     readiness_gates: String,
+    // named by the annotation kubectl.kubernetes.io/default-container, empty if none
+    default_container: String,
+    // names of the app containers, empty for pods not listed by kubectl
+    containers: Vec<String>,
+}
+
+impl PodInfo {
+    // a pod from its namespace and the columns of `kubectl get po -owide`,
+    // checking the columns rkl reads more than prints
+    fn new(namespace: &str, columns: [String; 9]) -> Result<PodInfo, String> {
+        let [name, ready, status, restarts, age, ip, node, nominated_node, readiness_gates] = columns;
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.') {
+            return Err(format!("invalid name {}", name));
+        }
+        let counts = ready.split_once('/').filter(|(a, b)| a.parse::<u32>().is_ok() && b.parse::<u32>().is_ok());
+        if counts.is_none() {
+            return Err(format!("ready {} is not like 1/2", ready));
+        }
+        if restarts.split_whitespace().next().and_then(|count| count.parse::<u32>().ok()).is_none() {
+            return Err(format!("restarts {} is not a number", restarts));
+        }
+        Ok(PodInfo {name, namespace: namespace.to_string(), ready, status, restarts, age, ip, node, nominated_node, readiness_gates, default_container: String::new(), containers: Vec::new()})
+    }
+}
+
+// a pod of the columns of `kubectl get po -owide`, in no namespace
+#[cfg(test)]
+fn test_pod(columns: [&str; 9]) -> PodInfo {
+    PodInfo::new("", columns.map(str::to_string)).unwrap()
+}
+
+#[test]
+fn test_pod_info_new() {
+    let pod_info = test_pod(["kg-1", "0/1", "CrashLoopBackOff", "4 (5m ago)", "12d", "172.26.0.124", "kg-node43", "<none>", "<none>"]);
+    assert_eq!(pod_info.restart_count(), 4);
+    assert_eq!(pod_info.node, "kg-node43");
+    let columns = ["kg-1", "Running", "0/1", "0", "12d", "172.26.0.124", "kg-node43", "<none>", "<none>"].map(str::to_string);
+    assert_eq!(PodInfo::new("sophon", columns).unwrap_err(), "ready Running is not like 1/2");
+    let columns = ["Kg_1", "0/1", "Running", "0", "12d", "172.26.0.124", "kg-node43", "<none>", "<none>"].map(str::to_string);
+    assert_eq!(PodInfo::new("sophon", columns).unwrap_err(), "invalid name Kg_1");
+}
+
+impl fmt::Display for PodInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.name, self.ready, self.status, self.restarts, self.age, self.ip, self.node, self.nominated_node, self.readiness_gates)
+    }
+}
+
+/// Columns of a pod shown in the candidate list
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    Compact,
+    Default,
+    Wide,
+}
+
+impl PodInfo {
+    fn columns(&self, layout: Layout) -> Vec<&str> {
+        match layout {
+            Layout::Compact => vec![&self.name, &self.status, &self.age],
+            Layout::Default => vec![&self.name, &self.ready, &self.status, &self.restarts, &self.age, &self.ip, &self.node],
+            Layout::Wide => vec![&self.name, &self.ready, &self.status, &self.restarts, &self.age, &self.ip, &self.node, &self.nominated_node, &self.readiness_gates],
+        }
+    }
+}
+
+impl Manager {
+    pub fn new(mut args: Args) -> Self {
+        // flags take precedence over the env and the config file
+        let config = Config::load();
+        args.kubectl = args.kubectl.or(config.kubectl);
+        // the default command needs the certificates of a master node, not there in a pod
+        let in_cluster = args.kubeconfig.is_none() && config.kubeconfig.is_none() && incluster::in_cluster();
+        if args.kubectl.is_none() {
+            let default = if in_cluster { incluster::IN_CLUSTER_KUBECTL_CMD } else { DEFAULT_KUBECTL_CMD };
+            args.kubectl = match &config.kubectl_path {
+                Some(_) => Some(default.to_string()),
+                None => match config::installed_frontend() {
+                    // oc, `microk8s kubectl` or `k3s kubectl` know their cluster already
+                    Some(frontend) if frontend != "kubectl" => Some(frontend.to_string()),
+                    _ => in_cluster.then(|| default.to_string()),
+                },
+            };
+        }
+        // kubectl_path swaps the program of whichever command is used, its flags kept
+        let kubectl_path = config.kubectl_path.as_deref();
+        args.kubectl = args.kubectl.map(|kubectl| with_program(&kubectl, kubectl_path));
+        args.middle = args.middle.or(config.middle);
+        args.namespace = args.namespace.or(config.namespace);
+        args.context = args.context.or(config.context);
+        args.kubeconfig = args.kubeconfig.or(config.kubeconfig);
+        args.token_file = args.token_file.or(config.token_file);
+        args.timeout = args.timeout.or(config.timeout);
+        if let Some(timeout) = args.timeout {
+            COMMAND_TIMEOUT.set(Duration::from_secs(timeout)).ok();
+        }
+        let default_policy = RetryPolicy::default();
+        RETRY_POLICY.set(RetryPolicy {
+            retries: config.retries.unwrap_or(default_policy.retries),
+            backoff: config.retry_backoff.map(Duration::from_millis).unwrap_or(default_policy.backoff),
+        }).ok();
+        if let Some(dir) = &args.record {
+            match Fixtures::record(Path::new(dir)) {
+                Ok(fixtures) => { FIXTURES.set(fixtures).ok(); }
+                Err(e) => {
+                    log::error!("failed to create {}: {}", dir, e);
+                    process::exit(1);
+                }
+            }
+        }
+        if let Some(dir) = &args.replay {
+            FIXTURES.set(Fixtures::replay(Path::new(dir))).ok();
+        }
+        // kubectl merges the files of KUBECONFIG itself, every command it runs inherits the list
+        if let Some(kubeconfig) = &args.kubeconfig {
+            std::env::set_var("KUBECONFIG", kubeconfig);
+        }
+        let kub = with_extra_args(get_kub(&args), config.extra_args.as_deref());
+        for warning in cert::expiry_warnings(&kub, config.cert_warn_days.unwrap_or(cert::DEFAULT_WARN_DAYS)) {
+            log::warn!("{}", warning);
+        }
+        let concurrency = args.concurrency.or(config.concurrency).unwrap_or(DEFAULT_CONCURRENCY);
+        let sidecars = if args.include_sidecars {
+            Vec::new()
+        } else {
+            config.sidecars.unwrap_or_else(|| container::DEFAULT_SIDECARS.iter().map(|sidecar| sidecar.to_string()).collect())
+        };
+        let read_only = args.read_only || config.read_only.unwrap_or(false);
+        let check_access = args.check_access || config.check_access.unwrap_or(false);
+        let redactor = if args.redact {
+            Redactor::new(&config.redact_keys.unwrap_or_else(|| redact::DEFAULT_KEYS.iter().map(|key| key.to_string()).collect()))
+        } else {
+            None
+        };
+        Manager { args, kub, candidate_size: get_candidate_size(config.candidate_size), concurrency, sidecars, redactor, require_reason: config.require_reason.unwrap_or(false), read_only, check_access }
+    }
+
+    pub fn run(&self) -> Result<String> {
+        let command = self.args.cmd.clone().unwrap();
+        if let Command::RUNSCRIPT {script, ..} = &command {
+            if !Path::new(script).is_file() {
+                bail!("script {} not found", script);
+            }
+        }
+        // the actions chained with --then are checked along with the command, before any of them runs
+        let chain: Vec<Command> = std::iter::once(command.clone())
+            .chain(self.args.then.iter().map(|action| action.to_command(String::new())))
+            .collect();
+        if self.read_only {
+            if let Some(name) = chain.iter().find_map(mutation) {
+                bail!("{} changes the cluster, refused in read-only mode", name);
+            }
+        }
+        if let Some(command) = chain.iter().find(|command| destructive(command)) {
+            self.check_reason(command)?;
+        }
+        if self.args.list_candidates_only {
+            return Ok(self.list_candidates(command_pod_name(&command)));
+        }
+        let (command, kub_commands) = match &command {
+            Command::CRASHING {name, action} => {
+                let pod_name_slice = self.pod_name_slice(name.as_deref().unwrap_or(""));
+                let crashing_pods = filter_crashing(self.list_pods(), &pod_name_slice);
+                let action = match action {
+                    Some(action) => action,
+                    None => return Ok(crashing_pods.iter().join("\n")),
+                };
+                if crashing_pods.is_empty() {
+                    log::info!("no crashing pod named like {} found!", pod_name_slice);
+                    return Ok(String::new());
+                }
+                let action_command = action.to_command(crashing_pods[0].name.clone());
+                let kub_commands = if crashing_pods.len() > 1 {
+                    self.handle_multiple_results(&action_command, &pod_name_slice, crashing_pods)
+                } else {
+                    vec![self.pod_command(&action_command, &crashing_pods[0])]
+                };
+                (action_command, kub_commands)
+            }
+            Command::OOM {name} => {
+                let pod_name_slice = self.pod_name_slice(name.as_deref().unwrap_or(""));
+                let cmd = format!("{} get po {}", self.kub, container::oom_jsonpath());
+                log::info!("{}", cmd);
+                // commands are not executed while debugging
+                let output = if cfg!(debug_assertions) { String::new() } else { shell_output(&cmd) };
+                let oom_killed: Vec<OomKilled> = container::parse_oom_killed(&output)
+                    .into_iter()
+                    .filter(|killed| killed.pod.contains(&pod_name_slice[..]))
+                    .collect();
+                if oom_killed.is_empty() {
+                    log::info!("no OOMKilled container in pods named like {} found!", pod_name_slice);
+                    return Ok(String::new());
+                }
+                return Ok(container::format_oom_killed(&oom_killed));
+            }
+            Command::RESTARTS {name, watch, threshold, interval} => {
+                let pod_name_slice = self.pod_name_slice(name.as_deref().unwrap_or(""));
+                if *watch {
+                    self.watch_restarts(&pod_name_slice, *threshold, *interval);
+                }
+                let pods: Vec<PodInfo> = self.list_pods().into_iter()
+                    .filter(|pod_info| pod_info.name.contains(&pod_name_slice[..]))
+                    .sorted_by(|a, b| b.restart_count().cmp(&a.restart_count()))
+                    .collect();
+                return Ok(pods.iter().map(|pod_info| {
+                    match threshold {
+                        Some(threshold) if pod_info.restart_count() > *threshold => format!("{}\t{}\t! above {}", pod_info.name, pod_info.restarts, threshold),
+                        _ => format!("{}\t{}", pod_info.name, pod_info.restarts),
+                    }
+                }).join("\n"));
+            }
+            Command::IMAGES {name} => {
+                let pod_name_slice = self.pod_name_slice(name.as_deref().unwrap_or(""));
+                let cmd = format!("{} get po {}", self.kub, container::images_jsonpath());
+                log::info!("{}", cmd);
+                // commands are not executed while debugging
+                let output = if cfg!(debug_assertions) { String::new() } else { shell_output(&cmd) };
+                let mut rows = vec![table::header(&["IMAGE", "PODS"])];
+                for (image, pods) in container::count_images(&output, &pod_name_slice) {
+                    rows.push(vec![image, pods.to_string()]);
+                }
+                return Ok(table::format_table(&rows));
+            }
+            Command::NODESLOAD => {
+                let mut rows = vec![table::header(&["NODE", "PODS", "RESTARTS"])];
+                for (node, pods, restarts) in nodes_load(&self.list_pods()) {
+                    rows.push(vec![node, pods.to_string(), restarts.to_string()]);
+                }
+                return Ok(table::format_table(&rows));
+            }
+            Command::CONFIG {action: ConfigAction::INIT} => return self.init_config(),
+            Command::CONFIG {action: ConfigAction::VALIDATE} => return validate_config(),
+            Command::CONFIG {action: ConfigAction::SHOW} => {
+                return Ok(format!("# {}\n{}", config_location(), config::show(&config::read_file(), config::env_value)));
+            }
+            Command::PROMPT {format} => {
+                // nothing is logged, the prompt is redrawn after every command
+                let cmd = context_namespace_cmd(&self.kub);
+                // commands are not executed while debugging
+                let output = if cfg!(debug_assertions) { String::new() } else { shell_output(&cmd) };
+                return Ok(fill_prompt(format, &output, self.args.context.as_deref(), self.args.namespace.as_deref()));
+            }
+            Command::REPORT {name} => {
+                let pod_name_slice = self.pod_name_slice(name.as_deref().unwrap_or(""));
+                let pods: Vec<report::PodState> = self.list_pods().into_iter()
+                    .filter(|pod_info| pod_info.name.contains(&pod_name_slice[..]))
+                    .map(|pod_info| report::PodState {restarts: pod_info.restart_count(), name: pod_info.name, status: pod_info.status})
+                    .collect();
+                let cmd = format!("{} get events {}", self.kub, event::warning_events_args());
+                log::info!("{}", cmd);
+                // commands are not executed while debugging
+                let output = if cfg!(debug_assertions) { String::new() } else { shell_output(&cmd) };
+                let title = if pod_name_slice.is_empty() { "all pods" } else { &pod_name_slice[..] };
+                return Ok(report::build_report(title, &pods, &event::parse_events(&output)));
+            }
+            Command::TOP {name, sort_by, above} => {
+                let above = match above.as_deref().map(|above| above.trim_end_matches('%').parse()) {
+                    Some(Ok(above)) => Some(above),
+                    Some(Err(_)) => bail!("invalid --above {}, expected a percentage like 80%", above.as_deref().unwrap_or("")),
+                    None => None,
+                };
+                let pod_name_slice = self.pod_name_slice(name.as_deref().unwrap_or(""));
+                let cmd = format!(
+                    "{kub} top pod --no-headers 2>&1; echo {sep}; {kub} get po {limits}",
+                    kub = self.kub, sep = top::USAGE_SEPARATOR, limits = top::limits_args()
+                );
+                log::info!("{}", cmd);
+                // commands are not executed while debugging
+                let output = if cfg!(debug_assertions) { String::new() } else { shell_output(&cmd) };
+                if metrics::metrics_unavailable(&output) {
+                    log::warn!("{}", metrics::METRICS_HINT);
+                    return Ok(String::new());
+                }
+                let pods = top::parse_pod_usage(&output).into_iter()
+                    .filter(|pod| pod.name.contains(&pod_name_slice[..]))
+                    .collect();
+                return Ok(top::format_pod_usage(&top::sort_and_filter(pods, *sort_by == Some(SortBy::Memory), above)));
+            }
+            Command::BENCH {name, names} => return Ok(self.bench(name, *names)),
+            Command::VERSION => return Ok(self.versions()),
+            Command::FIND {name, all_contexts} => return self.find(&self.pod_name_slice(name), *all_contexts),
+            Command::TOPNODES {sort_by} => {
+                let cmd = format!(
+                    "{kub} top nodes --no-headers 2>&1; echo {sep}; {kub} get nodes {allocatable}",
+                    kub = self.kub, sep = node::USAGE_SEPARATOR, allocatable = node::allocatable_args()
+                );
+                log::info!("{}", cmd);
+                // commands are not executed while debugging
+                let output = if cfg!(debug_assertions) { String::new() } else { shell_output(&cmd) };
+                let with_usage = !metrics::metrics_unavailable(&output);
+                if !with_usage {
+                    log::warn!("{}", metrics::METRICS_HINT);
+                }
+                let nodes = node::parse_node_usage(&output);
+                return Ok(node::format_node_usage(&nodes, *sort_by == Some(SortBy::Memory), atty::is(atty::Stream::Stdout), with_usage));
+            }
+            Command::DELETE {selector: Some(selector), ..} => {
+                let selected_pods = self.list_pods_matching(Some(selector));
+                if selected_pods.is_empty() {
+                    log::info!("no pod matches the selector {}", selector);
+                    return Ok(String::new());
+                }
+                let kub_commands = self.batch_delete(&command, &selected_pods, &format!("pods matching the selector {}", selector))?;
+                (command, kub_commands)
+            }
+            Command::DELETE {stdin: true, ..} => {
+                let mut input = String::new();
+                io::Read::read_to_string(&mut stdin(), &mut input)?;
+                return self.delete_patterns(&command, &read_patterns(&input));
+            }
+            Command::CLEANUP {name, evicted, completed, failed} => {
+                let pod_name_slice = self.pod_name_slice(name.as_deref().unwrap_or(""));
+                // without any state chosen, every terminal state is cleaned up
+                let all_states = !(*evicted || *completed || *failed);
+                let terminal_pods = filter_terminal(
+                    self.list_pods(), &pod_name_slice, all_states || *evicted, all_states || *completed, all_states || *failed
+                );
+                if terminal_pods.is_empty() {
+                    log::info!("no pod to clean up found!");
+                    return Ok(String::new());
+                }
+                let delete_command = Command::DELETE {name: None, selector: None, all: false, force: false, wait: false, stdin: false};
+                let kub_commands = self.batch_delete(&delete_command, &terminal_pods, "pods to clean up")?;
+                (delete_command, kub_commands)
+            }
+            Command::EXEC {name, all: true, command: remote_command, ..} => {
+                if remote_command.is_empty() {
+                    bail!("--all needs a command to run, e.g. rkl exec {} --all -- date", name);
+                }
+                let pod_name_slice = self.pod_name_slice(name);
+                let pods: Vec<PodInfo> = self.list_pods().into_iter()
+                    .filter(|pod_info| pod_info.name.contains(&pod_name_slice[..]))
+                    .collect();
+                if pods.is_empty() {
+                    log::info!("no such a pod named like {} found!", pod_name_slice);
+                    return Ok(String::new());
+                }
+                self.ensure_access(&command, &pods.iter().map(|pod_info| pod_info.namespace.clone()).collect::<Vec<String>>())?;
+                let (report, code) = self.exec_all(&command, &pods);
+                if code != 0 {
+                    return Err(Exit {output: report, code}.into());
+                }
+                return Ok(report);
+            }
+            Command::LOGS {name, follow: true, include_new: true, ..} => {
+                self.follow_all_logs(&command, &self.pod_name_slice(name));
+            }
+            Command::LOGS {name, follow: true, ..} => {
+                let pod_name_slice = self.pod_name_slice(name);
+                let pods = self.resolve_pods(&pod_name_slice);
+                if pods.len() > 1 {
+                    log::warn!("only one pod can be followed, following {}", pods[0].name);
+                }
+                self.follow_logs(&command, &pod_name_slice, &pods[0]);
+            }
+            Command::PORTFORWARD {name, port} => {
+                let (local_port, remote_port) = match parse_port_mapping(port) {
+                    Some(mapping) => mapping,
+                    None => bail!("invalid port {}, expected <remote> or <local>:<remote>", port),
+                };
+                let pod_name_slice = self.pod_name_slice(name);
+                let pods = self.resolve_pods(&pod_name_slice);
+                self.ensure_access(&command, &pods.iter().map(|pod_info| pod_info.namespace.clone()).collect::<Vec<String>>())?;
+                return self.port_forward(&pod_name_slice, &pods, local_port, remote_port);
+            }
+            Command::DIFF {first, second} => {
+                let pods: Vec<PodInfo> = [first, second].iter()
+                    .map(|name| {
+                        let pods = self.resolve_pods(&self.pod_name_slice(name));
+                        if pods.len() > 1 {
+                            log::warn!("several pods chosen, comparing {}", pods[0].name);
+                        }
+                        pods[0].clone()
+                    })
+                    .collect();
+                let specs: Vec<String> = pods.iter()
+                    .map(|pod_info| {
+                        let cmd = self.pod_command(&command, pod_info);
+                        log::info!("{}", cmd);
+                        // commands are not executed while debugging
+                        if cfg!(debug_assertions) { String::new() } else { diff::spec_section(&shell_output(&cmd)) }
+                    })
+                    .collect();
+                let spec_diff = diff::unified(&pods[0].name, &pods[1].name, &specs[0], &specs[1], atty::is(atty::Stream::Stdout));
+                if spec_diff.is_empty() {
+                    log::info!("{} and {} have the same spec", pods[0].name, pods[1].name);
+                }
+                return Ok(spec_diff);
+            }
+            Command::DRIFT {name} => {
+                let pods = self.resolve_pods(&self.pod_name_slice(name));
+                if pods.len() > 1 {
+                    log::warn!("several pods chosen, comparing {}", pods[0].name);
+                }
+                let pod_info = &pods[0];
+                let kub = self.pod_kub(pod_info);
+                let owner_cmd = get_kub_command(&kub, &command, &pod_info.name[..]);
+                log::info!("{}", owner_cmd);
+                // commands are not executed while debugging
+                if cfg!(debug_assertions) {
+                    return Ok(String::new());
+                }
+                let (resource, workload) = match workload::template_owner(&shell_output(&owner_cmd)) {
+                    Some(owner) => owner,
+                    None => bail!("{} is not made from the pod template of a deployment, statefulset, daemonset or job", pod_info.name),
+                };
+                let template_cmd = format!("{} get {} {} -o yaml", kub, resource, workload);
+                let pod_cmd = format!("{} get po {} -o yaml", kub, pod_info.name);
+                log::info!("{}", template_cmd);
+                log::info!("{}", pod_cmd);
+                let template = diff::template_spec_section(&shell_output(&template_cmd));
+                let spec = diff::spec_section(&shell_output(&pod_cmd));
+                let template_name = format!("{}/{}", resource, workload);
+                let drift = diff::unified(&template_name, &pod_info.name, &template, &spec, atty::is(atty::Stream::Stdout));
+                if drift.is_empty() {
+                    log::info!("{} matches the pod template of {}", pod_info.name, template_name);
+                }
+                return Ok(drift);
+            }
+            Command::EXPORT {name, kind: Some(kind)} => {
+                let resource = self.check_kind(kind);
+                let object = self.resolve_object(&resource, name);
+                let export_command = Command::EXPORT {name: object.clone(), kind: Some(resource)};
+                let kub_commands = vec![get_kub_command(&self.kub[..], &export_command, &object)];
+                (export_command, kub_commands)
+            }
+            Command::APPLY {path, dry_run, yes} => {
+                let files = match manifest::manifest_files(Path::new(path)) {
+                    Ok(files) => files,
+                    Err(e) => bail!("cannot read manifests in {}: {}", path, e),
+                };
+                let mut manifests = Vec::new();
+                for file in &files {
+                    match std::fs::read_to_string(file) {
+                        Ok(content) => {
+                            let file_manifests = manifest::parse_manifests(&content);
+                            if file_manifests.is_empty() {
+                                log::warn!("no resources read from {}, it is applied as it is", file.display());
+                            }
+                            manifests.extend(file_manifests);
+                        }
+                        Err(e) => bail!("cannot read {}: {}", file.display(), e),
+                    }
+                }
+                if manifests.is_empty() {
+                    bail!("no resources found in {}", path);
+                }
+                let namespace = self.current_namespace();
+                let resources = self.api_resources();
+                let mut rows = vec![table::header(&["KIND", "NAME", "NAMESPACE", "ACTION"])];
+                for manifest in &manifests {
+                    // without the kinds of the cluster, the well known ones tell the scope
+                    let namespaced = match kinds::resolve_kind(&manifest.kind, &resources) {
+                        Ok(resource) => resource.namespaced,
+                        Err(_) if resources.is_empty() => !manifest::cluster_scoped(&manifest.kind),
+                        Err(Some(suggestion)) => bail!("unknown kind {} of {}, did you mean {}?", manifest.kind, manifest.name, suggestion),
+                        Err(None) => bail!("unknown kind {} of {}, see kubectl api-resources", manifest.kind, manifest.name),
+                    };
+                    let target_namespace = if namespaced { Some(manifest.namespace.clone().unwrap_or_else(|| namespace.clone())) } else { None };
+                    let action = self.apply_action(manifest, target_namespace.as_deref());
+                    rows.push(vec![manifest.kind.clone(), manifest.name.clone(), target_namespace.unwrap_or_else(|| "-".to_string()), action.to_string()]);
+                }
+                log::info!("{}", table::format_table(&rows));
+                if !*dry_run && !*yes {
+                    if !interactive() {
+                        bail!("not asking for confirmation in non-interactive mode, pass --yes to apply");
+                    }
+                    if !confirm(&format!("type yes to apply these {} resources", manifests.len())) {
+                        bail!("aborted");
+                    }
+                }
+                let kub_commands = vec![get_kub_command(&self.kub[..], &command, path)];
+                (command, kub_commands)
+            }
+            Command::RESOLVE {name} => {
+                let pods = self.resolve_pods(&self.pod_name_slice(name));
+                return Ok(pods.iter().map(|pod_info| &pod_info.name).join("\n"));
+            }
+            Command::NODESSH {name, template} => {
+                let template = template.clone()
+                    .or_else(|| std::env::var(SSH_COMMAND_ENV).ok())
+                    .unwrap_or_else(|| DEFAULT_SSH_COMMAND.to_string());
+                let pods = self.resolve_pods(&self.pod_name_slice(name));
+                if pods.len() > 1 {
+                    log::warn!("several pods chosen, connecting to the node of {}", pods[0].name);
+                }
+                let node = &pods[0].node;
+                let ssh_command = fill_ssh_template(&template, node, &self.node_address(node));
+                log::info!("{}", ssh_command);
+                // commands are not executed while debugging
+                if cfg!(not(debug_assertions)) {
+                    signals::run_interactive(std::process::Command::new("sh").arg("-c").arg(&ssh_command))
+                        .expect("failed to execute cmd");
+                }
+                return Ok(String::new());
+            }
+            Command::NODECONTAINERS {name, template, inspect} => {
+                let template = template.clone()
+                    .or_else(|| std::env::var(SSH_COMMAND_ENV).ok())
+                    .unwrap_or_else(|| DEFAULT_SSH_COMMAND.to_string());
+                let pods = self.resolve_pods(&self.pod_name_slice(name));
+                if pods.len() > 1 {
+                    log::warn!("several pods chosen, listing the containers of {}", pods[0].name);
+                }
+                let pod_info = &pods[0];
+                let namespace = if pod_info.namespace.is_empty() { self.current_namespace() } else { pod_info.namespace.clone() };
+                let script = node::crictl_script(&pod_info.name, &namespace, *inspect);
+                let ssh_command = format!("{} {}", fill_ssh_template(&template, &pod_info.node, &self.node_address(&pod_info.node)), shell_quote(&script));
+                log::info!("{}", ssh_command);
+                // commands are not executed while debugging
+                if cfg!(debug_assertions) {
+                    return Ok(String::new());
+                }
+                let output = capture(&ssh_command)?;
+                if !output.status.success() {
+                    bail!("failed to list the containers of {} on {}: {}", pod_info.name, pod_info.node, String::from_utf8_lossy(&output.stderr).trim());
+                }
+                return Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string());
+            }
+            Command::OPEN {name, template} => {
+                let template = match template.clone().or_else(|| std::env::var(OPEN_URL_ENV).ok()) {
+                    Some(template) => template,
+                    None => bail!("no URL template, pass --template or set the env {}, e.g. https://grafana/d/pods?var-namespace={{namespace}}&var-pod={{pod}}", OPEN_URL_ENV),
+                };
+                let pods = self.resolve_pods(&self.pod_name_slice(name));
+                let namespace = self.current_namespace();
+                let urls: Vec<String> = pods.iter().map(|pod_info| fill_url_template(&template, pod_info, &namespace)).collect();
+                let kub_commands = urls.iter().map(|url| get_kub_command(&self.kub[..], &command, url)).collect();
+                (command, kub_commands)
+            }
+            Command::HISTORYROLLOUT {name} => {
+                let deployment = self.resolve_deployment(&self.pod_name_slice(name));
+                return Ok(self.revision_history(&deployment));
+            }
+            Command::ROLLBACK {name, to_revision, yes} => {
+                let deployment = self.resolve_deployment(&self.pod_name_slice(name));
+                log::info!("{}", self.revision_history(&deployment));
+                if !interactive() && (to_revision.is_none() || !*yes) {
+                    bail!("not asking in non-interactive mode, pass --to-revision and --yes to roll back");
+                }
+                let to_revision = match to_revision {
+                    Some(to_revision) => Some(*to_revision),
+                    None => ask_revision(),
+                };
+                let target = to_revision.map(|revision| format!("revision {}", revision)).unwrap_or_else(|| "the previous revision".to_string());
+                if !*yes && !confirm(&format!("type yes to roll {} back to {}", deployment, target)) {
+                    bail!("aborted");
+                }
+                let rollback_command = Command::ROLLBACK {name: deployment.clone(), to_revision, yes: *yes};
+                let kub_commands = vec![get_kub_command(&self.kub[..], &rollback_command, &deployment)];
+                (rollback_command, kub_commands)
+            }
+            _ if !self.args.then.is_empty() => return self.run_chain(&command),
+            _ => {
+                let kub_commands = self.get_kub_command(&command);
+                (command, kub_commands)
+            }
+        };
+        self.execute(&command, kub_commands)
+    }
+
+    // run the command, then each action given with --then, on the same pods
+    // resolved once
+    fn run_chain(&self, command: &Command) -> Result<String> {
+        let pods = self.resolve_command_pods(command);
+        let commands = std::iter::once(command.clone())
+            .chain(self.args.then.iter().map(|action| action.to_command(String::new())));
+        let mut outputs = Vec::new();
+        for command in commands {
+            let kub_commands = pods.iter()
+                .map(|pod_info| self.pod_command(&command, pod_info))
+                .collect();
+            outputs.push(self.execute(&command, kub_commands)?);
+        }
+        Ok(outputs.join("\n"))
+    }
+
+    // run the kubectl commands of a command, formatting their output
+    fn execute(&self, command: &Command, kub_commands: Vec<String>) -> Result<String> {
+        self.ensure_access(command, &kub_commands.iter().map(|kub_command| command_namespace(&self.kub, kub_command)).collect::<Vec<String>>())?;
+        if let Command::DELETE {force: true, ..} = command {
+            log::warn!("force deletion does not wait for the containers to stop, they may keep running on the node");
+            for kub_command in &kub_commands {
+                log::info!("{}", kub_command);
+            }
+            if !confirm("type yes to force delete") {
+                bail!("aborted");
+            }
+        }
+        if destructive(command) {
+            self.record_reason(&kub_commands.iter().filter_map(|kub_command| deleted_pod(kub_command)).collect::<Vec<String>>())?;
+        }
+        let mut command_output = Vec::new();
+        for kub_command in kub_commands {
+            log::info!("{}", kub_command);
+            // following code block is not executed while debugging
+            if cfg!(not(debug_assertions)) {
+                if let Command::RUNSCRIPT {..} = command {
+                    // stream the output of the script instead of capturing it
+                    signals::run_in_group(std::process::Command::new("sh").arg("-c").arg(kub_command))
+                        .expect("failed to execute cmd");
+                    continue;
+                }
+                if needs_terminal(command) {
+                    // kubectl sets the terminal raw for a session and restores it afterwards
+                    let mut session = std::process::Command::new("sh");
+                    let status = match command {
+                        Command::EXEC {max_duration: Some(limit), ..} => {
+                            // kubectl replaces the shell, so stopping the child stops kubectl
+                            session.arg("-c").arg(format!("exec {}", kub_command));
+                            let notice = SESSION_NOTICE.min(*limit / 2);
+                            let warning = format!("rkl: this session ends in {}s, at its --max-duration", notice.as_secs());
+                            signals::run_interactive_within(&mut session, *limit, notice, &warning)
+                        }
+                        _ => signals::run_interactive(session.arg("-c").arg(&kub_command)),
+                    }.expect("failed to execute cmd");
+                    if !status.success() {
+                        return Err(Exit {output: String::new(), code: exit_code(status)}.into());
+                    }
+                    continue;
+                }
+                let started = Instant::now();
+                let spinner = Spinner::start("waiting for kubectl");
+                let output = if read_only(command) { capture_retrying(&kub_command)? } else { capture(&kub_command)? };
+                drop(spinner);
+                if let Command::DELETE {wait: true, ..} = command {
+                    log::info!("finished after {:.1}s", started.elapsed().as_secs_f64());
+                }
+                let raw_output = String::from_utf8_lossy(&output.stdout).to_string();
+                let formatted = format_output(command, raw_output)?;
+                command_output.push(match (&self.redactor, command) {
+                    (Some(redactor), Command::DESCRIBE {..} | Command::LOGS {..}) => redactor.redact(&formatted),
+                    _ => formatted,
+                });
+            }
+        }
+        if let Command::LOGS {merge_by_time: true, color_lines, ..} = command {
+            let merged = logs::merge_by_time(&command_output);
+            // keep escape codes out of files and pipes
+            if atty::is(atty::Stream::Stdout) {
+                return Ok(logs::colorize(&merged, *color_lines));
+            }
+            return Ok(merged);
+        }
+        Ok(command_output.join("\n"))
+    }
+
+    fn get_kub_command(&self, command: &Command) -> Vec<String> {
+        self.resolve_command_pods(command).iter()
+            .map(|pod_info| self.pod_command(command, pod_info))
+            .collect()
+    }
+
+    // the pods the name given to a command stands for
+    fn resolve_command_pods(&self, command: &Command) -> Vec<PodInfo> {
+        // @FILE stands for the patterns in the file, resolved one after another
+        let patterns = match command_pod_name(command).strip_prefix('@') {
+            Some(file) => match std::fs::read_to_string(file) {
+                Ok(content) => read_patterns(&content),
+                Err(e) => {
+                    log::error!("cannot read patterns from {}: {}", file, e);
+                    process::exit(1)
+                }
+            },
+            None => vec![command_pod_name(command).to_string()],
+        };
+        patterns.iter()
+            .flat_map(|pattern| self.resolve_pods(&self.pod_name_slice(pattern)))
+            .collect()
+    }
+
+    // the pods named like the slice, falling back to fuzzy matching and
+    // letting the user choose when there are several
+    fn resolve_pods(&self, pod_name_slice: &str) -> Vec<PodInfo> {
+        let all_pods = self.list_pods();
+        self.exit_if_no_pods(&all_pods);
+        let ranked = rank_pods(all_pods, pod_name_slice);
+        let substring_matches = ranked.iter().filter(|(kind, _)| *kind == MatchKind::Substring).count();
+        let candidate_pods: Vec<PodInfo> = ranked.into_iter().map(|(_, pod_info)| pod_info).take(MAX_CANDIDATE_SIZE).collect();
+        let pods = if candidate_pods.is_empty() {
+            log::info!("no such a pod named like {} found!", pod_name_slice);
+            process::exit(0);
+        } else if substring_matches == 1 {
+            candidate_pods.into_iter().take(1).collect()
+        } else {
+            if substring_matches == 0 {
+                log::info!("no such a pod named like {} found, showing similar names", pod_name_slice);
+            } else {
+                log::info!("multiple pods named like {} found!", pod_name_slice);
+            }
+            self.choose_pods(pod_name_slice, candidate_pods)
+        };
+        if self.args.copy {
+            copy_to_clipboard(&pods.iter().map(|pod_info| &pod_info.name).join("\n"));
+        }
+        pods
+    }
+
+    // the ranked candidates for the name of a command, for --list-candidates-only
+    fn list_candidates(&self, name: &str) -> String {
+        let pod_name_slice = self.pod_name_slice(name);
+        let candidate_pods: Vec<PodInfo> = rank_pods(self.list_pods(), &pod_name_slice).into_iter()
+            .map(|(_, pod_info)| pod_info)
+            .take(MAX_CANDIDATE_SIZE)
+            .collect();
+        format_candidates(&candidate_pods, &pod_name_slice, self.args.candidates_format.unwrap_or(CandidatesFormat::Tsv))
+    }
+
+    // kubectl command for a listed pod, always in the namespace of the pod
+    fn pod_kub(&self, pod_info: &PodInfo) -> String {
+        namespaced_kub(&self.kub, &pod_info.namespace)
+    }
+
+    // the kubectl command running the command on a pod, in its default
+    // container unless the command names one, with the kubectl arguments
+    // given after --
+    fn pod_command(&self, command: &Command, pod_info: &PodInfo) -> String {
+        let apps = container::app_containers(&pod_info.containers, &self.sidecars);
+        let kub_command = match command {
+            Command::IMAGE {..} if apps.len() < pod_info.containers.len() => {
+                format!("{} get po {} {}", self.pod_kub(pod_info), pod_info.name, container::images_of_jsonpath(&apps))
+            }
+            Command::RAW {args, ..} => format!("{} {}", self.pod_kub(pod_info), fill_raw_args(args, pod_info).iter().map(|arg| shell_quote(arg)).join(" ")),
+            _ => get_kub_command(&self.pod_kub(pod_info), &in_default_container(command, pod_info, &self.sidecars), &pod_info.name),
+        };
+        with_kubectl_args(&kub_command, &self.args.kubectl_args)
+    }
+
+    // tell where no pods were found and stop, an empty namespace or a wrong
+    // context being more likely than a name matching nothing
+    fn exit_if_no_pods(&self, pods: &[PodInfo]) {
+        if !pods.is_empty() {
+            return;
+        }
+        let cmd = context_namespace_cmd(&self.kub);
+        let output = shell_output(&cmd);
+        let message = "no pods found in namespace {namespace} of context {context}, see --namespace and --context";
+        log::info!("{}", fill_prompt(message, &output, self.args.context.as_deref(), self.args.namespace.as_deref()));
+        process::exit(0);
+    }
+
+    // the deployment named like the slice, or owning a pod named like it
+    fn resolve_deployment(&self, slice: &str) -> String {
+        let pods = self.list_pods();
+        self.exit_if_no_pods(&pods);
+        let pod_names: Vec<&str> = pods.iter().map(|pod_info| &pod_info.name[..]).collect();
+        let deployments: Vec<String> = if cfg!(debug_assertions) {
+            // debug code, the deployments of the test pods
+            pod_names.iter().filter_map(|pod_name| workload::deployment_of(pod_name)).unique().collect()
+        } else {
+            let cmd = format!("{} get deploy {}", self.kub, workload::DEPLOYMENT_NAMES_ARGS);
+            log::info!("{}", cmd);
+            shell_output(&cmd).lines().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect()
+        };
+        let candidates = workload::match_deployments(&deployments, &pod_names, slice);
+        match candidates.len() {
+            0 => {
+                log::info!("no deployment named like {} found!", slice);
+                process::exit(0);
+            }
+            1 => candidates[0].clone(),
+            _ if self.args.first => candidates[0].clone(),
+            _ if self.args.select.is_some() => {
+                let names: Vec<&str> = candidates.iter().map(|name| &name[..]).collect();
+                match select_choice(self.args.select.as_deref().unwrap_or(""), &names) {
+                    Choice::Index(choice_index) => candidates[choice_index].clone(),
+                    Choice::Default => candidates[0].clone(),
+                    Choice::All => {
+                        log::error!("a single deployment is rolled out, pick one by its number or letter");
+                        process::exit(1)
+                    }
+                }
+            }
+            _ => {
+                log::info!("multiple deployments named like {} found!", slice);
+                exit_if_not_interactive(candidates.len(), slice);
+                pick_name(&candidates)
+            }
+        }
+    }
+
+    // run the exec command on every pod concurrently, at most `concurrency` at
+    // once, printing each output under a header with the pod name and exit
+    // code, and return them with the exit code of the first pod which failed
+    fn exec_all(&self, command: &Command, pods: &[PodInfo]) -> (String, i32) {
+        let semaphore = Semaphore::new(self.concurrency);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = pods.iter().map(|pod_info| {
+                let kub_command = self.pod_command(command, pod_info);
+                log::info!("{}", kub_command);
+                let pod_name = pod_info.name.clone();
+                let semaphore = &semaphore;
+                scope.spawn(move || {
+                    // commands are not executed while debugging
+                    if cfg!(debug_assertions) {
+                        return (pod_name, None, String::new());
+                    }
+                    let _permit = semaphore.acquire();
+                    let output = match capture(&kub_command) {
+                        Ok(output) => output,
+                        Err(e) => return (pod_name, None, e.to_string()),
+                    };
+                    let mut message = String::from_utf8_lossy(&output.stdout).to_string();
+                    message.push_str(&String::from_utf8_lossy(&output.stderr));
+                    (pod_name, output.status.code(), message)
+                })
+            }).collect();
+            let results: Vec<(String, Option<i32>, String)> = handles.into_iter().map(|handle| handle.join().expect("exec thread panicked")).collect();
+            let report = results.iter().map(|(pod_name, code, message)| {
+                let code = code.map(|code| code.to_string()).unwrap_or_else(|| "none".to_string());
+                format!("=== {} (exit {}) ===\n{}", pod_name, code, message.trim_end())
+            }).join("\n");
+            let codes: Vec<Option<i32>> = results.iter().map(|(_, code, _)| *code).collect();
+            (report, first_failure(&codes))
+        })
+    }
+
+    // forward to every pod at once, a single pod on the chosen local port and
+    // several pods each on a free local port, until interrupted
+    fn port_forward(&self, pod_name_slice: &str, pods: &[PodInfo], local_port: Option<u16>, remote_port: u16) -> Result<String> {
+        if pods.len() > 1 && local_port.is_some() {
+            log::warn!("several pods chosen, local ports are picked automatically");
+        }
+        let mut rows = vec![table::header(&["POD", "LOCAL", "REMOTE"])];
+        let mut forwards = Vec::new();
+        for pod_info in pods {
+            let local_port = match local_port {
+                Some(local_port) if pods.len() == 1 => local_port,
+                _ if pods.len() == 1 => remote_port,
+                _ => free_local_port()?,
+            };
+            rows.push(vec![pod_info.name.clone(), local_port.to_string(), remote_port.to_string()]);
+            forwards.push((pod_info.name.clone(), format!("{}:{}", local_port, remote_port), self.pod_kub(pod_info)));
+        }
+        println!("{}", table::format_table(&rows));
+        // commands are not executed while debugging
+        if cfg!(debug_assertions) {
+            for (pod_name, port, kub) in &forwards {
+                let forward_command = Command::PORTFORWARD {name: pod_name_slice.to_string(), port: port.clone()};
+                log::info!("{}", get_kub_command(kub, &forward_command, pod_name));
+            }
+            return Ok(String::new());
+        }
+        log::info!("forwarding, press Ctrl-C to stop");
+        let claimed = Mutex::new(forwards.iter().map(|(pod_name, _, _)| pod_name.clone()).collect::<HashSet<String>>());
+        std::thread::scope(|scope| {
+            for (pod_name, port, kub) in &forwards {
+                let claimed = &claimed;
+                scope.spawn(move || self.keep_forwarding(kub, pod_name_slice, pod_name, port, claimed));
+            }
+        });
+        Ok(String::new())
+    }
+
+    // rerun the forward whenever kubectl exits, backing off exponentially and
+    // moving on to a new pod named like the slice once the pod is gone
+    fn keep_forwarding(&self, kub: &str, pod_name_slice: &str, pod_name: &str, port: &str, claimed: &Mutex<HashSet<String>>) -> ! {
+        let forward_command = Command::PORTFORWARD {name: pod_name_slice.to_string(), port: port.to_string()};
+        let mut pod_name = pod_name.to_string();
+        let mut backoff = 1;
+        loop {
+            let kub_command = get_kub_command(kub, &forward_command, &pod_name);
+            log::info!("{}", kub_command);
+            let started = Instant::now();
+            let status = signals::run_in_group(std::process::Command::new("sh").arg("-c").arg(&kub_command))
+                .expect("failed to execute cmd");
+            // a forward which held for a while starts over with a short delay
+            if started.elapsed().as_secs() > MAX_RECONNECT_BACKOFF {
+                backoff = 1;
+            }
+            log::warn!("forward of {} to {} stopped ({}), reconnecting in {}s", port, pod_name, status, backoff);
+            std::thread::sleep(std::time::Duration::from_secs(backoff));
+            backoff = cmp::min(backoff * 2, MAX_RECONNECT_BACKOFF);
+            let pods: Vec<PodInfo> = self.list_pods().into_iter()
+                .filter(|pod_info| pod_info.name.contains(pod_name_slice))
+                .collect();
+            let mut claimed = claimed.lock().unwrap();
+            if let Some(next_pod_name) = next_followed_pod(&pods, &pod_name, &claimed) {
+                if next_pod_name != pod_name {
+                    log::info!("{} is gone, forwarding {} to {}", pod_name, port, next_pod_name);
+                    claimed.insert(next_pod_name.clone());
+                    pod_name = next_pod_name;
+                }
+            }
+        }
+    }
+
+    // ask for the settings, offering what kubectl knows, and write them
+    fn init_config(&self) -> Result<String> {
+        let path = match paths::config_file() {
+            Some(path) => path,
+            None => bail!("cannot tell where to write the config, HOME is not set"),
+        };
+        if path.exists() && !confirm(&format!("{} exists, type yes to overwrite it", path.display())) {
+            bail!("aborted");
+        }
+        // commands are not executed while debugging
+        let output = |cmd: &str| if cfg!(debug_assertions) { String::new() } else { shell_output(cmd) };
+        let detected = match config::installed_frontend() {
+            Some("kubectl") => "kubectl",
+            Some(frontend) => frontend,
+            None => DEFAULT_KUBECTL_CMD,
+        };
+        let kubectl = ask("kubectl command", self.args.kubectl.as_deref().unwrap_or(detected));
+        let mut contexts: Vec<String> = output(&format!("{} config get-contexts -o name", kubectl)).lines().map(str::to_string).collect();
+        let current_context = output(&format!("{} config current-context", kubectl)).trim().to_string();
+        // the current context is offered as the default
+        contexts.sort_by_key(|context| *context != current_context);
+        let context = if contexts.is_empty() {
+            None
+        } else {
+            log::info!("contexts:");
+            Some(pick_name(&contexts))
+        };
+        let context_arg = context.as_ref().map(|context| format!(" --context {}", shell_quote(context))).unwrap_or_default();
+        let mut namespaces: Vec<String> = output(&format!("{}{} get ns -o name", kubectl, context_arg)).lines()
+            .map(|namespace| namespace.trim_start_matches("namespace/").to_string())
+            .collect();
+        namespaces.sort_by_key(|namespace| namespace != "default");
+        let namespace = if namespaces.is_empty() {
+            None
+        } else {
+            log::info!("namespaces:");
+            Some(pick_name(&namespaces))
+        };
+        let candidate_size = ask("candidates listed at a time", &DEFAULT_CANDIDATE_SIZE.to_string()).parse().ok();
+        let middle = ask("middle name inserted between component and version (kg2 -> kg-sophon2 with -sophon)", "");
+        let config = Config {
+            namespace,
+            context,
+            // kubectl above already read the files given, they are kept
+            kubeconfig: self.args.kubeconfig.clone(),
+            token_file: self.args.token_file.clone(),
+            kubectl: Some(kubectl),
+            timeout: self.args.timeout,
+            concurrency: self.args.concurrency,
+            candidate_size,
+            middle: if middle.is_empty() { None } else { Some(middle) },
+            // retries are rarely tuned, the defaults are left out of the file
+            ..Config::default()
+        };
+        paths::write(&path, &config.render())?;
+        Ok(format!("config written to {}", path.display()))
+    }
+
+    // fail before running anything when a reason for deleting pods is needed
+    // but cannot be asked for, stdin being no terminal or holding the pods
+    fn check_reason(&self, command: &Command) -> Result<()> {
+        match &self.args.reason {
+            Some(reason) if reason.trim().is_empty() => bail!("a reason is required to delete pods, pass --reason"),
+            None if self.require_reason && (!interactive() || matches!(command, Command::DELETE {stdin: true, ..})) => {
+                bail!("a reason is required to delete pods, pass --reason")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // record the reason for deleting pods in the audit log right before they
+    // are deleted, asking for it when the config requires one and --reason was not given
+    fn record_reason(&self, pods: &[String]) -> Result<()> {
+        let reason = match &self.args.reason {
+            Some(reason) => reason.clone(),
+            None if !self.require_reason => return Ok(()),
+            None => {
+                log::info!("reason for deleting {}, recorded in the audit log:", pods.join(", "));
+                let mut input = String::new();
+                stdin().read_line(&mut input)?;
+                input.trim().to_string()
+            }
+        };
+        if reason.trim().is_empty() {
+            bail!("a reason is required to delete pods, pass --reason");
+        }
+        // the reason is kept while debugging too, only kubectl commands are not run
+        audit::record(pods, &reason).map_err(|e| anyhow::anyhow!("failed to write the audit log: {}", e))
+    }
+
+    // fail before running anything when kubectl auth can-i says the user may
+    // not run the command in one of the namespaces, empty for the namespace
+    // of the kubectl command, with --check-access
+    fn ensure_access(&self, command: &Command, namespaces: &[String]) -> Result<()> {
+        let (verb, resource) = match access(command) {
+            Some(access) if self.check_access => access,
+            _ => return Ok(()),
+        };
+        for namespace in namespaces.iter().unique() {
+            let cmd = format!("{} auth can-i {} {}", namespaced_kub(&self.kub, namespace), verb, resource);
+            log::info!("{}", cmd);
+            // commands are not executed while debugging
+            if cfg!(debug_assertions) {
+                continue;
+            }
+            let output = capture_retrying(&cmd)?;
+            // kubectl answers no with a failure, and fails the same way when it cannot ask
+            if String::from_utf8_lossy(&output.stdout).trim().starts_with("no") {
+                let namespace = if namespace.is_empty() { self.current_namespace() } else { namespace.clone() };
+                bail!("you lack permission to {} {} in namespace {}", verb, resource, namespace);
+            }
+            if !output.status.success() {
+                log::warn!("cannot check access, {}", String::from_utf8_lossy(&output.stderr).trim());
+            }
+        }
+        Ok(())
+    }
+
+    // internal IP of the node, or its name if it has none
+    fn node_address(&self, node: &str) -> String {
+        let cmd = format!("{} get node {} -o jsonpath='{{.status.addresses[?(@.type==\"InternalIP\")].address}}'", self.kub, node);
+        log::info!("{}", cmd);
+        // commands are not executed while debugging
+        let address = if cfg!(debug_assertions) { String::new() } else { shell_output(&cmd) };
+        match address.split_whitespace().next() {
+            Some(address) => address.to_string(),
+            None => node.to_string(),
+        }
+    }
+
+    // namespace the pods are listed in, the one of the current kubectl
+    // context unless set for rkl, or the one of the pod rkl runs in
+    fn current_namespace(&self) -> String {
+        if let Some(namespace) = &self.args.namespace {
+            return namespace.clone();
+        }
+        let cmd = format!("{} config view --minify -o jsonpath='{{..namespace}}'", self.kub);
+        log::info!("{}", cmd);
+        // commands are not executed while debugging
+        let namespace = if cfg!(debug_assertions) { String::new() } else { shell_output(&cmd) };
+        match namespace.trim() {
+            "" => incluster::namespace().unwrap_or_else(|| "default".to_string()),
+            namespace => namespace.to_string(),
+        }
+    }
+
+    // the kinds the cluster serves, cached for a while, none while debugging
+    // or when kubectl cannot list them
+    fn api_resources(&self) -> Vec<ApiResource> {
+        let cache = paths::api_resources_file(self.args.context.as_deref());
+        if let Some(output) = cache.as_deref().and_then(|path| kinds::read_cache(path, kinds::CACHE_MAX_AGE)) {
+            return kinds::parse_api_resources(&output);
+        }
+        let cmd = format!("{} api-resources {}", self.kub, kinds::API_RESOURCES_ARGS);
+        log::info!("{}", cmd);
+        // commands are not executed while debugging
+        if cfg!(debug_assertions) {
+            return Vec::new();
+        }
+        let output = match capture_retrying(&cmd) {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
+            Err(e) => {
+                log::warn!("cannot list the kinds of the cluster: {}", e);
+                return Vec::new();
+            }
+        };
+        if let Some(path) = cache {
+            if let Err(e) = paths::write(&path, &output) {
+                log::warn!("failed to cache kinds in {}: {}", path.display(), e);
+            }
+        }
+        kinds::parse_api_resources(&output)
+    }
+
+    // the resource name of a kind given by the user, stopping with the closest
+    // kind when it is not served
+    fn check_kind(&self, kind: &str) -> String {
+        let resources = self.api_resources();
+        if resources.is_empty() {
+            return kind.to_string();
+        }
+        match kinds::resolve_kind(kind, &resources) {
+            Ok(resource) => resource.name.clone(),
+            Err(suggestion) => {
+                match suggestion {
+                    Some(suggestion) => log::error!("unknown kind {}, did you mean {}?", kind, suggestion),
+                    None => log::error!("unknown kind {}, see kubectl api-resources", kind),
+                }
+                process::exit(1)
+            }
+        }
+    }
+
+    // the object of a resource named like the slice, the one named so if any
+    fn resolve_object(&self, resource: &str, slice: &str) -> String {
+        let cmd = format!("{} get {} {}", self.kub, resource, workload::DEPLOYMENT_NAMES_ARGS);
+        log::info!("{}", cmd);
+        // commands are not executed while debugging
+        if cfg!(debug_assertions) {
+            return slice.to_string();
+        }
+        let names: Vec<String> = shell_output(&cmd).lines().map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect();
+        if names.iter().any(|name| name == slice) {
+            return slice.to_string();
+        }
+        let candidates: Vec<String> = names.into_iter().filter(|name| name.contains(slice)).collect();
+        match candidates.len() {
+            0 => {
+                log::info!("no {} named like {} found!", resource, slice);
+                process::exit(0);
+            }
+            1 => candidates[0].clone(),
+            _ if self.args.first => candidates[0].clone(),
+            _ => {
+                log::info!("multiple {} named like {} found!", resource, slice);
+                exit_if_not_interactive(candidates.len(), slice);
+                pick_name(&candidates)
+            }
+        }
+    }
+
+    // whether applying a manifest creates its resource or updates it
+    fn apply_action(&self, manifest: &Manifest, namespace: Option<&str>) -> &'static str {
+        let mut cmd = format!("{} get {} {} --ignore-not-found -o name", self.kub, manifest.kind, shell_quote(&manifest.name));
+        if let Some(namespace) = namespace {
+            cmd.push_str(&format!(" -n {}", namespace));
+        }
+        log::info!("{}", cmd);
+        // commands are not executed while debugging
+        if cfg!(debug_assertions) {
+            return "unknown";
+        }
+        if shell_output(&cmd).trim().is_empty() { "create" } else { "update" }
+    }
+
+    // table of the revisions of a deployment
+    fn revision_history(&self, deployment: &str) -> String {
+        let cmd = format!("{} get rs {}", self.kub, workload::revisions_args());
+        log::info!("{}", cmd);
+        // commands are not executed while debugging
+        let output = if cfg!(debug_assertions) { String::new() } else { shell_output(&cmd) };
+        workload::format_revisions(&workload::parse_revisions(&output, deployment))
+    }
+
+    // show the pods about to be deleted and return their delete commands once confirmed
+    fn batch_delete(&self, delete_command: &Command, pods: &[PodInfo], description: &str) -> Result<Vec<String>> {
+        log::info!("{}:", description);
+        for pod_info in pods {
+            log::info!("{}", pod_info.columns(self.layout()).join("\t"));
+        }
+        if !confirm(&format!("type yes to delete these {} pods", pods.len())) {
+            bail!("aborted");
+        }
+        Ok(pods.iter()
+            .map(|pod_info| self.pod_command(delete_command, pod_info))
+            .collect())
+    }
+
+    // delete the pod named like each pattern, resolved without asking, and
+    // tell what became of every pattern once all of them are done
+    fn delete_patterns(&self, delete_command: &Command, patterns: &[String]) -> Result<String> {
+        let all_pods = self.list_pods();
+        let resolved: Vec<(&String, Result<Vec<PodInfo>, String>)> = patterns.iter()
+            .map(|pattern| {
+                let slice = self.pod_name_slice(pattern);
+                let containing: Vec<PodInfo> = all_pods.iter().filter(|p| p.name.contains(&slice[..])).cloned().collect();
+                (pattern, pick_without_asking(containing, self.args.first, self.args.all_matches, self.args.select.as_deref()))
+            })
+            .collect();
+        let namespaces: Vec<String> = resolved.iter()
+            .flat_map(|(_, pods)| pods.iter().flatten().map(|pod_info| pod_info.namespace.clone()))
+            .collect();
+        self.ensure_access(delete_command, &namespaces)?;
+        let names: Vec<String> = resolved.iter().flat_map(|(_, pods)| pods.iter().flatten().map(|pod_info| pod_info.name.clone())).collect();
+        if !names.is_empty() {
+            self.record_reason(&names)?;
+        }
+        let mut rows = vec![table::header(&["PATTERN", "POD", "RESULT"])];
+        for (pattern, pods) in resolved {
+            let pods = match pods {
+                Ok(pods) => pods,
+                Err(reason) => {
+                    rows.push(vec![pattern.clone(), "-".to_string(), reason]);
+                    continue;
+                }
+            };
+            for pod_info in pods {
+                let kub_command = self.pod_command(delete_command, &pod_info);
+                log::info!("{}", kub_command);
+                // commands are not executed while debugging
+                let result = if cfg!(debug_assertions) {
+                    "not run while debugging".to_string()
+                } else {
+                    match capture(&kub_command) {
+                        Ok(output) if output.status.success() => "deleted".to_string(),
+                        Ok(output) => format!("failed: {}", String::from_utf8_lossy(&output.stderr).lines().next().unwrap_or("")),
+                        Err(e) => format!("failed: {}", e.to_string().lines().next().unwrap_or("")),
+                    }
+                };
+                rows.push(vec![pattern.clone(), pod_info.name.clone(), result]);
+            }
+        }
+        Ok(table::format_table(&rows))
+    }
+
+    // poll pods named like the slice forever, printing an alert with a bell
+    // whenever one restarts or exceeds the threshold
+    fn watch_restarts(&self, pod_name_slice: &str, threshold: Option<u32>, interval: u64) -> ! {
+        log::info!("watching restarts of pods named like {} every {}s, press Ctrl-C to stop", pod_name_slice, interval);
+        let mut restarts = HashMap::new();
+        loop {
+            let pods: Vec<PodInfo> = self.list_pods().into_iter()
+                .filter(|pod_info| pod_info.name.contains(pod_name_slice))
+                .collect();
+            let alerts = restart_alerts(&restarts, &pods, threshold);
+            for alert in &alerts {
+                println!("{}", alert);
+            }
+            if !alerts.is_empty() {
+                // ring the terminal bell
+                eprint!("\x07");
+            }
+            restarts = pods.iter().map(|pod_info| (pod_info.name.clone(), pod_info.restart_count())).collect();
+            std::thread::sleep(std::time::Duration::from_secs(interval));
+        }
+    }
+
+    // stream the log of the pod, and whenever the stream ends wait for the
+    // pod, or a new pod named like the slice replacing it, to run again
+    fn follow_logs(&self, command: &Command, pod_name_slice: &str, pod_info: &PodInfo) -> ! {
+        let mut pod_info = pod_info.clone();
+        // pods replacing it are listed in the same namespace
+        let kub = self.pod_kub(&pod_info);
+        let mut seen: HashSet<String> = self.list_pods().into_iter()
+            .filter(|pod_info| pod_info.name.contains(pod_name_slice))
+            .map(|pod_info| pod_info.name)
+            .collect();
+        loop {
+            let kub_command = with_kubectl_args(&get_kub_command(&kub, &in_default_container(command, &pod_info, &self.sidecars), &pod_info.name), &self.args.kubectl_args);
+            log::info!("{}", kub_command);
+            // commands are not executed while debugging
+            if cfg!(debug_assertions) {
+                process::exit(0);
+            }
+            // lines are piped only to mask them, kubectl writes to the terminal otherwise
+            match &self.redactor {
+                Some(redactor) => stream_lines(&kub_command, None, Some(redactor)),
+                None => {
+                    signals::run_in_group(std::process::Command::new("sh").arg("-c").arg(&kub_command))
+                        .expect("failed to execute cmd");
+                }
+            }
+            let next_pod = loop {
+                let pods: Vec<PodInfo> = self.list_pods().into_iter()
+                    .filter(|pod_info| pod_info.name.contains(pod_name_slice))
+                    .collect();
+                if let Some(next_pod_name) = next_followed_pod(&pods, &pod_info.name, &seen) {
+                    break pods.into_iter().find(|pod_info| pod_info.name == next_pod_name).expect("followed pod is listed");
+                }
+                std::thread::sleep(std::time::Duration::from_secs(FOLLOW_RETRY_INTERVAL));
+            };
+            if next_pod.name == pod_info.name {
+                log::info!("=== {} restarted ===", pod_info.name);
+            } else {
+                log::info!("=== {} is gone, following {} ===", pod_info.name, next_pod.name);
+                seen.insert(next_pod.name.clone());
+            }
+            pod_info = next_pod;
+        }
+    }
+
+    // stream the logs of every pod named like the slice at once, and of pods
+    // named like it once they show up, as a rollout or a scale-up goes
+    fn follow_all_logs(&self, command: &Command, pod_name_slice: &str) -> ! {
+        let colored = atty::is(atty::Stream::Stdout);
+        // kubectl prefixes the lines of every container itself
+        let prefixed = !matches!(command, Command::LOGS {all_containers: true, ..});
+        let mut followed: HashSet<String> = HashSet::new();
+        loop {
+            let first_round = followed.is_empty();
+            let pods: Vec<PodInfo> = self.list_pods().into_iter()
+                .filter(|pod_info| pod_info.name.contains(pod_name_slice))
+                .collect();
+            for pod_info in pods {
+                if !followed.insert(pod_info.name.clone()) {
+                    continue;
+                }
+                let kub_command = self.pod_command(command, &pod_info);
+                log::info!("{}", kub_command);
+                if !first_round {
+                    log::info!("=== {} showed up, following it ===", pod_info.name);
+                }
+                let prefix = if prefixed { Some(logs::prefix(&pod_info.name, colored)) } else { None };
+                let redactor = self.redactor.clone();
+                // commands are not executed while debugging
+                if cfg!(not(debug_assertions)) {
+                    std::thread::spawn(move || stream_lines(&kub_command, prefix.as_deref(), redactor.as_ref()));
+                }
+            }
+            if cfg!(debug_assertions) {
+                process::exit(0);
+            }
+            std::thread::sleep(std::time::Duration::from_secs(FOLLOW_RETRY_INTERVAL));
+        }
+    }
+
+    fn layout(&self) -> Layout {
+        if self.args.compact {
+            Layout::Compact
+        } else if self.args.wide {
+            Layout::Wide
+        } else {
+            Layout::Default
+        }
+    }
+
+    // the pod name slice given by the user, filled with the middle name if set
+    fn pod_name_slice(&self, pod_name_slice: &str) -> String {
+        match &self.args.middle {
+            Some(middle_name) => filled_with_middle_name(pod_name_slice, &middle_name[..]),
+            None => pod_name_slice.to_string(),
+        }
+    }
+
+    fn handle_multiple_results(&self, cmd: &Command, pattern: &str, candidate_pods: Vec<PodInfo>) -> Vec<String> {
+        self.choose_pods(pattern, candidate_pods).iter()
+            .map(|pod_info| self.pod_command(cmd, pod_info))
+            .collect()
+    }
+
+    fn choose_pods(&self, pattern: &str, mut candidate_pods: Vec<PodInfo>) -> Vec<PodInfo> {
+        if self.args.first {
+            return candidate_pods.into_iter().take(1).collect();
+        }
+        if self.args.all_matches {
+            return candidate_pods;
+        }
+        if let Some(select) = &self.args.select {
+            let names: Vec<&str> = candidate_pods.iter().map(|p| &p.name[..]).collect();
+            return match select_choice(select, &names) {
+                Choice::Default => candidate_pods.into_iter().take(1).collect(),
+                Choice::Index(choice_index) => vec![candidate_pods[choice_index].clone()],
+                Choice::All => candidate_pods,
+            };
+        }
+        exit_if_not_interactive(candidate_pods.len(), pattern);
+        log::info!("possible choices:");
+        let kub = &self.kub[..];
+        if self.args.forget {
+            selection::forget(pattern);
+        }
+        // offer the pod picked last time for this pattern as the default
+        let mut default_mark = "(default)";
+        let last_choice = selection::last_choice(pattern)
+            .and_then(|last_choice| candidate_pods.iter().position(|p| p.name == last_choice));
+        if let Some(position) = last_choice {
+            let pod_info = candidate_pods.remove(position);
+            candidate_pods.insert(0, pod_info);
+            default_mark = "(last choice)";
+        }
+        // get candidate size
+        let candidate_size = self.candidate_size;
+        log::info!("you are getting candidate size of {}, try to alter env RKL_CANDIDATE_SIZE to view more", candidate_size);
+        let events = if self.args.with_events { list_warning_events(kub) } else { HashMap::new() };
+        let pages: Vec<&[PodInfo]> = candidate_pods.chunks(candidate_size).collect();
+        let mut page = 0;
+        loop {
+            let page_pods = pages[page];
+            let choices = get_candidate_option(page_pods.len());
+            self.show_candidates(pattern, page_pods, &choices, &events, if page == 0 { Some(default_mark) } else { None });
+            if pages.len() > 1 {
+                log::info!("z: apply to all {} candidates", candidate_pods.len());
+                log::info!("page {}/{} of {} candidates, type > or < to turn the page", page + 1, pages.len(), candidate_pods.len());
+            } else {
+                log::info!("z: apply to all");
+            }
+            log::info!("type your choice or part of a pod name, or press Enter for the default, prefix it with ? to preview a pod...");
+            let mut input = String::new();
+            stdin().read_line(&mut input).unwrap();
+            let input_choice = &input.trim().to_lowercase();
+            if let Some(next_page) = turn_page(input_choice, page, pages.len()) {
+                page = next_page;
+                continue;
+            }
+            if let Some(preview_choice) = input_choice.strip_prefix('?') {
+                match parse_choice(preview_choice.trim(), &choices, page_pods.len()) {
+                    Some(Choice::Index(choice_index)) => log::info!("{}", self.preview(&page_pods[choice_index])),
+                    Some(Choice::Default) => log::info!("{}", self.preview(&candidate_pods[0])),
+                    _ => log::error!("input is not a valid option to preview"),
+                }
+                continue;
+            }
+            let chosen_pod = match parse_choice(input_choice, &choices, page_pods.len()) {
+                Some(Choice::Default) => &candidate_pods[0],
+                Some(Choice::Index(choice_index)) => &page_pods[choice_index],
+                // every candidate, those of the other pages too
+                Some(Choice::All) => return candidate_pods.clone(),
+                // a name typed or pasted is looked up among the candidates of every page,
+                // a single letter is rather a mistyped choice
+                None => match find_typed_name(input_choice, &candidate_pods) {
+                    Ok(position) if input_choice.len() > 1 => &candidate_pods[position],
+                    Err(matches) if input_choice.len() > 1 && matches > 1 => {
+                        log::error!("{} candidates are named like {}, type more of the name", matches, input_choice);
+                        continue;
+                    }
+                    _ => {
+                        log::error!("input is not a valid option");
+                        process::exit(1)
+                    }
+                },
+            };
+            selection::remember(pattern, &chosen_pod.name);
+            return vec![chosen_pod.clone()];
+        }
+    }
+
+    // status, containers and recent events of a candidate, fetched on demand
+    fn preview(&self, pod_info: &PodInfo) -> String {
+        let mut lines = vec![
+            format!("--- {} ---", pod_info.name),
+            format!("status: {}  ready: {}  restarts: {}  age: {}", pod_info.status, pod_info.ready, pod_info.restarts, pod_info.age),
+            format!("node: {}  ip: {}", pod_info.node, pod_info.ip),
+        ];
+        // commands are not executed while debugging
+        if cfg!(not(debug_assertions)) {
+            let kub = self.pod_kub(pod_info);
+            let containers_cmd = get_kub_command(&kub, &Command::CONTAINER {name: pod_info.name.clone()}, &pod_info.name[..]);
+            if let Ok(containers) = container::parse_containers(&shell_output(&containers_cmd)) {
+                lines.push(container::format_containers(&containers));
+            }
+            let events_cmd = format!("{} get events {}", kub, event::pod_events_args(&pod_info.name));
+            for event in event::parse_events(&shell_output(&events_cmd)).iter().take(PREVIEW_EVENTS) {
+                lines.push(format!("event: {}", event));
+            }
+        }
+        lines.join("\n")
+    }
+
+    // print one page of candidates with their container restarts and warnings
+    fn show_candidates(&self, pattern: &str, page_pods: &[PodInfo], choices: &str, events: &HashMap<String, Event>, default_mark: Option<&str>) {
+        let shown_pods: Vec<&str> = page_pods.iter().map(|p| &p.name[..]).collect();
+        let restarts = list_container_restarts(&self.kub[..], &shown_pods);
+        let layout = self.layout();
+        // candidates are listed on stderr, keep escape codes out of it when redirected
+        let colored = atty::is(atty::Stream::Stderr);
+        let rows: Vec<Vec<String>> = page_pods.iter().map(|y| candidate_columns(y, pattern, layout, colored)).collect();
+        let rows = table::format_table(&rows);
+        for (i, (y, row)) in page_pods.iter().zip(rows.lines()).enumerate() {
+            // the top ranked candidate is picked on plain Enter
+            let default_mark = match default_mark {
+                Some(default_mark) if i == 0 => format!("\t{}", default_mark),
+                _ => String::new(),
+            };
+            match choices.chars().nth(i) {
+                Some(x) => log::info!{"{}/{}: {}{}", i + 1, x, row, default_mark},
+                None => log::info!{"{}: {}{}", i + 1, row, default_mark},
+            }
+            if let Some(containers) = restarts.get(&y.name) {
+                log::info!("   containers: {}", containers.iter().join(", "));
+            }
+            if let Some(event) = events.get(&y.name) {
+                log::info!("   last warning: {}", event);
+            }
+        }
+    }
+
+    // where the pods named like the slice live, in every namespace of the
+    // current context or of every context, the contexts listed in parallel
+    fn find(&self, pod_name_slice: &str, all_contexts: bool) -> Result<String> {
+        let contexts: Vec<Option<String>> = if all_contexts {
+            let cmd = format!("{} config get-contexts -o name", self.kub);
+            log::info!("{}", cmd);
+            shell_output(&cmd).lines().map(str::trim).filter(|context| !context.is_empty()).map(|context| Some(context.to_string())).collect()
+        } else {
+            vec![None]
+        };
+        if contexts.is_empty() {
+            bail!("no context found in the kubeconfig");
+        }
+        let semaphore = Semaphore::new(self.concurrency);
+        let now = pod::now();
+        let listings: Vec<(Option<String>, Result<String>)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = contexts.iter().map(|context| {
+                // a later --context wins over the one of the kubectl command, and -A over its -n
+                let context_arg = context.as_ref().map(|context| format!(" --context {}", shell_quote(context))).unwrap_or_default();
+                let cmd = format!("{}{} get po -A {}", self.kub, context_arg, pod::list_args());
+                log::info!("{}", cmd);
+                let semaphore = &semaphore;
+                scope.spawn(move || {
+                    let _permit = semaphore.acquire();
+                    let listing = capture(&cmd).and_then(|output| {
+                        if output.status.success() {
+                            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+                        } else {
+                            Err(anyhow::anyhow!("{}", String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or("").trim().to_string()))
+                        }
+                    });
+                    (context.clone(), listing)
+                })
+            }).collect();
+            handles.into_iter().map(|handle| handle.join().expect("listing thread panicked")).collect()
+        });
+        let mut titles = vec!["NAMESPACE", "POD", "STATUS", "AGE", "NODE"];
+        if all_contexts {
+            titles.insert(0, "CONTEXT");
+        }
+        let mut rows = vec![table::header(&titles)];
+        let mut failures = 0;
+        for (context, listing) in &listings {
+            let output = match listing {
+                Ok(output) => output,
+                Err(e) => {
+                    log::warn!("cannot list the pods of {}: {}", context.as_deref().unwrap_or("the current context"), e);
+                    failures += 1;
+                    continue;
+                }
+            };
+            let pods = pod::parse_pods(output).iter().filter_map(|pod| pod_info_of(pod, now))
+                .filter(|pod_info| pod_info.name.contains(pod_name_slice))
+                .sorted_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+            for pod_info in pods {
+                let mut row = vec![pod_info.namespace.clone(), pod_info.name.clone(), pod_info.status.clone(), pod_info.age.clone(), pod_info.node.clone()];
+                if let Some(context) = context {
+                    row.insert(0, context.clone());
+                }
+                rows.push(row);
+            }
+        }
+        if failures == listings.len() {
+            bail!("no context could be searched");
+        }
+        if rows.len() == 1 {
+            log::info!("no such a pod named like {} found!", pod_name_slice);
+            return Ok(String::new());
+        }
+        Ok(table::format_table(&rows))
+    }
+
+    // the versions of rkl, kubectl and the cluster as a table
+    fn versions(&self) -> String {
+        let cmd = format!("{} {}", self.kub, version::VERSION_ARGS);
+        log::info!("{}", cmd);
+        // run while debugging too, it changes nothing; kubectl fails without a cluster but still prints its own version
+        let output = capture(&cmd).map(|output| String::from_utf8_lossy(&output.stdout).to_string()).unwrap_or_default();
+        let (client, server) = version::parse_versions(&output);
+        if let (Some(client), Some(server)) = (&client, &server) {
+            if let Some(warning) = version::skew_warning(client, server) {
+                log::warn!("{}", warning);
+            }
+        }
+        let git_version = |version: &Option<Version>, missing: &str| version.as_ref().map(|version| version.git_version.clone()).unwrap_or_else(|| missing.to_string());
+        table::format_table(&[
+            vec!["rkl".to_string(), env!("CARGO_PKG_VERSION").to_string()],
+            vec!["kubectl".to_string(), git_version(&client, "unknown")],
+            vec!["cluster".to_string(), git_version(&server, "unreachable")],
+        ])
+    }
+
+    // time the steps of resolving a name, ranking `names` synthetic pods on
+    // top of those of the cluster
+    fn bench(&self, name: &str, names: usize) -> String {
+        let mut timings = Vec::new();
+        let cmd = format!("{} get po {}", self.kub, pod::list_args());
+        log::info!("{}", cmd);
+        let started = Instant::now();
+        let output = shell_output(&cmd);
+        timings.push(Timing {step: "list pods", took: started.elapsed(), detail: format!("{} bytes", output.len())});
+
+        let started = Instant::now();
+        let now = pod::now();
+        let pods: Vec<PodInfo> = pod::parse_pods(&output).iter().filter_map(|pod| pod_info_of(pod, now)).collect();
+        timings.push(Timing {step: "parse pods", took: started.elapsed(), detail: format!("{} pods", pods.len())});
+
+        // synthetic pods share every column but the name with a pod of the cluster, or a made up one
+        let template = pods.first().cloned().or_else(|| {
+            let columns = ["pod", "1/1", "Running", "0", "1d", "10.0.0.1", "node", "<none>", "<none>"].map(str::to_string);
+            PodInfo::new("", columns).ok()
+        }).expect("a valid made up pod");
+        let mut all_pods = pods.clone();
+        all_pods.extend(bench::synthetic_names(names).into_iter().map(|name| PodInfo {name, ..template.clone()}));
+        let total = all_pods.len();
+        let started = Instant::now();
+        let ranked = rank_pods(all_pods, name);
+        timings.push(Timing {step: "rank pods", took: started.elapsed(), detail: format!("{} of {} pods named like {}", ranked.len(), total, name)});
+
+        let cmd = format!("{} top po --no-headers", self.kub);
+        log::info!("{}", cmd);
+        let started = Instant::now();
+        let output = shell_output(&cmd);
+        let detail = if metrics::metrics_unavailable(&output) { "metrics unavailable".to_string() } else { format!("{} pods", output.lines().count()) };
+        timings.push(Timing {step: "query metrics", took: started.elapsed(), detail});
+
+        let caches = [
+            ("read pod cache", paths::pods_file(self.args.context.as_deref(), self.args.namespace.as_deref())),
+            ("read kind cache", paths::api_resources_file(self.args.context.as_deref())),
+        ];
+        for (step, cache) in caches.iter() {
+            let started = Instant::now();
+            let content = cache.as_deref().and_then(|path| std::fs::read_to_string(path).ok());
+            let age = cache.as_deref().and_then(paths::age);
+            let detail = match (content, age) {
+                (Some(content), Some(age)) => format!("hit, {} bytes written {} ago", content.len(), pod::human_duration(age.as_secs())),
+                _ => "miss".to_string(),
+            };
+            timings.push(Timing {step, took: started.elapsed(), detail});
+        }
+        bench::report(&timings)
+    }
+
+    fn list_pods(&self) -> Vec<PodInfo> {
+        self.list_pods_matching(None)
+    }
+
+    // list pods, only those matching the label selector if given
+    fn list_pods_matching(&self, selector: Option<&str>) -> Vec<PodInfo> {
+        // only full listings are cached, those of a selector would leave pods out
+        let cache = if selector.is_none() { paths::pods_file(self.args.context.as_deref(), self.args.namespace.as_deref()) } else { None };
+        let output = if self.args.offline {
+            if selector.is_some() {
+                log::error!("pods cannot be selected by label offline");
+                process::exit(1);
+            }
+            read_cached_pods(cache.as_deref())
+        } else {
+            let selector_arg = selector.map(|selector| format!(" -l {}", shell_quote(selector))).unwrap_or_default();
+            let cmd = format!("{} get po{} {}", self.kub, selector_arg, pod::list_args());
+            log::info!("{}", cmd);
+            let output = {
+                let _spinner = Spinner::start("listing pods");
+                match capture_retrying(&cmd) {
+                    Ok(output) => output,
+                    Err(e) => {
+                        log::warn!("{}", e);
+                        process::exit(1);
+                    }
+                }
+            };
+            // a failed listing leaves the cache of the last one for --offline
+            if !output.status.success() {
+                log::warn!("failed to list pods, {}", String::from_utf8_lossy(&output.stderr).trim());
+            } else if let Some(path) = &cache {
+                if let Err(e) = paths::write(path, &String::from_utf8_lossy(&output.stdout)) {
+                    log::warn!("failed to cache pods in {}: {}", path.display(), e);
+                }
+            }
+            String::from_utf8_lossy(&output.stdout).to_string()
+        };
+        let now = pod::now();
+        pod::parse_pods(&output).iter().filter_map(|pod| pod_info_of(pod, now)).collect()
+    }
+}
+
+// the pods listed by the last run, warning how old the list is, or stop
+// when there is none
+fn read_cached_pods(cache: Option<&Path>) -> String {
+    let cached = cache.and_then(|path| Some((std::fs::read_to_string(path).ok()?, paths::age(path)?)));
+    match cached {
+        Some((output, age)) => {
+            log::warn!("offline, using the pods listed {} ago, they may have changed since", pod::human_duration(age.as_secs()));
+            output
+        }
+        None => {
+            log::error!("no pods listed yet to pick from offline, run once without --offline");
+            process::exit(1)
+        }
+    }
+}
+
+impl PodInfo {
+    // restarts column is a plain number, newer kubectl may append "(5m ago)"
+    fn restart_count(&self) -> u32 {
+        self.restarts.split_whitespace().next().and_then(|r| r.parse().ok()).unwrap_or(0)
+    }
+}
+
+// how a pod name matches the slice, the better matches first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MatchKind {
+    // the name contains the slice
+    Substring,
+    // the words of the slice, split on dashes, start words of the name in order
+    Prefix,
+    // the name shares enough letters with the slice
+    Fuzzy,
+}
+
+// fuzzy matches further than this from the slice are left out
+static FUZZY_THRESHOLD: f64 = 0.8;
+
+fn match_kind(name: &str, slice: &str, distance: f64) -> Option<MatchKind> {
+    if name.contains(slice) {
+        return Some(MatchKind::Substring);
+    }
+    let mut name_words = name.split('-');
+    let prefix = slice.split('-').filter(|word| !word.is_empty())
+        .all(|word| name_words.any(|name_word| name_word.starts_with(word)));
+    if prefix {
+        Some(MatchKind::Prefix)
+    } else if distance <= FUZZY_THRESHOLD {
+        Some(MatchKind::Fuzzy)
+    } else {
+        None
+    }
+}
+
+// which characters of the name match the slice: the substring, else the
+// starts of the words, else the letters of the slice found in order
+fn matched_chars(name: &str, slice: &str) -> Vec<bool> {
+    let mut matched = vec![false; name.chars().count()];
+    if let Some(start) = name.find(slice).filter(|_| !slice.is_empty()) {
+        let start = name[..start].chars().count();
+        matched[start..start + slice.chars().count()].iter_mut().for_each(|m| *m = true);
+        return matched;
+    }
+    if match_kind(name, slice, 1.0) == Some(MatchKind::Prefix) {
+        let mut words = slice.split('-').filter(|word| !word.is_empty()).peekable();
+        let mut offset = 0;
+        for name_word in name.split('-') {
+            if let Some(word) = words.peek() {
+                if name_word.starts_with(word) {
+                    matched[offset..offset + word.chars().count()].iter_mut().for_each(|m| *m = true);
+                    words.next();
+                }
+            }
+            offset += name_word.chars().count() + 1;
+        }
+        return matched;
+    }
+    let mut slice_chars = slice.chars().peekable();
+    for (i, c) in name.chars().enumerate() {
+        if slice_chars.peek() == Some(&c) {
+            matched[i] = true;
+            slice_chars.next();
+        }
+    }
+    matched
+}
+
+// the name with the characters matching the slice in bold
+fn highlight_matches(name: &str, slice: &str) -> String {
+    let matched = matched_chars(name, slice);
+    name.chars().zip(matched)
+        .group_by(|(_, matched)| *matched)
+        .into_iter()
+        .map(|(matched, run)| {
+            let run: String = run.map(|(c, _)| c).collect();
+            if matched { table::paint(&run, 1) } else { run }
+        })
+        .collect()
+}
+
+#[test]
+fn test_highlight_matches() {
+    assert_eq!(highlight_matches("sophon-kg-sophon2-4hqgv", "kg"), "sophon-\x1b[1mkg\x1b[0m-sophon2-4hqgv");
+    assert_eq!(highlight_matches("sophon-kgx-sophon2", "so-kg"), "\x1b[1mso\x1b[0mphon-\x1b[1mkg\x1b[0mx-sophon2");
+    assert_eq!(highlight_matches("mysql-0", "myql"), "\x1b[1mmy\x1b[0ms\x1b[1mql\x1b[0m-0");
+}
+
+// the color of a status: green for running pods with all containers ready,
+// yellow for pods on their way, red for the others
+fn status_color(status: &str, ready: &str) -> u8 {
+    let all_ready = ready.split_once('/').is_some_and(|(ready, all)| ready == all);
+    match status {
+        "Running" if all_ready => 32,
+        "Running" | "Pending" | "ContainerCreating" | "PodInitializing" => 33,
+        _ if status.starts_with("Init:") && !status.contains("Error") && !status.contains("BackOff") => 33,
+        _ => 31,
+    }
+}
+
+#[test]
+fn test_status_color() {
+    assert_eq!(status_color("Running", "1/1"), 32);
+    assert_eq!(status_color("Running", "0/1"), 33);
+    assert_eq!(status_color("Init:0/1", "0/1"), 33);
+    assert_eq!(status_color("Init:CrashLoopBackOff", "0/1"), 31);
+    assert_eq!(status_color("CrashLoopBackOff", "0/1"), 31);
+}
+
+// a row of the candidate list, the status in its color and pods going away
+// or done dimmed as a whole
+fn candidate_columns(pod_info: &PodInfo, slice: &str, layout: Layout, colored: bool) -> Vec<String> {
+    let columns = pod_info.columns(layout);
+    if !colored {
+        return columns.iter().map(|c| c.to_string()).collect();
+    }
+    if matches!(&pod_info.status[..], "Terminating" | "Completed" | "Succeeded") {
+        return columns.iter().map(|c| table::paint(c, 2)).collect();
+    }
+    let status_column = if layout == Layout::Compact { 1 } else { 2 };
+    columns.iter().enumerate()
+        .map(|(i, c)| match i {
+            0 => highlight_matches(c, slice),
+            _ if i == status_column => table::paint(c, status_color(&pod_info.status, &pod_info.ready)),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+// the pods matching the slice as one list, substring matches first, then
+// prefix and fuzzy matches, each kind ranked by distance to the slice
+fn rank_pods(all_pods: Vec<PodInfo>, slice: &str) -> Vec<(MatchKind, PodInfo)> {
+    all_pods.into_iter()
+        .filter_map(|pod_info| {
+            let distance = Jaccard::new(1).str_distance(&pod_info.name, slice);
+            match_kind(&pod_info.name, slice, distance).map(|kind| (kind, distance, pod_info))
+        })
+        .sorted_by(|a, b| a.0.cmp(&b.0).then(a.1.partial_cmp(&b.1).unwrap_or(cmp::Ordering::Equal)).then_with(|| a.2.name.cmp(&b.2.name)))
+        .map(|(kind, _, pod_info)| (kind, pod_info))
+        .collect()
+}
+
+#[test]
+fn test_rank_pods() {
+    let pods: Vec<PodInfo> = vec![
+        ["sophon-kg-sophon2-bf9769d97-4hqgv",   "1/1", "Running", "0", "56m", "172.26.0.124", "kg-node43", "<none>", "<none>"],
+        ["sophon-ui-sophon2-79c997dd8c-vkths",  "1/1", "Running", "0", "9d",  "172.26.0.124", "kg-node43", "<none>", "<none>"],
+        ["sophon-kgx-sophon2-5fc7f9dcb7-j7srl", "1/1", "Running", "0", "9d",  "172.26.0.124", "kg-node43", "<none>", "<none>"],
+        ["mysql-0",                             "1/1", "Running", "0", "9d",  "172.26.0.124", "kg-node43", "<none>", "<none>"],
+    ].into_iter().map(test_pod).collect();
+    let ranked = |slice: &str| -> Vec<(MatchKind, String)> {
+        rank_pods(pods.clone(), slice).into_iter().map(|(kind, pod_info)| (kind, pod_info.name)).collect()
+    };
+    assert_eq!(ranked("kg-"), vec![
+        (MatchKind::Substring, "sophon-kg-sophon2-bf9769d97-4hqgv".to_string()),
+        (MatchKind::Prefix, "sophon-kgx-sophon2-5fc7f9dcb7-j7srl".to_string()),
+    ]);
+    // a typo still finds the pod, ranked by the letters shared
+    assert_eq!(ranked("sohpon-kg")[0], (MatchKind::Fuzzy, "sophon-kg-sophon2-bf9769d97-4hqgv".to_string()));
+}
+
+// the name given to a command, standing for the pods it runs on
+fn command_pod_name(command: &Command) -> &str {
+    match command {
+        Command::DELETE {name, ..} => name.as_deref().unwrap_or(""),
+        Command::DESCRIBE {name, ..} => name,
+        Command::IMAGE {name} => name,
+        Command::CONTAINER {name} => name,
+        Command::INFO {name} => name,
+        Command::LOGS {name, ..} => name,
+        Command::EXEC {name, ..} => name,
+        Command::RUNSCRIPT {name, ..} => name,
+        Command::RESOURCES {name} => name,
+        Command::WHYPENDING {name} => name,
+        Command::DIFF {first, ..} => first,
+        Command::DRIFT {name} => name,
+        Command::EXPORT {name, ..} => name,
+        Command::PORTFORWARD {name, ..} | Command::OPEN {name, ..} => name,
+        Command::RESOLVE {name} | Command::NODESSH {name, ..} | Command::NODECONTAINERS {name, ..} | Command::RAW {name, ..} => name,
+        Command::ROLLBACK {name, ..} | Command::HISTORYROLLOUT {name} => name,
+        Command::CRASHING {name, ..} | Command::OOM {name} | Command::RESTARTS {name, ..} | Command::IMAGES {name} | Command::CLEANUP {name, ..} | Command::TOP {name, ..} | Command::REPORT {name} => name.as_deref().unwrap_or(""),
+        Command::NODESLOAD | Command::TOPNODES {..} | Command::PROMPT {..} | Command::CONFIG {..} | Command::APPLY {..} | Command::BENCH {..} | Command::VERSION => "",
+        Command::FIND {name, ..} => name,
+    }
+}
+
+// the ranked candidates for an external picker, one per line with their
+// name, the share of letters they have in common with the slice, status
+// and namespace, their choice for --select being their name
+fn format_candidates(candidates: &[PodInfo], slice: &str, format: CandidatesFormat) -> String {
+    let fields = candidates.iter().map(|pod_info| {
+        let score = 1.0 - Jaccard::new(1).str_distance(&pod_info.name, slice);
+        (&pod_info.name, format!("{:.2}", score), &pod_info.status, &pod_info.namespace)
+    });
+    match format {
+        CandidatesFormat::Tsv => fields.map(|(name, score, status, namespace)| format!("{}\t{}\t{}\t{}", name, score, status, namespace)).join("\n"),
+        CandidatesFormat::Json if candidates.is_empty() => "[]".to_string(),
+        CandidatesFormat::Json => {
+            let mut objects = fields.map(|(name, score, status, namespace)| {
+                format!(
+                    r#"  {{"name": {}, "score": {}, "status": {}, "namespace": {}}}"#,
+                    json::string(name), score, json::string(status), json::string(namespace)
+                )
+            });
+            format!("[\n{}\n]", objects.join(",\n"))
+        }
+    }
+}
+
+#[test]
+fn test_format_candidates() {
+    let candidates: Vec<PodInfo> = vec![
+        ["sophon-kg-sophon2-bf9769d97-fgpnn", "0/1", "CrashLoopBackOff", "23", "12d", "172.26.0.125", "kg-node44", "<none>", "<none>"],
+        ["sophon-kg-sophon2-bf9769d97-4hqgv", "1/1", "Running",          "1",  "56m", "172.26.0.124", "kg-node43", "<none>", "<none>"],
+    ].into_iter().map(test_pod).map(|pod_info| PodInfo {namespace: "sophon".to_string(), ..pod_info}).collect();
+    assert_eq!(
+        format_candidates(&candidates, "kg", CandidatesFormat::Tsv),
+        "sophon-kg-sophon2-bf9769d97-fgpnn\t0.13\tCrashLoopBackOff\tsophon\n\
+         sophon-kg-sophon2-bf9769d97-4hqgv\t0.11\tRunning\tsophon"
+    );
+    assert_eq!(
+        format_candidates(&candidates[1..], "kg", CandidatesFormat::Json),
+        "[\n  {\"name\": \"sophon-kg-sophon2-bf9769d97-4hqgv\", \"score\": 0.11, \"status\": \"Running\", \"namespace\": \"sophon\"}\n]"
+    );
+    let odd = PodInfo {name: r#"kg-"a"\b"#.to_string(), ..candidates[1].clone()};
+    assert!(format_candidates(&[odd], "kg", CandidatesFormat::Json).contains(r#""name": "kg-\"a\"\\b""#));
+}
+
+// pods neither running nor completed whose name contains the slice, most restarted first
+fn filter_crashing(all_pods: Vec<PodInfo>, pod_name_slice: &str) -> Vec<PodInfo> {
+    all_pods.into_iter()
+        .filter(|pod_info| pod_info.status != "Running" && pod_info.status != "Completed")
+        .filter(|pod_info| pod_info.name.contains(pod_name_slice))
+        .sorted_by(|a, b| b.restart_count().cmp(&a.restart_count()))
+        .collect()
+}
+
+#[test]
+fn test_filter_crashing() {
+    let all_pods: Vec<PodInfo> = vec![
+        ["sophon-kg-sophon2-bf9769d97-4hqgv",   "1/1", "Running",          "0",  "56m", "172.26.0.124", "kg-node43", "<none>", "<none>"],
+        ["sophon-kg-sophon2-bf9769d97-fgpnn",   "0/1", "CrashLoopBackOff", "23", "12d", "172.26.0.125", "kg-node44", "<none>", "<none>"],
+        ["sophon-ui-sophon2-79c997dd8c-vkths",  "0/1", "ImagePullBackOff", "0",  "9d",  "172.26.0.124", "kg-node43", "<none>", "<none>"],
+        ["sophon-ui-sophon2-79c997dd8c-x2b9q",  "0/1", "Error",            "31", "9d",  "172.26.0.124", "kg-node43", "<none>", "<none>"],
+        ["sophon-job-sophon2-1612345678-k2x9z", "0/1", "Completed",        "0",  "1d",  "172.26.0.124", "kg-node43", "<none>", "<none>"],
+    ].into_iter().map(test_pod).collect();
+    let crashing = filter_crashing(all_pods.clone(), "");
+    let names: Vec<&str> = crashing.iter().map(|p| &p.name[..]).collect();
+    assert_eq!(names, vec!["sophon-ui-sophon2-79c997dd8c-x2b9q", "sophon-kg-sophon2-bf9769d97-fgpnn", "sophon-ui-sophon2-79c997dd8c-vkths"]);
+    let crashing = filter_crashing(all_pods, "kg");
+    assert_eq!(crashing.len(), 1);
+    assert_eq!(crashing[0].restart_count(), 23);
+}
+
+#[test]
+fn test_pod_columns() {
+    let pod_info = test_pod(["sophon-kg-sophon2-bf9769d97-fgpnn", "0/1", "CrashLoopBackOff", "23", "12d", "172.26.0.125", "kg-node44", "<none>", "<none>"]);
+    assert_eq!(pod_info.columns(Layout::Compact), vec!["sophon-kg-sophon2-bf9769d97-fgpnn", "CrashLoopBackOff", "12d"]);
+    assert_eq!(pod_info.columns(Layout::Default).len(), 7);
+    assert_eq!(pod_info.columns(Layout::Wide).join("\t"), pod_info.to_string());
+}
+
+// pods in the chosen terminal states whose name contains the slice
+fn filter_terminal(all_pods: Vec<PodInfo>, pod_name_slice: &str, evicted: bool, completed: bool, failed: bool) -> Vec<PodInfo> {
+    all_pods.into_iter()
+        .filter(|pod_info| match &pod_info.status[..] {
+            "Evicted" => evicted,
+            "Completed" => completed,
+            "Error" | "Failed" => failed,
+            _ => false,
+        })
+        .filter(|pod_info| pod_info.name.contains(pod_name_slice))
+        .collect()
+}
+
+#[test]
+fn test_filter_terminal() {
+    let all_pods: Vec<PodInfo> = vec![
+        ["sophon-kg-sophon2-bf9769d97-4hqgv",   "1/1", "Running",   "0", "56m", "172.26.0.124", "kg-node43", "<none>", "<none>"],
+        ["sophon-kg-sophon2-bf9769d97-x7k2m",   "0/1", "Evicted",   "0", "3d",  "<none>",       "kg-node44", "<none>", "<none>"],
+        ["sophon-job-sophon2-1612345678-k2x9z", "0/1", "Completed", "0", "1d",  "172.26.0.124", "kg-node43", "<none>", "<none>"],
+        ["sophon-job-sophon2-1612345678-p8q7r", "0/1", "Error",     "0", "1d",  "172.26.0.124", "kg-node43", "<none>", "<none>"],
+    ].into_iter().map(test_pod).collect();
+    assert_eq!(filter_terminal(all_pods.clone(), "", true, true, true).len(), 3);
+    let evicted = filter_terminal(all_pods.clone(), "", true, false, false);
+    assert_eq!(evicted.len(), 1);
+    assert_eq!(evicted[0].name, "sophon-kg-sophon2-bf9769d97-x7k2m");
+    let job = filter_terminal(all_pods, "job", false, false, true);
+    assert_eq!(job.len(), 1);
+    assert_eq!(job[0].status, "Error");
+}
+
+// alerts for pods which restarted since the previous poll, or which exceed the
+// threshold for the first time, i.e. on the first poll or when they cross it
+fn restart_alerts(previous: &HashMap<String, u32>, pods: &[PodInfo], threshold: Option<u32>) -> Vec<String> {
+    let mut alerts = Vec::new();
+    for pod_info in pods {
+        let count = pod_info.restart_count();
+        let previous_count = previous.get(&pod_info.name).copied();
+        if let Some(previous_count) = previous_count {
+            if count > previous_count {
+                alerts.push(format!("{} restarted ({} -> {})", pod_info.name, previous_count, count));
+            }
+        }
+        if let Some(threshold) = threshold {
+            if count > threshold && previous_count.is_none_or(|p| p <= threshold) {
+                alerts.push(format!("{} exceeds {} restarts ({})", pod_info.name, threshold, count));
+            }
+        }
+    }
+    alerts
+}
+
+#[test]
+fn test_restart_alerts() {
+    let pods: Vec<PodInfo> = vec![
+        ["sophon-kg-sophon2-bf9769d97-4hqgv", "1/1", "Running",          "1",  "56m", "172.26.0.124", "kg-node43", "<none>", "<none>"],
+        ["sophon-kg-sophon2-bf9769d97-fgpnn", "0/1", "CrashLoopBackOff", "23", "12d", "172.26.0.125", "kg-node44", "<none>", "<none>"],
+    ].into_iter().map(test_pod).collect();
+    // first poll only reports pods above the threshold
+    assert_eq!(restart_alerts(&HashMap::new(), &pods, None), Vec::<String>::new());
+    assert_eq!(restart_alerts(&HashMap::new(), &pods, Some(5)), vec!["sophon-kg-sophon2-bf9769d97-fgpnn exceeds 5 restarts (23)"]);
+    let mut previous = HashMap::new();
+    previous.insert("sophon-kg-sophon2-bf9769d97-4hqgv".to_string(), 0);
+    previous.insert("sophon-kg-sophon2-bf9769d97-fgpnn".to_string(), 23);
+    assert_eq!(restart_alerts(&previous, &pods, Some(5)), vec!["sophon-kg-sophon2-bf9769d97-4hqgv restarted (0 -> 1)"]);
+    assert_eq!(restart_alerts(&previous, &pods, Some(0)), vec![
+        "sophon-kg-sophon2-bf9769d97-4hqgv restarted (0 -> 1)",
+        "sophon-kg-sophon2-bf9769d97-4hqgv exceeds 0 restarts (1)",
+    ]);
+}
+
+// print the lines a command writes as they come, after the prefix if any,
+// masked by the redactor if any
+fn stream_lines(cmd: &str, prefix: Option<&str>, redactor: Option<&Redactor>) {
+    let child = signals::spawn_in_group(std::process::Command::new("sh").arg("-c").arg(cmd).stdout(std::process::Stdio::piped()));
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            log::warn!("cannot run {}: {}", cmd, e);
+            return;
+        }
+    };
+    if let Some(stdout) = child.stdout.take() {
+        for line in io::BufReader::new(stdout).lines().map_while(Result::ok) {
+            let line = match redactor {
+                Some(redactor) => redactor.redact(&line),
+                None => line,
+            };
+            match prefix {
+                Some(prefix) => println!("{} {}", prefix, line),
+                None => println!("{}", line),
+            }
+        }
+    }
+    child.wait().ok();
+    signals::forget(&child);
+}
+
+// the pod to follow once the log stream or port-forward of the current pod
+// ends: the pod itself while it exists, otherwise a running pod not seen before
+fn next_followed_pod(pods: &[PodInfo], current: &str, seen: &HashSet<String>) -> Option<String> {
+    if let Some(pod_info) = pods.iter().find(|pod_info| pod_info.name == current) {
+        return if pod_info.status == "Running" { Some(pod_info.name.clone()) } else { None };
+    }
+    pods.iter()
+        .find(|pod_info| pod_info.status == "Running" && !seen.contains(&pod_info.name))
+        .map(|pod_info| pod_info.name.clone())
+}
+
+#[test]
+fn test_next_followed_pod() {
+    let pods = vec![
+        test_pod(["kg-old", "1/1", "Running", "0", "12d", "172.26.0.124", "kg-node43", "<none>", "<none>"]),
+        test_pod(["kg-new", "1/1", "Running", "0", "1m", "172.26.0.125", "kg-node44", "<none>", "<none>"]),
+    ];
+    let seen: HashSet<String> = ["kg-old".to_string()].iter().cloned().collect();
+    assert_eq!(next_followed_pod(&pods, "kg-old", &seen), Some("kg-old".to_string()));
+    assert_eq!(next_followed_pod(&pods[1..], "kg-old", &seen), Some("kg-new".to_string()));
+    assert_eq!(next_followed_pod(&pods[..1], "kg-gone", &seen), None);
+}
+
+// pod count and total restarts of every node, busiest node first
+fn nodes_load(pods: &[PodInfo]) -> Vec<(String, usize, u32)> {
+    pods.iter()
+        .into_group_map_by(|pod_info| pod_info.node.clone())
+        .into_iter()
+        .map(|(node, pods)| {
+            let restarts = pods.iter().map(|pod_info| pod_info.restart_count()).sum();
+            (node, pods.len(), restarts)
+        })
+        .sorted_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)))
+        .collect()
+}
+
+#[test]
+fn test_nodes_load() {
+    let pods: Vec<PodInfo> = vec![
+        ["sophon-kg-sophon2-bf9769d97-4hqgv",  "1/1", "Running",          "1",  "56m", "172.26.0.124", "kg-node43", "<none>", "<none>"],
+        ["sophon-kg-sophon2-bf9769d97-fgpnn",  "0/1", "CrashLoopBackOff", "23", "12d", "172.26.0.125", "kg-node44", "<none>", "<none>"],
+        ["sophon-ui-sophon2-79c997dd8c-vkths", "1/1", "Running",          "4",  "9d",  "172.26.0.126", "kg-node43", "<none>", "<none>"],
+    ].into_iter().map(test_pod).collect();
+    assert_eq!(nodes_load(&pods), vec![
+        ("kg-node43".to_string(), 2, 5),
+        ("kg-node44".to_string(), 1, 23),
+    ]);
+}
+
+// the columns of a listed pod, as `kubectl get po -owide` prints them
+fn pod_info_of(pod: &pod::Pod, now: u64) -> Option<PodInfo> {
+    match PodInfo::new(&pod.namespace, pod.columns(now)) {
+        Ok(pod_info) => Some(PodInfo {default_container: pod.default_container.clone(), containers: pod.containers.clone(), ..pod_info}),
+        Err(reason) => {
+            log::warn!("skipping pod {}: {}", pod.name, reason);
+            None
+        }
+    }
+}
+
+// the kubectl command with the namespace of a pod, unless already there
+fn namespaced_kub(kub: &str, namespace: &str) -> String {
+    let namespace_arg = format!(" -n {}", shell_quote(namespace));
+    if namespace.is_empty() || kub.ends_with(&namespace_arg) || kub.contains(&format!("{} ", namespace_arg)) {
+        kub.to_string()
+    } else {
+        format!("{}{}", kub, namespace_arg)
+    }
+}
+
+#[test]
+fn test_namespaced_kub() {
+    assert_eq!(namespaced_kub("kubectl", "sophon"), "kubectl -n sophon");
+    assert_eq!(namespaced_kub("kubectl -n sophon", "sophon"), "kubectl -n sophon");
+    assert_eq!(namespaced_kub("kubectl -n sophon --as jane", "sophon"), "kubectl -n sophon --as jane");
+    assert_eq!(namespaced_kub("kubectl -n sophon-dev", "sophon"), "kubectl -n sophon-dev -n sophon");
+    assert_eq!(namespaced_kub("kubectl", ""), "kubectl");
+}
+
+// the patterns of a patterns file, one per line, `#` starting a comment
+fn read_patterns(content: &str) -> Vec<String> {
+    content.lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn test_read_patterns() {
+    let content = "# pods restarted after the certificates are rotated\nkg-sophon2\n\n  gateway  # the ingress one\n";
+    assert_eq!(read_patterns(content), vec!["kg-sophon2", "gateway"]);
+}
+
+// the pods containing a pattern given in batch, where there is nobody to
+// ask: the only one, else those --first, --all-matches or --select pick, else why none
+fn pick_without_asking(candidates: Vec<PodInfo>, first: bool, all: bool, select: Option<&str>) -> Result<Vec<PodInfo>, String> {
+    if candidates.is_empty() {
+        return Err("no pod named like it".to_string());
+    }
+    if candidates.len() == 1 || first {
+        return Ok(candidates.into_iter().take(1).collect());
+    }
+    if all {
+        return Ok(candidates);
+    }
+    let select = match select {
+        Some(select) => select,
+        None => return Err(format!("ambiguous, {} pods named like it, pass --first, --all-matches or --select", candidates.len())),
+    };
+    match parse_choice(&select.trim().to_lowercase(), &get_candidate_option(candidates.len()), candidates.len()) {
+        Some(Choice::Default) => Ok(candidates.into_iter().take(1).collect()),
+        Some(Choice::Index(choice_index)) => Ok(vec![candidates[choice_index].clone()]),
+        Some(Choice::All) => Ok(candidates),
+        None => Err(format!("{} is not one of the {} pods named like it", select, candidates.len())),
+    }
+}
+
+#[test]
+fn test_pick_without_asking() {
+    let candidates: Vec<PodInfo> = vec![
+        ["sophon-kg-sophon2-bf9769d97-4hqgv", "1/1", "Running",          "0",  "56m", "172.26.0.124", "kg-node43", "<none>", "<none>"],
+        ["sophon-kg-sophon2-bf9769d97-fgpnn", "0/1", "CrashLoopBackOff", "23", "12d", "172.26.0.125", "kg-node44", "<none>", "<none>"],
+    ].into_iter().map(test_pod).collect();
+    let picked = |candidates: &[PodInfo], first, all, select| -> Result<Vec<String>, String> {
+        pick_without_asking(candidates.to_vec(), first, all, select).map(|pods| pods.into_iter().map(|p| p.name).collect())
+    };
+    assert_eq!(picked(&candidates[1..], false, false, None), Ok(vec!["sophon-kg-sophon2-bf9769d97-fgpnn".to_string()]));
+    assert_eq!(picked(&candidates, false, false, None), Err("ambiguous, 2 pods named like it, pass --first, --all-matches or --select".to_string()));
+    assert_eq!(picked(&candidates, true, false, None), Ok(vec!["sophon-kg-sophon2-bf9769d97-4hqgv".to_string()]));
+    assert_eq!(picked(&candidates, false, true, None).map(|names| names.len()), Ok(2));
+    assert_eq!(picked(&candidates, false, false, Some("b")), Ok(vec!["sophon-kg-sophon2-bf9769d97-fgpnn".to_string()]));
+    assert_eq!(picked(&[], false, false, None), Err("no pod named like it".to_string()));
 }
 
-impl From<(&str, &str, &str, &str, &str, &str, &str, &str, &str)> for PodInfo {
-    fn from(t: (&str, &str, &str, &str, &str, &str, &str, &str, &str)) -> PodInfo {
-        PodInfo {
-            name: t.0.to_string(),
-            ready: t.1.to_string(),
-            status: t.2.to_string(),
-            restarts: t.3.to_string(),
-            age: t.4.to_string(),
-            ip: t.5.to_string(),
-            node: t.6.to_string(),
-            nominated_node: t.7.to_string(),
-            readiness_gates: t.8.to_string(),
+// the choice given to --select among the candidates of every page, or stop
+// listing the valid choices
+fn select_choice(select: &str, names: &[&str]) -> Choice {
+    // a name printed by --list-candidates-only
+    if let Some(position) = names.iter().position(|name| *name == select.trim()) {
+        return Choice::Index(position);
+    }
+    let choices = get_candidate_option(names.len());
+    match parse_choice(&select.trim().to_lowercase(), &choices, names.len()) {
+        Some(choice) => choice,
+        None => {
+            log::error!("{} is not a valid choice, pick one of:", select);
+            for (i, name) in names.iter().enumerate() {
+                match choices.chars().nth(i) {
+                    Some(x) => log::error!("{}/{}: {}", i + 1, x, name),
+                    None => log::error!("{}: {}", i + 1, name),
+                }
+            }
+            process::exit(1)
         }
     }
 }
 
-impl fmt::Display for PodInfo {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
-            self.name, self.ready, self.status, self.restarts, self.age, self.ip, self.node, self.nominated_node, self.readiness_gates)
+// stop when a choice is needed but nobody is there to type it, under cron
+// or in a pipe, rather than taking the end of stdin for the default
+fn exit_if_not_interactive(matches: usize, slice: &str) {
+    if !interactive() {
+        log::error!("ambiguous match in non-interactive mode: {} candidates named like {}, pass --first or --all-matches to pick without asking", matches, slice);
+        process::exit(1);
     }
 }
 
-impl Manager {
-    pub fn new(args: Args) -> Self {
-        let kub = get_kub(&args);
-        Manager { args, kub }
-    }
+// whether questions can be asked on stdin
+fn interactive() -> bool {
+    atty::is(atty::Stream::Stdin) || std::env::var_os(INTERACTIVE_ENV).is_some()
+}
 
-    pub fn run(&self) -> Result<String> {
-        let command = &self.args.cmd.clone().unwrap();
-        let kub_commands = self.get_kub_command(command);
-        let mut command_output = Vec::new();
-        for kub_command in kub_commands {
-            log::info!("{}", kub_command);
-            // following code block is not executed while debugging
-            if cfg!(not(debug_assertions)) {
-                let output = std::process::Command::new("sh")
-                    .arg("-c")
-                    .arg(kub_command)
-                    .output().expect("failed to execute cmd");
-                command_output.push(String::from_utf8_lossy(&output.stdout).to_string());
-            }
+// let the user pick one of a few names, the first one on plain Enter
+fn pick_name(names: &[String]) -> String {
+    let choices = get_candidate_option(names.len());
+    for (i, name) in names.iter().enumerate() {
+        match choices.chars().nth(i) {
+            Some(x) => log::info!("{}/{}: {}{}", i + 1, x, name, if i == 0 { "\t(default)" } else { "" }),
+            None => log::info!("{}: {}", i + 1, name),
         }
-        Ok(command_output.join("\n"))
     }
-
-    fn get_kub_command(&self, command: &Command) -> Vec<String> {
-        let get_pod_name = || -> &str {
-            match command {
-                Command::DELETE { name } => name,
-                Command::DESCRIBE {name} => name,
-                Command::IMAGE {name} => name,
-                Command::CONTAINER {name} => name,
-                Command::LOGS {name} => name,
-                Command::EXEC {name} => name,
-            }
-        };
-        let pod_name_slice = get_pod_name();
-        let insert_middle_name = self.args.middle.is_some();
-        let pod_name_slice = if insert_middle_name {
-            let middle_name = self.args.middle.as_ref().unwrap();
-            filled_with_middle_name(pod_name_slice, &middle_name[..])
-        } else {
-            pod_name_slice.to_string()
-        };
-        let pod_name_slice = &pod_name_slice;
-        let all_pods = self.list_pods();
-        let candidate_pods = self.get_candidate_pod(all_pods.to_vec(), pod_name_slice, false);
-        if candidate_pods.len() == 0 {
-            log::info!("no such a pod named like {} found!", pod_name_slice);
-            log::info!("trying fuzzy match...");
-            let candidate_pods_fuzzy = self.get_candidate_pod(all_pods.to_vec(), pod_name_slice, true);
-            if candidate_pods_fuzzy.len() == 0 {
-                log::info!("fuzzy match has no results...");
-                process::exit(0);
-            } else {
-                handle_multiple_results(&self.kub[..], command, candidate_pods_fuzzy)
-            }
+    log::info!("type your choice, or press Enter for the default...");
+    let mut input = String::new();
+    stdin().read_line(&mut input).unwrap();
+    match parse_choice(&input.trim().to_lowercase(), &choices, names.len()) {
+        Some(Choice::Default) => names[0].clone(),
+        Some(Choice::Index(choice_index)) => names[choice_index].clone(),
+        _ => {
+            log::error!("input is not a valid option");
+            process::exit(1)
         }
-        else if candidate_pods.len() > 1 {
-            log::info!("multiple pods named like {} found!", pod_name_slice);
-            log::info!("possible choices:");
-            handle_multiple_results(&self.kub[..], command, candidate_pods)
+    }
+}
+
+// ask for a revision to roll back to, None on plain Enter for the previous one
+fn ask_revision() -> Option<u32> {
+    log::info!("type a revision to roll back to, or press Enter for the previous one...");
+    let mut input = String::new();
+    stdin().read_line(&mut input).unwrap();
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+    match input.parse() {
+        Ok(revision) => Some(revision),
+        Err(_) => {
+            log::error!("{} is not a revision", input);
+            process::exit(1)
         }
-        else {
-            vec![get_kub_command(&self.kub[..], command, &candidate_pods[0].name[..])]
+    }
+}
+
+// quote an argument for sh, so it reaches the remote command unchanged
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c)) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+#[test]
+fn test_shell_quote() {
+    assert_eq!(shell_quote("date"), "date");
+    assert_eq!(shell_quote("/tmp/a-b_c.log"), "/tmp/a-b_c.log");
+    assert_eq!(shell_quote("echo $HOME"), "'echo $HOME'");
+    assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    assert_eq!(shell_quote(""), "''");
+}
+
+// the arguments of a raw kubectl command, with the name and namespace of the pod filled in
+fn fill_raw_args(args: &[String], pod_info: &PodInfo) -> Vec<String> {
+    args.iter().map(|arg| arg.replace("{pod}", &pod_info.name).replace("{ns}", &pod_info.namespace)).collect()
+}
+
+#[test]
+fn test_fill_raw_args() {
+    let pod_info = PodInfo {namespace: "sophon".to_string(), ..test_pod(["kg-1", "1/1", "Running", "0", "12d", "172.26.0.124", "kg-node43", "<none>", "<none>"])};
+    let args: Vec<String> = ["get", "events", "-n", "{ns}", "--field-selector", "involvedObject.name={pod}"].iter().map(|arg| arg.to_string()).collect();
+    assert_eq!(fill_raw_args(&args, &pod_info).join(" "), "get events -n sophon --field-selector involvedObject.name=kg-1");
+}
+
+// the URL of the template for a pod, in `namespace` unless listed in its own
+fn fill_url_template(template: &str, pod_info: &PodInfo, namespace: &str) -> String {
+    let namespace = if pod_info.namespace.is_empty() { namespace } else { &pod_info.namespace };
+    template
+        .replace("{pod}", &pod_info.name)
+        .replace("{namespace}", namespace)
+        .replace("{node}", &pod_info.node)
+}
+
+#[test]
+fn test_fill_url_template() {
+    let mut pod_info = test_pod(["kg-1", "1/1", "Running", "0", "12d", "172.26.0.124", "kg-node43", "<none>", "<none>"]);
+    let template = "https://grafana/d/pods?var-namespace={namespace}&var-pod={pod}&var-node={node}";
+    assert_eq!(fill_url_template(template, &pod_info, "sophon"), "https://grafana/d/pods?var-namespace=sophon&var-pod=kg-1&var-node=kg-node43");
+    pod_info.namespace = "kg".to_string();
+    assert_eq!(fill_url_template(template, &pod_info, "sophon"), "https://grafana/d/pods?var-namespace=kg&var-pod=kg-1&var-node=kg-node43");
+}
+
+// command printing the current context, then the namespace, as fill_prompt reads them
+fn context_namespace_cmd(kub: &str) -> String {
+    format!(
+        "{kub} config current-context 2>/dev/null; {kub} config view --minify -o jsonpath='{{..namespace}}' 2>/dev/null",
+        kub = kub
+    )
+}
+
+// the current context on the first line of the output, the namespace on the
+// second, which is left out by kubectl when it is the default one. Context
+// and namespace set for rkl itself take precedence.
+fn fill_prompt(format: &str, output: &str, context: Option<&str>, namespace: Option<&str>) -> String {
+    let mut lines = output.lines().map(str::trim);
+    let context = context.or(lines.next().filter(|context| !context.is_empty())).unwrap_or("none");
+    let namespace = namespace.or(lines.next().filter(|namespace| !namespace.is_empty())).unwrap_or("default");
+    format.replace("{context}", context).replace("{namespace}", namespace)
+}
+
+#[test]
+fn test_fill_prompt() {
+    assert_eq!(fill_prompt("{context}/{namespace}", "prod\nsophon", None, None), "prod/sophon");
+    assert_eq!(fill_prompt("k8s:{context}", "prod\n", None, None), "k8s:prod");
+    assert_eq!(fill_prompt("{context}/{namespace}", "", None, None), "none/default");
+    assert_eq!(fill_prompt("{context}/{namespace}", "prod\nsophon", None, Some("kube-system")), "prod/kube-system");
+}
+
+fn fill_ssh_template(template: &str, node: &str, address: &str) -> String {
+    template.replace("{address}", address).replace("{node}", node)
+}
+
+#[test]
+fn test_fill_ssh_template() {
+    assert_eq!(fill_ssh_template(DEFAULT_SSH_COMMAND, "kg-node43", "172.26.0.124"), "ssh 172.26.0.124");
+    assert_eq!(fill_ssh_template("ssh -J bastion root@{node}", "kg-node43", "172.26.0.124"), "ssh -J bastion root@kg-node43");
+}
+
+// "8080" forwards the same local port, "9000:8080" local port 9000
+fn parse_port_mapping(port: &str) -> Option<(Option<u16>, u16)> {
+    match port.split_once(':') {
+        Some((local, remote)) => Some((Some(local.parse().ok()?), remote.parse().ok()?)),
+        None => Some((None, port.parse().ok()?)),
+    }
+}
+
+#[test]
+fn test_parse_port_mapping() {
+    assert_eq!(parse_port_mapping("8080"), Some((None, 8080)));
+    assert_eq!(parse_port_mapping("9000:8080"), Some((Some(9000), 8080)));
+    assert_eq!(parse_port_mapping("http"), None);
+    assert_eq!(parse_port_mapping("9000:"), None);
+}
+
+// a local port nobody listens on, as chosen by the system
+fn free_local_port() -> Result<u16> {
+    Ok(std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+// hand the text to the clipboard tool of the platform
+fn copy_to_clipboard(text: &str) {
+    let tool = if cfg!(target_os = "macos") {
+        "pbcopy"
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        "wl-copy"
+    } else {
+        "xclip -selection clipboard"
+    };
+    log::info!("copying {} with {}", text, tool);
+    // commands are not executed while debugging
+    if cfg!(debug_assertions) {
+        return;
+    }
+    let copied = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(tool)
+        .stdin(process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            child.stdin.take().expect("stdin is piped").write_all(text.as_bytes())?;
+            child.wait()
+        });
+    match copied {
+        Ok(status) if status.success() => {}
+        Ok(status) => log::warn!("failed to copy to the clipboard, {} {}", tool, status),
+        Err(e) => log::warn!("failed to copy to the clipboard: {}", e),
+    }
+}
+
+// where the config file is, or why there is none
+fn config_location() -> String {
+    match paths::config_file() {
+        Some(path) if path.exists() => path.display().to_string(),
+        Some(path) => format!("{} (not created yet)", path.display()),
+        None => "no config file, HOME is not set".to_string(),
+    }
+}
+
+// problems of the config file and of the env overrides, exiting with 1 if
+// there are any so scripts can check the config before using it
+fn validate_config() -> Result<String> {
+    let location = config_location();
+    let (mut config, problems) = Config::parse_checked(&config::read_file());
+    let mut problems: Vec<String> = problems.into_iter().map(|problem| format!("{}: {}", location, problem)).collect();
+    problems.extend(config.override_checked(config::env_value));
+    problems.extend(config.check());
+    if !problems.is_empty() {
+        for problem in problems {
+            log::warn!("{}", problem);
         }
+        log::info!("invalid config");
+        process::exit(1);
     }
+    Ok(format!("{} is valid", location))
+}
 
-    fn get_candidate_pod(&self, all_pods: Vec<PodInfo>, pod_name_slice: &str, fuzzy_match: bool) -> Vec<PodInfo> {
-        if !fuzzy_match {
-            all_pods.into_iter().filter(
-                |pod_info| pod_info.name.contains(pod_name_slice)
-            ).collect()
-        } else {
-            all_pods.into_iter().sorted_by(
-                |a, b|
-                    Jaccard::new(1).str_distance(&a.name, pod_name_slice).partial_cmp(
-                    &Jaccard::new(1).str_distance(&b.name, pod_name_slice)).unwrap()
-                ).take(MAX_CANDIDATE_SIZE).collect()
+// ask for a value, the default on plain Enter
+fn ask(question: &str, default: &str) -> String {
+    if default.is_empty() {
+        log::info!("{}:", question);
+    } else {
+        log::info!("{} [{}]:", question, default);
+    }
+    let mut input = String::new();
+    stdin().read_line(&mut input).unwrap();
+    match input.trim() {
+        "" => default.to_string(),
+        input => input.to_string(),
+    }
+}
+
+// ask the user a question on stderr, true if answered with yes
+fn confirm(question: &str) -> bool {
+    log::info!("{}...", question);
+    let mut input = String::new();
+    stdin().read_line(&mut input).unwrap();
+    input.trim().eq_ignore_ascii_case("yes")
+}
+
+// fetch restart counts and last termination reasons of the containers of the given pods
+fn list_container_restarts(kub: &str, pod_names: &[&str]) -> HashMap<String, Vec<ContainerRestarts>> {
+    if cfg!(debug_assertions) || pod_names.is_empty() {
+        // commands are not executed while debugging
+        return HashMap::new();
+    }
+    let cmd = format!("{} get po {} {}", kub, pod_names.join(" "), container::restarts_jsonpath());
+    container::parse_restarts(&shell_output(&cmd))
+}
+
+// fetch the newest warning event of every pod
+fn list_warning_events(kub: &str) -> HashMap<String, Event> {
+    if cfg!(debug_assertions) {
+        // commands are not executed while debugging
+        return HashMap::new();
+    }
+    let cmd = format!("{} get events {}", kub, event::warning_events_args());
+    event::newest_by_pod(&shell_output(&cmd))
+}
+
+// run a read-only helper command through sh and return its stdout, giving
+// up on everything once it times out
+fn shell_output(cmd: &str) -> String {
+    match capture_retrying(cmd) {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
+        Err(e) => {
+            log::warn!("{}", e);
+            process::exit(1);
         }
     }
+}
 
-    fn list_pods(&self) -> Vec<PodInfo> {
-        if cfg!(debug_assertions) {
-            // debug code
-            let test_pod = "
-                sophon-apimanager-sophon2-58f4b7965-n99hz                      1/1     Running             4          12d     172.26.0.124   kg-node43   <none>           <none>
-                sophon-approval-sophon2-7748d4b87b-rt8zr                       1/1     Running             4          12d     172.26.0.124   kg-node43   <none>           <none>
-                sophon-audit-sophon2-654889f8c-g8xjc                           1/1     Running             4          12d     172.26.0.124   kg-node43   <none>           <none>
-                sophon-base-sophon2-557b9f49d4-xf95j                           1/1     Running             0          9d      172.26.0.124   kg-node43   <none>           <none>
-                sophon-gateway-sophon2-6dbf875495-dckc4                        1/1     Running             5          12d     172.26.0.124   kg-node43   <none>           <none>
-                sophon-jobmanager-sophon2-5f4df546f6-pld27                     1/1     Running             4          12d     172.26.0.124   kg-node43   <none>           <none>
-                sophon-kg-sophon2-bf9769d97-4hqgv                              1/1     Running             0          56m     172.26.0.124   kg-node43   <none>           <none>
-                sophon-notebook-sophon2-57f5c77786-8lpkw                       1/1     Running             0          20h     172.26.0.124   kg-node43   <none>           <none>
-                sophon-notification-sophon2-6bc6b754ff-59nbc                   1/1     Running             4          12d     172.26.0.124   kg-node43   <none>           <none>
-                sophon-resource-sophon2-5fc7f9dcb7-j7srl                       1/1     Running             4          12d     172.26.0.124   kg-node43   <none>           <none>
-                sophon-retrieve-sophon2-f5789bdb4-r7qcg                        1/1     Running             4          12d     172.26.0.124   kg-node43   <none>           <none>
-                sophon-session-sophon2-58cd56dbf9-slcjp                        1/1     Running             4          10d     172.26.0.124   kg-node43   <none>           <none>
-                sophon-share-sophon2-7b5795b4d4-gcqwv                          1/1     Running             4          12d     172.26.0.124   kg-node43   <none>           <none>
-                sophon-ui-sophon2-79c997dd8c-vkths                             1/1     Running             1          9d      172.26.0.124   kg-node43   <none>           <none>
-                sophon-user-sophon2-6586dd74c4-r4ndp                           1/1     Running             4          12d     172.26.0.124   kg-node43   <none>           <none>
-            ";
-            let kub_info: Vec<PodInfo> = test_pod.trim().split("\n").map(convert_to_kub_info).collect();
-            kub_info
-        } else {
-            // release code
-            let cmd = format!("{} get po -owide | tail -n+2", self.kub);
-            log::info!("{}", cmd);
-            let output = std::process::Command::new("sh")
-                .arg("-c")
-                .arg(&cmd)
-                .output()
-                .expect("failed to execute cmd");
-            let output_message = String::from_utf8_lossy(&output.stdout).to_string();
-            if output_message.trim().is_empty() {
-                Vec::new()
-            } else {
-                output_message.trim().split("\n").map(convert_to_kub_info).collect()
-            }
+// whether the command talks to the user through the terminal, which is then
+// handed over to kubectl instead of capturing its output
+fn needs_terminal(command: &Command) -> bool {
+    matches!(command, Command::EXEC {all: false, ..} | Command::RAW {..})
+}
+
+#[test]
+fn test_needs_terminal() {
+    let exec = |all: bool, command: &[&str]| Command::EXEC {name: "kg".to_string(), all, container: None, max_duration: None, command: command.iter().map(|arg| arg.to_string()).collect()};
+    assert!(needs_terminal(&exec(false, &[])));
+    assert!(!needs_terminal(&exec(true, &["date"])));
+    assert!(get_kub_command("kubectl", &exec(false, &[]), "kg-1").ends_with(" kg-1 -- sh -c 'command -v bash >/dev/null && exec bash || exec sh'"));
+    assert_eq!(get_kub_command("kubectl", &exec(true, &["date"]), "kg-1"), "kubectl exec kg-1 -- date");
+}
+
+// the exit code of a failed command, 128 plus the signal for one killed by a
+// signal, the way shells report it
+fn exit_code(status: process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return 128 + signal;
+    }
+    status.code().unwrap_or(1)
+}
+
+// the exit code of commands run on several pods: that of the first one which
+// failed, 1 for one which could not run at all
+fn first_failure(codes: &[Option<i32>]) -> i32 {
+    codes.iter().map(|code| code.unwrap_or(1)).find(|&code| code != 0).unwrap_or(0)
+}
+
+#[test]
+fn test_first_failure() {
+    assert_eq!(first_failure(&[Some(0), Some(0)]), 0);
+    assert_eq!(first_failure(&[Some(0), Some(3), Some(1)]), 3);
+    assert_eq!(first_failure(&[Some(0), None]), 1);
+}
+
+// flags of kubectl exec passing stdin on, and the terminal too when rkl runs
+// in one, so piped data like `cat dump.sql | rkl exec db -- psql` reaches the command
+fn terminal_flags() -> &'static str {
+    if atty::is(atty::Stream::Stdin) && atty::is(atty::Stream::Stdout) { "-it" } else { "-i" }
+}
+
+// whether the command deletes pods, needing a reason when require_reason is set
+fn destructive(command: &Command) -> bool {
+    matches!(command, Command::DELETE {..} | Command::CLEANUP {..} | Command::CRASHING {action: Some(Action::Delete), ..})
+}
+
+// the pod a kubectl delete command of get_kub_command deletes
+fn deleted_pod(kub_command: &str) -> Option<String> {
+    kub_command.split(" delete po ").nth(1)?.split_whitespace().next().map(str::to_string)
+}
+
+#[test]
+fn test_deleted_pod() {
+    assert_eq!(deleted_pod("kubectl -n sophon delete po kg-1 --grace-period=0 --force").as_deref(), Some("kg-1"));
+    assert_eq!(deleted_pod("kubectl get po kg-1 -owide"), None);
+}
+
+// the verb and resource kubectl auth can-i checks for a command, None for
+// commands which are not run on pods or whose kubectl command is free form
+fn access(command: &Command) -> Option<(&'static str, &'static str)> {
+    match command {
+        Command::DELETE {..} | Command::CLEANUP {..} => Some(("delete", "pods")),
+        Command::EXEC {..} | Command::RUNSCRIPT {..} => Some(("create", "pods/exec")),
+        Command::PORTFORWARD {..} => Some(("create", "pods/portforward")),
+        Command::LOGS {..} => Some(("get", "pods/log")),
+        Command::ROLLBACK {..} => Some(("patch", "deployments")),
+        Command::DESCRIBE {..} | Command::IMAGE {..} | Command::CONTAINER {..} | Command::INFO {..} | Command::EXPORT {..} => Some(("get", "pods")),
+        _ => None,
+    }
+}
+
+// the namespace of a kubectl command built with namespaced_kub, empty for
+// the namespace of the kubectl command itself
+fn command_namespace(kub: &str, kub_command: &str) -> String {
+    kub_command.strip_prefix(kub)
+        .and_then(|rest| rest.strip_prefix(" -n "))
+        .and_then(|rest| rest.split(' ').next())
+        .unwrap_or("")
+        .to_string()
+}
+
+#[test]
+fn test_access() {
+    assert_eq!(access(&Action::Delete.to_command("kg".to_string())), Some(("delete", "pods")));
+    assert_eq!(access(&Action::Exec.to_command("kg".to_string())), Some(("create", "pods/exec")));
+    assert_eq!(access(&Command::VERSION), None);
+    assert_eq!(command_namespace("kubectl", "kubectl -n sophon delete po kg-1"), "sophon");
+    assert_eq!(command_namespace("kubectl -n sophon", "kubectl -n sophon delete po kg-1"), "");
+    assert_eq!(command_namespace("kubectl", "kubectl rollout undo deployment/kg"), "");
+}
+
+// kubectl commands which only read, allowed by `raw` in read-only mode
+static READ_VERBS: [&str; 7] = ["get", "describe", "logs", "top", "events", "explain", "api-resources"];
+
+// subcommands of `kubectl auth` which only read, the others reconcile RBAC roles
+static READ_AUTH_SUBCOMMANDS: [&str; 2] = ["can-i", "whoami"];
+
+// whether the arguments of `raw` run a kubectl command which only reads
+fn reads_only(args: &[String]) -> bool {
+    match args.first().map(String::as_str) {
+        Some("auth") => args.get(1).is_some_and(|subcommand| READ_AUTH_SUBCOMMANDS.contains(&subcommand.as_str())),
+        Some(verb) => READ_VERBS.contains(&verb),
+        None => false,
+    }
+}
+
+// the name of the command if it changes the cluster or gives a shell on it
+fn mutation(command: &Command) -> Option<&'static str> {
+    match command {
+        Command::DELETE {..} => Some("delete"),
+        Command::CLEANUP {..} => Some("cleanup"),
+        Command::EXEC {..} => Some("exec"),
+        Command::RUNSCRIPT {..} => Some("run-script"),
+        Command::ROLLBACK {..} => Some("rollback"),
+        Command::APPLY {dry_run: false, ..} => Some("apply"),
+        Command::NODESSH {..} => Some("node-ssh"),
+        Command::NODECONTAINERS {..} => Some("node-containers"),
+        Command::CRASHING {action: Some(Action::Delete), ..} => Some("crashing --action delete"),
+        Command::CRASHING {action: Some(Action::Exec), ..} => Some("crashing --action exec"),
+        Command::RAW {args, ..} if !reads_only(args) => Some("raw"),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_mutation() {
+    let raw = |args: &[&str]| Command::RAW {name: "kg".to_string(), args: args.iter().map(|arg| arg.to_string()).collect()};
+    assert_eq!(mutation(&raw(&["get", "events"])), None);
+    assert_eq!(mutation(&raw(&["label", "po", "{pod}", "debug=true"])), Some("raw"));
+    assert_eq!(mutation(&raw(&["auth", "can-i", "delete", "pods"])), None);
+    assert_eq!(mutation(&raw(&["auth", "reconcile", "-f", "rbac.yaml"])), Some("raw"));
+    assert_eq!(mutation(&Command::APPLY {path: "k8s".to_string(), dry_run: true, yes: false}), None);
+    assert_eq!(mutation(&Command::APPLY {path: "k8s".to_string(), dry_run: false, yes: true}), Some("apply"));
+    assert_eq!(mutation(&Command::CRASHING {name: None, action: Some(Action::Logs)}), None);
+    assert_eq!(mutation(&Action::Delete.to_command("kg".to_string())), Some("delete"));
+    assert_eq!(mutation(&Command::NODECONTAINERS {name: "kg".to_string(), template: None, inspect: false}), Some("node-containers"));
+}
+
+// whether running the command twice does no harm
+fn read_only(command: &Command) -> bool {
+    matches!(
+        command,
+        Command::DESCRIBE {..} | Command::IMAGE {..} | Command::CONTAINER {..} | Command::INFO {..} | Command::LOGS {..}
+            | Command::RESOURCES {..} | Command::HISTORYROLLOUT {..} | Command::WHYPENDING {..} | Command::DIFF {..} | Command::DRIFT {..} | Command::EXPORT {..}
+    )
+}
+
+// capture a read-only command, running it again while it fails for a reason
+// which may go away, like an apiserver restarting
+fn capture_retrying(cmd: &str) -> Result<process::Output> {
+    let policy = RETRY_POLICY.get().copied().unwrap_or_default();
+    let mut attempt = 0;
+    loop {
+        let output = capture(cmd)?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // kubectl warns on stderr about things like throttling even when it succeeds
+        if output.status.success() || attempt >= policy.retries || !retry::is_transient(&stderr) {
+            return Ok(output);
         }
+        let delay = policy.delay(attempt);
+        log::warn!("{}, retrying in {:.1}s", stderr.lines().last().unwrap_or("").trim(), delay.as_secs_f64());
+        std::thread::sleep(delay);
+        attempt += 1;
     }
 }
 
-fn convert_to_kub_info(s: &str) -> PodInfo {
-    let kub_output : (&str, &str, &str, &str, &str, &str, &str, &str, &str) = s.split_whitespace().collect_tuple().unwrap();
-    let pod_info: PodInfo = kub_output.into();
-    pod_info
+#[test]
+fn test_capture_retrying() {
+    // a command which succeeds is not run again, whatever it warns about
+    let runs = std::env::temp_dir().join(format!("rkl-retrying-{}", process::id()));
+    let cmd = format!("echo run >> {}; echo 'connection refused' >&2", shell_quote(&runs.to_string_lossy()));
+    assert!(capture_retrying(&cmd).unwrap().status.success());
+    assert_eq!(std::fs::read_to_string(&runs).unwrap(), "run\n");
+    std::fs::remove_file(&runs).ok();
 }
 
-fn handle_multiple_results(kub: &str, cmd: &Command, candidate_pods: Vec<PodInfo>) -> Vec<String> {
-    // get candidate size
-    let candidate_size = get_candidate_size();
-    log::info!("you are getting candidate size of {}, try to alter env RKL_CANDIDATE_SIZE to view more", candidate_size);
-    let choices = get_candidate_option(candidate_size);
-    for (x, y) in choices.chars().zip(candidate_pods.iter()) {
-        log::info!{"{}: {}", x, y};
+// run a command through sh capturing its output, within the timeout if one is
+// set, or replay its recorded output
+fn capture(cmd: &str) -> Result<process::Output> {
+    let fixtures = FIXTURES.get();
+    if let Some(fixtures) = fixtures.filter(|fixtures| fixtures.replaying()) {
+        return fixtures.load(cmd);
     }
-    log::info!("z: apply to all");
-    log::info!("type your choice...");
-    let mut input = String::new();
-    stdin().read_line(&mut input).unwrap();
-    let input_choice = &input.trim().to_lowercase();
-    if input_choice.len() != 1 || !choices.contains(input_choice) && input_choice != "z" {
-        log::error!("input is not a valid option");
-        process::exit(1)
-    } else {
-        let input_char: char = input_choice.chars().next().unwrap();
-        if input_char == 'z' {
-            let mut kub_cmds = Vec::new();
-            for candidate_idx in 0..candidate_size {
-                kub_cmds.push(get_kub_command(kub, cmd, &candidate_pods[candidate_idx].name[..]));
-            }
-            kub_cmds
-        } else {
-            let choice_index = choices.chars().position(|c| c == input_char).unwrap();
-            vec![get_kub_command(kub, cmd, &candidate_pods[choice_index].name[..])]
+    let output = capture_within(cmd, COMMAND_TIMEOUT.get().copied())?;
+    if let Some(fixtures) = fixtures {
+        if let Err(e) = fixtures.save(cmd, &output) {
+            log::warn!("failed to record {}: {}", cmd, e);
+        }
+    }
+    Ok(output)
+}
+
+// run a command through sh capturing its output, killing it with everything
+// it started once it runs longer than the timeout
+fn capture_within(cmd: &str, timeout: Option<Duration>) -> Result<process::Output> {
+    let mut command = process::Command::new("sh");
+    command.arg("-c").arg(cmd);
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return Ok(command.output().expect("failed to execute cmd")),
+    };
+    // a group of its own, so kubectl is killed too and not only sh, and no
+    // stdin, which is left to the commands it is piped to like exec
+    let mut child = signals::spawn_in_group(command.stdin(process::Stdio::null()).stdout(process::Stdio::piped()).stderr(process::Stdio::piped()))
+        .expect("failed to execute cmd");
+    // drain the pipes meanwhile, a command filling one would block forever
+    let read = |mut pipe: Box<dyn io::Read + Send>| std::thread::spawn(move || {
+        let mut content = Vec::new();
+        pipe.read_to_end(&mut content).map(|_| content)
+    });
+    let stdout = read(Box::new(child.stdout.take().unwrap()));
+    let stderr = read(Box::new(child.stderr.take().unwrap()));
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            signals::forget(&child);
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = process::Command::new("kill").args(["-KILL", "--", &format!("-{}", child.id())]).status();
+            let _ = child.kill();
+            let _ = child.wait();
+            signals::forget(&child);
+            bail!("timeout: gave up after {}s on {}", timeout.as_secs_f64(), cmd);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    Ok(process::Output {
+        status,
+        stdout: stdout.join().expect("reader thread panicked")?,
+        stderr: stderr.join().expect("reader thread panicked")?,
+    })
+}
+
+#[test]
+fn test_capture_within() {
+    let output = capture_within("echo sophon; echo oops >&2", Some(Duration::from_secs(5))).unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "sophon\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "oops\n");
+    let started = Instant::now();
+    let error = capture_within("sleep 5 | cat", Some(Duration::from_millis(100))).unwrap_err();
+    assert!(error.to_string().starts_with("timeout: gave up after 0.1s"));
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+// the command run in the container the pod names with the annotation
+// kubectl.kubernetes.io/default-container, if it names one and the command
+// runs in a single container none was given for, else in its first container
+// which is not a sidecar, rather than in the first one kubectl would pick
+fn in_default_container(command: &Command, pod_info: &PodInfo, sidecars: &[String]) -> Command {
+    let mut command = command.clone();
+    if let Command::LOGS {container: container @ None, all_containers: false, ..} | Command::EXEC {container: container @ None, ..} = &mut command {
+        let apps = container::app_containers(&pod_info.containers, sidecars);
+        if !pod_info.default_container.is_empty() {
+            *container = Some(pod_info.default_container.clone());
+        } else if apps.len() < pod_info.containers.len() {
+            *container = Some(apps[0].clone());
         }
     }
+    command
+}
+
+// a kubectl command with more arguments, put before what the shell does
+// with its output or input
+fn with_kubectl_args(kub_command: &str, kubectl_args: &[String]) -> String {
+    if kubectl_args.is_empty() {
+        return kub_command.to_string();
+    }
+    let args = kubectl_args.iter().map(|arg| shell_quote(arg)).join(" ");
+    match [" | ", " < "].iter().filter_map(|redirect| kub_command.find(redirect)).min() {
+        Some(end) => format!("{} {}{}", &kub_command[..end], args, &kub_command[end..]),
+        None => format!("{} {}", kub_command, args),
+    }
+}
+
+#[test]
+fn test_with_kubectl_args() {
+    let args = vec!["--tail".to_string(), "10".to_string()];
+    assert_eq!(with_kubectl_args("kubectl logs kg-1", &args), "kubectl logs kg-1 --tail 10");
+    assert_eq!(with_kubectl_args("kubectl logs kg-1 --timestamps | sed 's|^|[kg-1] |'", &args), "kubectl logs kg-1 --timestamps --tail 10 | sed 's|^|[kg-1] |'");
+    assert_eq!(with_kubectl_args("kubectl describe po kg-1 | grep Image", &[]), "kubectl describe po kg-1 | grep Image");
+}
+
+// the flag of kubectl choosing a container, if one is given
+fn container_arg(container: &Option<String>) -> String {
+    container.as_ref().map(|container| format!(" -c {}", shell_quote(container))).unwrap_or_default()
 }
 
 fn get_kub_command(kub: &str, command: &Command, pod_name: &str) -> String {
     match command {
-        Command::DELETE {name: _} => format!("{} delete po {}", kub, pod_name),
-        Command::DESCRIBE {name: _} => format!("{} describe po {}", kub, pod_name),
-        Command::LOGS {name: _} => format!("{} logs {}", kub, pod_name),
+        Command::CLEANUP {..} => format!("{} delete po {}", kub, pod_name),
+        // the script is fed through stdin, so nothing is left behind in the pod
+        Command::RUNSCRIPT {script, shell, args, ..} => format!(
+            "{} exec -i {} -- {} -s -- {} < {}",
+            kub, pod_name, shell_quote(shell), args.iter().map(|arg| shell_quote(arg)).join(" "), shell_quote(script)
+        ),
+        Command::HISTORYROLLOUT {..} => format!("{} rollout history deployment/{}", kub, pod_name),
+        Command::ROLLBACK {to_revision: None, ..} => format!("{} rollout undo deployment/{}", kub, pod_name),
+        Command::ROLLBACK {to_revision: Some(revision), ..} => format!("{} rollout undo deployment/{} --to-revision={}", kub, pod_name, revision),
+        // kubectl waits for the pod to be gone already, --wait only times it
+        Command::DELETE {force, ..} => {
+            let mut delete_command = format!("{} delete po {}", kub, pod_name);
+            if *force {
+                delete_command.push_str(" --grace-period=0 --force");
+            }
+            delete_command
+        }
+        Command::DESCRIBE {..} => format!("{} describe po {}", kub, pod_name),
+        Command::LOGS {merge_by_time, follow, all_containers, container, ..} => {
+            let mut logs_command = format!("{} logs {}{}", kub, pod_name, container_arg(container));
+            if *follow {
+                logs_command.push_str(" -f");
+            }
+            if *all_containers {
+                // kubectl prefixes every line with [pod/<pod>/<container>]
+                logs_command.push_str(" --all-containers --prefix");
+            }
+            if *merge_by_time {
+                logs_command.push_str(" --timestamps");
+                // prefix every line with the pod name, so lines can be told apart once merged
+                if !*all_containers {
+                    logs_command.push_str(&format!(" | sed 's|^|[{}] |'", pod_name));
+                }
+            }
+            logs_command
+        }
         Command::IMAGE {name: _} => format!("{} describe po {} | grep Image", kub, pod_name),
-        Command::CONTAINER {name: _} => format!("{} describe po {} | grep container", kub, pod_name),
-        Command::EXEC {name: _} => format!("{} exec -it {}", kub, pod_name),
+        Command::CONTAINER {name: _} => format!("{} get po {} {}", kub, pod_name, container::container_jsonpath()),
+        Command::INFO {name: _} => format!("{} get po {} {}", kub, pod_name, info::info_jsonpath()),
+        Command::EXEC {command, container, ..} if command.is_empty() => format!(
+            "{} exec {} {}{} -- sh -c {}", kub, terminal_flags(), pod_name, container_arg(container), shell_quote(SESSION_SHELL)
+        ),
+        // the command of a single pod gets the terminal, those of several pods run side by side without stdin
+        Command::EXEC {command, all: false, container, ..} => format!(
+            "{} exec {} {}{} -- {}", kub, terminal_flags(), pod_name, container_arg(container), command.iter().map(|arg| shell_quote(arg)).join(" ")
+        ),
+        Command::EXEC {command, container, ..} => format!(
+            "{} exec {}{} -- {}", kub, pod_name, container_arg(container), command.iter().map(|arg| shell_quote(arg)).join(" ")
+        ),
+        Command::CRASHING {..} | Command::OOM {..} | Command::RESTARTS {..} | Command::IMAGES {..} | Command::NODESLOAD | Command::PROMPT {..} | Command::CONFIG {..} | Command::REPORT {..} | Command::TOP {..} | Command::TOPNODES {..} | Command::RESOLVE {..} | Command::NODESSH {..} | Command::NODECONTAINERS {..} | Command::BENCH {..} | Command::RAW {..} | Command::VERSION | Command::FIND {..} => format!("{} get po {} -owide", kub, pod_name),
+        Command::PORTFORWARD {port, ..} => format!("{} port-forward {} {}", kub, pod_name, port),
+        // the opener gets the URL, filled in beforehand, as pod name
+        Command::OPEN {..} => format!("{} {}", if cfg!(target_os = "macos") { "open" } else { "xdg-open" }, shell_quote(pod_name)),
+        Command::EXPORT {kind: Some(kind), ..} => format!("{} get {} {} -o yaml", kub, kind, pod_name),
+        Command::DIFF {..} | Command::EXPORT {..} => format!("{} get po {} -o yaml", kub, pod_name),
+        // the path of the manifests is given as pod name
+        Command::APPLY {dry_run, ..} => format!("{} apply -f {}{}", kub, shell_quote(pod_name), if *dry_run { " --dry-run=server" } else { "" }),
+        Command::DRIFT {..} => format!("{} get po {} {}", kub, pod_name, workload::OWNER_ARGS),
+        Command::WHYPENDING {name: _} => format!(
+            "{kub} get events {events}; echo {sep}; {kub} get nodes {taints}",
+            kub = kub, events = pending::scheduling_events_args(pod_name), sep = pending::TAINTS_SEPARATOR, taints = pending::node_taints_args()
+        ),
+        Command::RESOURCES {name: _} => format!(
+            "{kub} get po {pod} {spec}; echo {sep}; {kub} top pod {pod} --containers --no-headers 2>&1",
+            kub = kub, pod = pod_name, spec = container::resources_jsonpath(), sep = container::USAGE_SEPARATOR
+        ),
     }
 }
 
+#[test]
+fn test_logs_command() {
+    let logs = |merge_by_time, all_containers| Command::LOGS {
+        name: "kg".to_string(), merge_by_time, color_lines: false, follow: false, include_new: false, all_containers, container: None, pretty_json: false, json_fields: None,
+    };
+    assert_eq!(get_kub_command("kubectl", &logs(false, true), "kg-1"), "kubectl logs kg-1 --all-containers --prefix");
+    assert_eq!(get_kub_command("kubectl", &logs(true, false), "kg-1"), "kubectl logs kg-1 --timestamps | sed 's|^|[kg-1] |'");
+    assert_eq!(get_kub_command("kubectl", &logs(true, true), "kg-1"), "kubectl logs kg-1 --all-containers --prefix --timestamps");
+}
+
+// turn the raw stdout of a kubectl command into the message shown to the user
+fn format_output(command: &Command, raw_output: String) -> Result<String> {
+    match command {
+        Command::CONTAINER {name: _} => {
+            let containers = container::parse_containers(&raw_output)?;
+            Ok(container::format_containers(&containers))
+        }
+        Command::INFO {name: _} => Ok(info::format_info(&info::parse_info(&raw_output)?)),
+        Command::RESOURCES {name: _} => {
+            let with_usage = !metrics::metrics_unavailable(&raw_output);
+            if !with_usage {
+                log::warn!("{}", metrics::METRICS_HINT);
+            }
+            let resources = container::parse_resources(&raw_output);
+            Ok(container::format_resources(&resources, with_usage))
+        }
+        Command::WHYPENDING {name: _} => Ok(pending::summarize(&raw_output)),
+        Command::EXPORT {..} => Ok(export::sanitize(&raw_output)),
+        Command::DESCRIBE {section: Some(section), ..} => Ok(describe::extract_sections(&raw_output, section.headers())),
+        Command::LOGS {pretty_json: true, json_fields, ..} => {
+            let fields: Option<Vec<&str>> = json_fields.as_ref().map(|fields| fields.split(',').map(str::trim).collect());
+            Ok(logs::pretty_json(&raw_output, fields.as_deref(), atty::is(atty::Stream::Stdout)))
+        }
+        _ => Ok(raw_output),
+    }
+}
 
 // if the input pod name is a component followed a version number, e.g. kg2,
 // can be converted to kg-sophon2 with `middle` name "-sophon"
@@ -254,11 +2999,96 @@ fn test_insert_middle_name() {
     assert_eq!(filled_with_middle_name("s", "-test"), "s-test");
 }
 
+// the page shown after the user typed `input`, None if it does not turn the page
+fn turn_page(input: &str, page: usize, pages: usize) -> Option<usize> {
+    match input {
+        ">" | "next" => Some(cmp::min(page + 1, pages.saturating_sub(1))),
+        "<" | "prev" => Some(page.saturating_sub(1)),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_turn_page() {
+    assert_eq!(turn_page(">", 0, 3), Some(1));
+    assert_eq!(turn_page("next", 2, 3), Some(2));
+    assert_eq!(turn_page("<", 1, 3), Some(0));
+    assert_eq!(turn_page("prev", 0, 3), Some(0));
+    assert_eq!(turn_page("a", 0, 3), None);
+}
+
+#[derive(Debug, PartialEq)]
+enum Choice {
+    Default,
+    All,
+    Index(usize),
+}
+
+// a letter or a number from 1 picks one of the candidates on the page,
+// an empty input picks the top ranked candidate
+fn parse_choice(input: &str, choices: &str, page_len: usize) -> Option<Choice> {
+    if input.is_empty() {
+        return Some(Choice::Default);
+    }
+    if input == "z" {
+        return Some(Choice::All);
+    }
+    if let Ok(number) = input.parse::<usize>() {
+        return if number >= 1 && number <= page_len { Some(Choice::Index(number - 1)) } else { None };
+    }
+    let mut chars = input.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => choices.chars().position(|x| x == c).map(Choice::Index),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_parse_choice() {
+    let choices = get_candidate_option(30);
+    assert_eq!(parse_choice("z", &choices, 30), Some(Choice::All));
+    assert_eq!(parse_choice("a", &choices, 30), Some(Choice::Index(0)));
+    assert_eq!(parse_choice("y", &choices, 30), Some(Choice::Index(24)));
+    assert_eq!(parse_choice("1", &choices, 30), Some(Choice::Index(0)));
+    assert_eq!(parse_choice("30", &choices, 30), Some(Choice::Index(29)));
+    assert_eq!(parse_choice("31", &choices, 30), None);
+    assert_eq!(parse_choice("0", &choices, 30), None);
+    assert_eq!(parse_choice("c", &get_candidate_option(2), 2), None);
+    assert_eq!(parse_choice("ab", &choices, 30), None);
+    assert_eq!(parse_choice("", &choices, 30), Some(Choice::Default));
+}
+
+// the position of the candidate a typed name picks: the one named so, else
+// the only one containing it, or how many contain it
+fn find_typed_name(input: &str, candidates: &[PodInfo]) -> Result<usize, usize> {
+    if let Some(position) = candidates.iter().position(|p| p.name == input) {
+        return Ok(position);
+    }
+    let matches: Vec<usize> = candidates.iter().positions(|p| p.name.contains(input)).collect();
+    match matches[..] {
+        [position] => Ok(position),
+        _ => Err(matches.len()),
+    }
+}
+
+#[test]
+fn test_find_typed_name() {
+    let candidates: Vec<PodInfo> = vec![
+        ["sophon-kg-sophon2-bf9769d97-4hqgv", "1/1", "Running",          "0",  "56m", "172.26.0.124", "kg-node43", "<none>", "<none>"],
+        ["sophon-kg-sophon2-bf9769d97-fgpnn", "0/1", "CrashLoopBackOff", "23", "12d", "172.26.0.125", "kg-node44", "<none>", "<none>"],
+        ["sophon-kg",                         "1/1", "Running",          "0",  "9d",  "172.26.0.124", "kg-node43", "<none>", "<none>"],
+    ].into_iter().map(test_pod).collect();
+    assert_eq!(find_typed_name("fgpnn", &candidates), Ok(1));
+    assert_eq!(find_typed_name("sophon-kg", &candidates), Ok(2));
+    assert_eq!(find_typed_name("bf9769d97", &candidates), Err(2));
+    assert_eq!(find_typed_name("mysql", &candidates), Err(0));
+}
+
 fn get_candidate_option(candidate_size: usize) -> String {
     // hard to index utf8, convert to chars and index with O(1) cost
     let alphabet = String::from_utf8(
         (b'a'..=b'z').collect()
-    ).unwrap().chars().take(cmp::min(MAX_CANDIDATE_SIZE, candidate_size)).collect();
+    ).unwrap().chars().take(cmp::min(MAX_LETTER_CHOICES, candidate_size)).collect();
     alphabet
 }
 
@@ -271,32 +3101,91 @@ fn test_get_candidate_option() {
     assert_eq!("abcdefghijklmnopqrstuvwxy", alphabet);
 }
 
-fn get_candidate_size() -> usize {
-    match std::env::var("RKL_CANDIDATE_SIZE").map(|s| s.parse()) {
-        Ok(Ok(n)) => if n < 1 {DEFAULT_CANDIDATE_SIZE} else {cmp::min(n, MAX_CANDIDATE_SIZE)}, // this is nested Result, env::var could fail && parse could fail
-        _ => DEFAULT_CANDIDATE_SIZE // if it fails for whatever cause, set as DEFAULT_CANDIATE_SIZE
+// candidate_size from the config file or env RKL_CANDIDATE_SIZE
+fn get_candidate_size(candidate_size: Option<usize>) -> usize {
+    match candidate_size {
+        Some(n) => if n < 1 {DEFAULT_CANDIDATE_SIZE} else {cmp::min(n, MAX_CANDIDATE_SIZE)},
+        None => DEFAULT_CANDIDATE_SIZE // if it is not set or invalid, set as DEFAULT_CANDIATE_SIZE
     }
 }
 
 #[test]
 fn test_get_candiate_size() {
-    // without env "RKL_CANDIDATE_SIZE", get default size
-    assert_eq!(get_candidate_size(), DEFAULT_CANDIDATE_SIZE);
-    std::env::set_var("RKL_CANDIDATE_SIZE", "100");
+    let size_of = |value: &str| {
+        let mut config = Config::default();
+        config.override_with(|key| if key == "candidate_size" { Some(value.to_string()) } else { None });
+        get_candidate_size(config.candidate_size)
+    };
+    // without candidate size, get default size
+    assert_eq!(get_candidate_size(None), DEFAULT_CANDIDATE_SIZE);
     // for size that is too large, get max size
-    assert_eq!(get_candidate_size(), MAX_CANDIDATE_SIZE);
+    assert_eq!(size_of("1000"), MAX_CANDIDATE_SIZE);
     // for invalid size, get default size
-    std::env::set_var("RKL_CANDIDATE_SIZE", "-1");
-    assert_eq!(get_candidate_size(), DEFAULT_CANDIDATE_SIZE);
-    std::env::set_var("RKL_CANDIDATE_SIZE", "abcd");
-    assert_eq!(get_candidate_size(), DEFAULT_CANDIDATE_SIZE);
+    assert_eq!(size_of("0"), DEFAULT_CANDIDATE_SIZE);
+    assert_eq!(size_of("-1"), DEFAULT_CANDIDATE_SIZE);
+    assert_eq!(size_of("abcd"), DEFAULT_CANDIDATE_SIZE);
+}
+
+// a kubectl command running the kubectl binary at `path` with its flags, if
+// one is given, which may be followed by a subcommand like `microk8s kubectl`
+fn with_program(command: &str, path: Option<&str>) -> String {
+    let program = match path {
+        Some(path) => path.split_whitespace().map(shell_quote).join(" "),
+        None => return command.to_string(),
+    };
+    match command.split_once(' ') {
+        Some((_, flags)) => format!("{} {}", program, flags),
+        None => program,
+    }
+}
+
+// the kubectl command followed by the flags of extra_args, passed on as they are written
+fn with_extra_args(kub: String, extra_args: Option<&str>) -> String {
+    match extra_args.map(str::trim).filter(|extra_args| !extra_args.is_empty()) {
+        Some(extra_args) => format!("{} {}", kub, extra_args),
+        None => kub,
+    }
+}
+
+#[test]
+fn test_configured_kubectl() {
+    assert_eq!(with_program("kubectl", Some("/opt/k8s/bin/kubectl")), "/opt/k8s/bin/kubectl");
+    assert_eq!(
+        with_program(DEFAULT_KUBECTL_CMD, Some("/usr/local/bin/kubectl-1.25")),
+        DEFAULT_KUBECTL_CMD.replacen("kubectl", "/usr/local/bin/kubectl-1.25", 1)
+    );
+    assert_eq!(with_program("kubectl", None), "kubectl");
+    assert_eq!(with_program("kubectl", Some("microk8s kubectl")), "microk8s kubectl");
+    assert_eq!(with_program("kubectl --context prod", Some("/opt/k8s/bin/kubectl")), "/opt/k8s/bin/kubectl --context prod");
+    assert_eq!(
+        with_extra_args("kubectl -n sophon".to_string(), Some(" --insecure-skip-tls-verify --request-timeout=10s")),
+        "kubectl -n sophon --insecure-skip-tls-verify --request-timeout=10s"
+    );
+    assert_eq!(with_extra_args("kubectl".to_string(), Some("")), "kubectl");
 }
 
 pub fn get_kub(args: &Args) -> String {
-    match &args.kubectl {
+    let mut kub = match &args.kubectl {
         Some(k) => k.clone(),
         _ => DEFAULT_KUBECTL_CMD.to_string(),
+    };
+    if let Some(context) = &args.context {
+        kub.push_str(&format!(" --context {}", shell_quote(context)));
+    }
+    if let Some(namespace) = &args.namespace {
+        kub.push_str(&format!(" -n {}", shell_quote(namespace)));
     }
+    if let Some(user) = &args.as_user {
+        kub.push_str(&format!(" --as {}", shell_quote(user)));
+    }
+    for group in &args.as_group {
+        kub.push_str(&format!(" --as-group {}", shell_quote(group)));
+    }
+    if let Some(token_file) = &args.token_file {
+        // the shell reads the token, so it never shows in the logged commands
+        kub.push_str(&format!(" --token=\"$(cat {})\"", shell_quote(token_file)));
+    }
+    kub
 }
 
 #[test]
@@ -306,6 +3195,27 @@ fn test_get_kubectl_cmd() {
         middle: None,
         kubectl: Some("kubectl".to_string()),
         cmd: Some(Command::CONTAINER {name: "sophon".to_string()}),
+        ..Default::default()
     };
     assert_eq!(get_kub(&args), "kubectl".to_string());
+    let args = Args {
+        kubectl: Some("kubectl".to_string()),
+        namespace: Some("sophon".to_string()),
+        context: Some("prod".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(get_kub(&args), "kubectl --context prod -n sophon".to_string());
+    let args = Args {
+        kubectl: Some("kubectl".to_string()),
+        token_file: Some("/var/run/token".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(get_kub(&args), "kubectl --token=\"$(cat /var/run/token)\"".to_string());
+    let args = Args {
+        kubectl: Some("kubectl".to_string()),
+        as_user: Some("jane".to_string()),
+        as_group: vec!["dev".to_string(), "site ops".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(get_kub(&args), "kubectl --as jane --as-group dev --as-group 'site ops'".to_string());
 }
\ No newline at end of file
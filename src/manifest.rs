@@ -0,0 +1,102 @@
+// This module reads which resources a set of manifests holds before they are
+// applied: the kind, name and namespace of every yaml document, found the way
+// `kubectl apply -f` finds them, in a file or in the yaml and json files of a
+// directory.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest {
+    pub kind: String,
+    pub name: String,
+    /// None when the manifest leaves it to the namespace applied to.
+    pub namespace: Option<String>,
+}
+
+/// The manifest files of a path, the file itself or the manifests of a directory, sorted.
+pub fn manifest_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(path)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|file| matches!(file.extension().and_then(|e| e.to_str()), Some("yaml") | Some("yml") | Some("json")))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// kinds of resources which belong to no namespace
+static CLUSTER_KINDS: [&str; 12] = [
+    "Namespace", "Node", "PersistentVolume", "StorageClass", "ClusterRole", "ClusterRoleBinding", "CustomResourceDefinition",
+    "PriorityClass", "IngressClass", "RuntimeClass", "MutatingWebhookConfiguration", "ValidatingWebhookConfiguration",
+];
+
+/// Whether resources of the kind belong to no namespace.
+pub fn cluster_scoped(kind: &str) -> bool {
+    CLUSTER_KINDS.contains(&kind)
+}
+
+// a `key: value` line, the value unquoted
+fn field<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let value = line.strip_prefix(key)?.strip_prefix(':')?.trim();
+    Some(value.trim_matches(|c| c == '"' || c == '\''))
+}
+
+/// The resources of the yaml documents in a manifest, skipping documents without a kind or name.
+pub fn parse_manifests(content: &str) -> Vec<Manifest> {
+    let mut manifests = Vec::new();
+    for document in content.split("\n---") {
+        let (mut kind, mut name, mut namespace) = (None, None, None);
+        let mut in_metadata = false;
+        for line in document.lines() {
+            if !line.trim().is_empty() && !line.starts_with(char::is_whitespace) {
+                in_metadata = line.trim_end() == "metadata:";
+            }
+            if let Some(value) = field(line, "kind") {
+                kind = Some(value.to_string());
+            }
+            if in_metadata {
+                if let Some(value) = field(line, "  name") {
+                    name = Some(value.to_string());
+                }
+                if let Some(value) = field(line, "  namespace") {
+                    namespace = Some(value.to_string());
+                }
+            }
+        }
+        if let (Some(kind), Some(name)) = (kind, name) {
+            manifests.push(Manifest {kind, name, namespace});
+        }
+    }
+    manifests
+}
+
+#[test]
+fn test_parse_manifests() {
+    let content = "apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: kg
+  labels:
+    name: not-the-name
+spec:
+  template:
+    metadata:
+      name: template
+---
+# a comment
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: \"kg-config\"
+  namespace: sophon
+---
+";
+    assert_eq!(parse_manifests(content), vec![
+        Manifest {kind: "Deployment".to_string(), name: "kg".to_string(), namespace: None},
+        Manifest {kind: "ConfigMap".to_string(), name: "kg-config".to_string(), namespace: Some("sophon".to_string())},
+    ]);
+}
@@ -0,0 +1,22 @@
+// This module recognizes clusters without metrics-server, where every
+// `kubectl top` fails. Commands showing usage run `kubectl top` with its
+// stderr redirected into their output, look for the error here, and leave
+// usage out with a single hint instead of printing kubectl's message.
+
+/// Shown once when usage is left out.
+pub static METRICS_HINT: &str = "usage is not available, metrics-server does not seem to be installed (see https://github.com/kubernetes-sigs/metrics-server)";
+
+// errors of kubectl top, from recent versions back to the heapster days
+static METRICS_ERRORS: [&str; 3] = ["Metrics API not available", "metrics.k8s.io", "heapster"];
+
+/// Whether the output of `kubectl top ... 2>&1` tells metrics are missing.
+pub fn metrics_unavailable(output: &str) -> bool {
+    output.lines().any(|line| METRICS_ERRORS.iter().any(|error| line.contains(error)))
+}
+
+#[test]
+fn test_metrics_unavailable() {
+    assert!(metrics_unavailable("error: Metrics API not available\n---\nkg\t500m\t1Gi\t2\t4Gi"));
+    assert!(metrics_unavailable("Error from server (NotFound): the server could not find the requested resource (get services http:heapster:)"));
+    assert!(!metrics_unavailable("kg-1   900m   300Mi"));
+}
@@ -0,0 +1,166 @@
+// This module shows how busy the nodes are. Usage comes from
+// `kubectl top nodes`, whose percentages are relative to the allocatable
+// resources, and the allocatable resources themselves from a jsonpath
+// template, both printed by one shell command around a separator.
+
+use crate::table::{format_table, header, paint};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Line printed between the output of `kubectl top nodes` and the allocatable resources.
+pub static USAGE_SEPARATOR: &str = "---";
+
+/// Arguments to `kubectl get nodes` printing one tab separated line per node:
+/// name, allocatable cpu and allocatable memory.
+pub fn allocatable_args() -> String {
+    r#"-o jsonpath='{range .items[*]}{.metadata.name}{"\t"}{.status.allocatable.cpu}{"\t"}{.status.allocatable.memory}{"\n"}{end}'"#.to_string()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeUsage {
+    pub name: String,
+    pub cpu: String,
+    pub cpu_percent: Option<u32>,
+    pub memory: String,
+    pub memory_percent: Option<u32>,
+    pub allocatable_cpu: String,
+    pub allocatable_memory: String,
+}
+
+fn parse_percent(cell: &str) -> Option<u32> {
+    cell.strip_suffix('%')?.parse().ok()
+}
+
+/// Parse `kubectl top nodes --no-headers`, the separator and the output of
+/// `allocatable_args`. Nodes without metrics show `<unknown>` usage, and
+/// without metrics-server at all the nodes come from the allocatable lines.
+pub fn parse_node_usage(output: &str) -> Vec<NodeUsage> {
+    let mut lines = output.lines();
+    let usage: Vec<Vec<&str>> = lines.by_ref()
+        .take_while(|line| line.trim() != USAGE_SEPARATOR)
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>())
+        // skip anything else kubectl printed, like its errors
+        .filter(|cells| cells.len() == 5 && (cells[2].ends_with('%') || cells[2] == "<unknown>"))
+        .collect();
+    let allocatable: Vec<(&str, &str, &str)> = lines.filter_map(|line| line.split('\t').collect_tuple()).collect();
+    if usage.is_empty() {
+        return allocatable.into_iter()
+            .map(|(name, cpu, memory)| NodeUsage {
+                name: name.to_string(),
+                cpu: String::new(),
+                cpu_percent: None,
+                memory: String::new(),
+                memory_percent: None,
+                allocatable_cpu: cpu.to_string(),
+                allocatable_memory: memory.to_string(),
+            })
+            .collect();
+    }
+    let allocatable: HashMap<&str, (&str, &str)> = allocatable.into_iter()
+        .map(|(name, cpu, memory)| (name, (cpu, memory)))
+        .collect();
+    usage.into_iter()
+        .map(|cells| {
+            let (allocatable_cpu, allocatable_memory) = allocatable.get(cells[0]).copied().unwrap_or(("", ""));
+            NodeUsage {
+                name: cells[0].to_string(),
+                cpu: cells[1].to_string(),
+                cpu_percent: parse_percent(cells[2]),
+                memory: cells[3].to_string(),
+                memory_percent: parse_percent(cells[4]),
+                allocatable_cpu: allocatable_cpu.to_string(),
+                allocatable_memory: allocatable_memory.to_string(),
+            }
+        })
+        .collect()
+}
+
+// red when nearly full, yellow when getting there
+fn percent_cell(percent: Option<u32>, color: bool) -> String {
+    match percent {
+        None => "<unknown>".to_string(),
+        Some(percent) if !color => format!("{}%", percent),
+        Some(percent) if percent >= 90 => paint(&format!("{}%", percent), 31),
+        Some(percent) if percent >= 70 => paint(&format!("{}%", percent), 33),
+        Some(percent) => paint(&format!("{}%", percent), 32),
+    }
+}
+
+/// Render the nodes as a table, the busiest first by cpu or by memory.
+/// Usage columns are left out when metrics are not available.
+pub fn format_node_usage(nodes: &[NodeUsage], by_memory: bool, color: bool, with_usage: bool) -> String {
+    if !with_usage {
+        let mut rows = vec![header(&["NODE", "ALLOCATABLE CPU", "ALLOCATABLE MEMORY"])];
+        for n in nodes.iter().sorted_by(|a, b| a.name.cmp(&b.name)) {
+            rows.push(vec![n.name.clone(), n.allocatable_cpu.clone(), n.allocatable_memory.clone()]);
+        }
+        return format_table(&rows);
+    }
+    let mut rows = vec![header(&["NODE", "CPU", "CPU%", "ALLOCATABLE CPU", "MEMORY", "MEMORY%", "ALLOCATABLE MEMORY"])];
+    let sorted = nodes.iter().sorted_by(|a, b| {
+        let (a_percent, b_percent) = if by_memory { (a.memory_percent, b.memory_percent) } else { (a.cpu_percent, b.cpu_percent) };
+        b_percent.cmp(&a_percent).then_with(|| a.name.cmp(&b.name))
+    });
+    for n in sorted {
+        rows.push(vec![
+            n.name.clone(),
+            n.cpu.clone(),
+            percent_cell(n.cpu_percent, color),
+            n.allocatable_cpu.clone(),
+            n.memory.clone(),
+            percent_cell(n.memory_percent, color),
+            n.allocatable_memory.clone(),
+        ]);
+    }
+    format_table(&rows)
+}
+
+#[test]
+fn test_node_usage() {
+    let output = "kg-node43   3120m   78%   12040Mi   40%\n\
+                  kg-node44   <unknown>   <unknown>   <unknown>   <unknown>\n\
+                  kg-node45   3900m   97%   3010Mi   10%\n\
+                  ---\n\
+                  kg-node43\t4\t30104Mi\n\
+                  kg-node45\t4\t30104Mi\n";
+    let nodes = parse_node_usage(output);
+    assert_eq!(nodes.len(), 3);
+    assert_eq!(nodes[1].cpu_percent, None);
+    assert_eq!(
+        format_node_usage(&nodes, false, false, true),
+        "NODE       CPU        CPU%       ALLOCATABLE CPU  MEMORY     MEMORY%    ALLOCATABLE MEMORY\n\
+         kg-node45  3900m      97%        4                3010Mi     10%        30104Mi\n\
+         kg-node43  3120m      78%        4                12040Mi    40%        30104Mi\n\
+         kg-node44  <unknown>  <unknown>                   <unknown>  <unknown>"
+    );
+    // metrics-server absent
+    let nodes = parse_node_usage("error: Metrics API not available\n---\nkg-node43\t4\t30104Mi\n");
+    assert_eq!(
+        format_node_usage(&nodes, false, false, false),
+        "NODE       ALLOCATABLE CPU  ALLOCATABLE MEMORY\n\
+         kg-node43  4                30104Mi"
+    );
+    let nodes = parse_node_usage(output);
+    assert!(format_node_usage(&nodes, true, false, true).lines().nth(1).unwrap().starts_with("kg-node43"));
+}
+
+/// A script run on the node of a pod listing the containers of its latest
+/// sandbox with crictl, or inspecting them, as the container runtime sees
+/// them whatever the apiserver says.
+pub fn crictl_script(pod: &str, namespace: &str, inspect: bool) -> String {
+    let containers = if inspect { r#"crictl inspect $(crictl ps -a -q --pod "$id")"# } else { r#"crictl ps -a --pod "$id""# };
+    // the filters of crictl are regular expressions, anchored not to match longer names
+    format!(
+        r#"id=$(crictl pods --name '^{pod}$' --namespace '^{namespace}$' --latest -q); [ -n "$id" ] || {{ echo 'no sandbox of {pod} on this node' >&2; exit 1; }}; {containers}"#,
+        pod = pod, namespace = namespace, containers = containers
+    )
+}
+
+#[test]
+fn test_crictl_script() {
+    assert_eq!(
+        crictl_script("kg-1", "sophon", false),
+        r#"id=$(crictl pods --name '^kg-1$' --namespace '^sophon$' --latest -q); [ -n "$id" ] || { echo 'no sandbox of kg-1 on this node' >&2; exit 1; }; crictl ps -a --pod "$id""#
+    );
+    assert!(crictl_script("kg-1", "sophon", true).ends_with(r#"; crictl inspect $(crictl ps -a -q --pod "$id")"#));
+}
@@ -0,0 +1,61 @@
+// This module tells when a long command, like a batch delete or `exec --all`,
+// is done, so one can switch to another window meanwhile. A desktop
+// notification is sent with notify-send, or osascript on macOS, falling back
+// to the bell of the terminal where neither works.
+
+use itertools::Itertools;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+// when rkl started, set once notifications are wanted
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+/// Notify when the command finishes, timed from now.
+pub fn enable() {
+    STARTED.set(Instant::now()).ok();
+}
+
+/// Tell that the command line of rkl finished, if notifications are wanted.
+pub fn finished(success: bool) {
+    if let Some(started) = STARTED.get() {
+        let command_line = format!("rkl {}", std::env::args().skip(1).join(" "));
+        send(&message(&command_line, success, started.elapsed()));
+    }
+}
+
+// the text of the notification of a command line which finished
+fn message(command_line: &str, success: bool, took: Duration) -> String {
+    format!("{} {} after {:.1}s", command_line, if success { "succeeded" } else { "failed" }, took.as_secs_f64())
+}
+
+#[test]
+fn test_message() {
+    assert_eq!(message("rkl delete kg --wait", true, Duration::from_millis(12345)), "rkl delete kg --wait succeeded after 12.3s");
+    assert_eq!(message("rkl exec kg --all -- true", false, Duration::from_secs(2)), "rkl exec kg --all -- true failed after 2.0s");
+}
+
+// send a desktop notification, or ring the bell if none can be sent
+fn send(message: &str) {
+    log::info!("{}", message);
+    // notifications are not sent while debugging
+    if cfg!(debug_assertions) || !desktop_notification(message) {
+        eprint!("\x07");
+    }
+}
+
+fn desktop_notification(message: &str) -> bool {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!("display notification {:?} with title \"rkl\"", message));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg("rkl").arg(message);
+        command
+    };
+    command.stdout(Stdio::null()).stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
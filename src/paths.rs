@@ -0,0 +1,126 @@
+// This module tells where rkl keeps its files. They follow the XDG base
+// directories on Linux and the platform conventions elsewhere:
+//   config  $XDG_CONFIG_HOME/rkl  (~/.config/rkl)       settings
+//   data    $XDG_DATA_HOME/rkl    (~/.local/share/rkl)  remembered choices
+// On macOS both live under ~/Library/Application Support/rkl, on Windows
+// under %APPDATA%\rkl and %LOCALAPPDATA%\rkl.
+// Files of older versions, like ~/.rkl_config, keep being used until the new
+// ones are created.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+static APP_DIR: &str = "rkl";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Config,
+    Data,
+}
+
+fn home() -> Option<PathBuf> {
+    env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")).map(PathBuf::from)
+}
+
+// the XDG directory from its env, which must be absolute to count, or the default under home
+fn xdg_dir(xdg: Option<PathBuf>, home: &Path, default: &str) -> PathBuf {
+    match xdg {
+        Some(dir) if dir.is_absolute() => dir,
+        _ => home.join(default),
+    }
+}
+
+#[test]
+fn test_xdg_dir() {
+    let home = Path::new("/home/luyi");
+    assert_eq!(xdg_dir(None, home, ".config"), Path::new("/home/luyi/.config"));
+    assert_eq!(xdg_dir(Some(PathBuf::from("/xdg/config")), home, ".config"), Path::new("/xdg/config"));
+    // relative paths are invalid according to the spec
+    assert_eq!(xdg_dir(Some(PathBuf::from("config")), home, ".config"), Path::new("/home/luyi/.config"));
+}
+
+fn base_dir(kind: Kind) -> Option<PathBuf> {
+    if cfg!(windows) {
+        let var = if kind == Kind::Config { "APPDATA" } else { "LOCALAPPDATA" };
+        return env::var_os(var).map(PathBuf::from);
+    }
+    let home = home()?;
+    if cfg!(target_os = "macos") {
+        return Some(home.join("Library/Application Support"));
+    }
+    let (var, default) = match kind {
+        Kind::Config => ("XDG_CONFIG_HOME", ".config"),
+        Kind::Data => ("XDG_DATA_HOME", ".local/share"),
+    };
+    Some(xdg_dir(env::var_os(var).map(PathBuf::from), &home, default))
+}
+
+// the file in the directory of its kind, or the file of an older version
+// in the home directory while only that one exists
+fn app_file(kind: Kind, name: &str, legacy: &str) -> Option<PathBuf> {
+    let path = base_dir(kind)?.join(APP_DIR).join(name);
+    if !path.exists() {
+        if let Some(legacy) = home().map(|home| home.join(legacy)).filter(|legacy| legacy.exists()) {
+            return Some(legacy);
+        }
+    }
+    Some(path)
+}
+
+/// The config file, `config` in the config directory.
+pub fn config_file() -> Option<PathBuf> {
+    app_file(Kind::Config, "config", ".rkl_config")
+}
+
+/// The file remembering picked pods, `selections` in the data directory.
+pub fn selections_file() -> Option<PathBuf> {
+    app_file(Kind::Data, "selections", ".rkl_selections")
+}
+
+/// The audit log of destructive commands, `audit` in the data directory.
+pub fn audit_file() -> Option<PathBuf> {
+    Some(base_dir(Kind::Data)?.join(APP_DIR).join("audit"))
+}
+
+// a cache file in the data directory, its name suffixed with the parts given
+fn cache_file(name: &str, parts: &[Option<&str>]) -> Option<PathBuf> {
+    let mut name = name.to_string();
+    for part in parts.iter().flatten() {
+        name.push('-');
+        name.push_str(&part.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_"));
+    }
+    Some(base_dir(Kind::Data)?.join(APP_DIR).join(name))
+}
+
+#[test]
+fn test_cache_file() {
+    let file = cache_file("pods", &[Some("admin@prod"), None]).unwrap();
+    assert_eq!(file.file_name().unwrap(), "pods-admin_prod");
+}
+
+/// The cached kinds a cluster serves, `api-resources` in the data directory,
+/// suffixed with the context when one is given.
+pub fn api_resources_file(context: Option<&str>) -> Option<PathBuf> {
+    cache_file("api-resources", &[context])
+}
+
+/// The pods listed last, `pods` in the data directory, suffixed with the
+/// context and namespace when given.
+pub fn pods_file(context: Option<&str>, namespace: Option<&str>) -> Option<PathBuf> {
+    cache_file("pods", &[context, namespace])
+}
+
+/// How long ago a file was written.
+pub fn age(path: &Path) -> Option<std::time::Duration> {
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok()?;
+    Some(std::time::SystemTime::now().duration_since(modified).unwrap_or_default())
+}
+
+/// Write a file of rkl, creating its directory first.
+pub fn write(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)
+}
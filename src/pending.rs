@@ -0,0 +1,134 @@
+// This module explains why a pod is stuck in Pending. The scheduler records
+// a FailedScheduling event like
+//   0/5 nodes are available: 2 Insufficient cpu, 1 node(s) had taint
+//   {node-role.kubernetes.io/master: }, that the pod didn't tolerate.
+// which we break down into one line per rejection reason, together with the
+// taints of every node when taints are part of the story.
+
+use itertools::Itertools;
+
+/// Printed between the scheduling events and the node taints.
+pub static TAINTS_SEPARATOR: &str = "---";
+
+/// Arguments to `kubectl get events` printing the FailedScheduling events of
+/// a pod, one tab separated line each: last timestamp, event time and message.
+pub fn scheduling_events_args(pod_name: &str) -> String {
+    format!(
+        r#"--field-selector involvedObject.name={},reason=FailedScheduling -o jsonpath='{{range .items[*]}}{{.lastTimestamp}}{{"\t"}}{{.eventTime}}{{"\t"}}{{.message}}{{"\n"}}{{end}}'"#,
+        pod_name
+    )
+}
+
+/// Arguments to `kubectl get nodes` printing every node name followed by its
+/// taints as `key=value:effect` separated by spaces.
+pub fn node_taints_args() -> String {
+    r#"-o jsonpath='{range .items[*]}{.metadata.name}{"\t"}{range .spec.taints[*]}{.key}={.value}:{.effect}{" "}{end}{"\n"}{end}'"#.to_string()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchedulingFailure {
+    /// e.g. "0/5", available nodes out of all nodes
+    pub available: String,
+    /// number of rejected nodes and the reason they were rejected for
+    pub reasons: Vec<(u32, String)>,
+}
+
+/// Break a FailedScheduling message down into its rejection reasons.
+pub fn parse_scheduling_message(message: &str) -> Option<SchedulingFailure> {
+    let (available, reasons) = message.splitn(2, " nodes are available: ").collect_tuple()?;
+    // newer schedulers append the outcome of preemption, which repeats the counts
+    let reasons = reasons.split(" preemption:").next().unwrap_or("").trim().trim_end_matches('.');
+    let mut parsed: Vec<(u32, String)> = Vec::new();
+    for piece in reasons.split(", ") {
+        let mut words = piece.splitn(2, ' ');
+        let count = words.next().and_then(|c| c.parse().ok());
+        match (count, words.next(), parsed.last_mut()) {
+            (Some(count), Some(reason), _) => parsed.push((count, reason.to_string())),
+            // a reason containing ", ", like "that the pod didn't tolerate"
+            (_, _, Some(last)) => {
+                last.1.push_str(", ");
+                last.1.push_str(piece);
+            }
+            _ => return None,
+        }
+    }
+    Some(SchedulingFailure { available: available.trim().to_string(), reasons: parsed })
+}
+
+/// Summarize the newest scheduling failure, followed by the tainted nodes
+/// when some nodes were rejected for their taints.
+pub fn summarize(output: &str) -> String {
+    let mut lines = output.lines();
+    let newest_message = lines
+        .by_ref()
+        .take_while(|line| line.trim() != TAINTS_SEPARATOR)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(3, '\t').collect();
+            if fields.len() != 3 {
+                return None;
+            }
+            let timestamp = if fields[0].is_empty() { fields[1] } else { fields[0] };
+            Some((timestamp.to_string(), fields[2].to_string()))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0));
+    let (timestamp, message) = match newest_message {
+        Some(newest_message) => newest_message,
+        None => return "no scheduling failure recorded for the pod".to_string(),
+    };
+    let failure = match parse_scheduling_message(&message) {
+        Some(failure) => failure,
+        None => return format!("{} {}", timestamp, message),
+    };
+    let mut summary = vec![format!("{} {} nodes are available", timestamp, failure.available)];
+    for (count, reason) in &failure.reasons {
+        summary.push(format!("  {} {}: {}", count, if *count == 1 { "node" } else { "nodes" }, reason.trim_start_matches("node(s) ")));
+    }
+    if failure.reasons.iter().any(|(_, reason)| reason.contains("taint")) {
+        summary.push("tainted nodes:".to_string());
+        for line in lines {
+            let mut fields = line.splitn(2, '\t');
+            if let (Some(node), Some(taints)) = (fields.next(), fields.next()) {
+                if !taints.trim().is_empty() {
+                    summary.push(format!("  {}: {}", node, taints.trim()));
+                }
+            }
+        }
+    }
+    summary.join("\n")
+}
+
+#[test]
+fn test_parse_scheduling_message() {
+    let failure = parse_scheduling_message(
+        "0/5 nodes are available: 2 Insufficient cpu, 1 node(s) had taint {node-role.kubernetes.io/master: }, that the pod didn't tolerate, 2 node(s) didn't match node selector.",
+    ).unwrap();
+    assert_eq!(failure.available, "0/5");
+    assert_eq!(failure.reasons, vec![
+        (2, "Insufficient cpu".to_string()),
+        (1, "node(s) had taint {node-role.kubernetes.io/master: }, that the pod didn't tolerate".to_string()),
+        (2, "node(s) didn't match node selector".to_string()),
+    ]);
+    let failure = parse_scheduling_message(
+        "0/3 nodes are available: 3 Insufficient memory. preemption: 0/3 nodes are available: 3 No preemption victims found for incoming pod.",
+    ).unwrap();
+    assert_eq!(failure.reasons, vec![(3, "Insufficient memory".to_string())]);
+    assert_eq!(parse_scheduling_message("pod has unbound immediate PersistentVolumeClaims"), None);
+}
+
+#[test]
+fn test_summarize() {
+    let output = "2021-03-01T10:00:00Z\t\t0/3 nodes are available: 3 Insufficient cpu.\n\
+                  2021-03-01T11:00:00Z\t\t0/3 nodes are available: 2 Insufficient cpu, 1 node(s) had taint {dedicated: gpu}, that the pod didn't tolerate.\n\
+                  ---\n\
+                  kg-node43\t\n\
+                  kg-node44\tdedicated=gpu:NoSchedule \n";
+    assert_eq!(
+        summarize(output),
+        "2021-03-01T11:00:00Z 0/3 nodes are available\n  \
+         2 nodes: Insufficient cpu\n  \
+         1 node: had taint {dedicated: gpu}, that the pod didn't tolerate\n\
+         tainted nodes:\n  \
+         kg-node44: dedicated=gpu:NoSchedule"
+    );
+    assert_eq!(summarize("---\nkg-node43\t\n"), "no scheduling failure recorded for the pod");
+}
@@ -0,0 +1,324 @@
+// This module lists pods from their fields rather than from the columns of
+// `kubectl get po -owide`, whose layout changes between kubectl versions
+// (restarts gained an "(5m ago)" note) and breaks on whitespace. kubectl
+// prints the fields of each pod as one tab separated line through a jsonpath
+// template, and the columns people know (ready, status, restarts, age) are
+// computed here the way kubectl computes them.
+
+use itertools::Itertools;
+use std::convert::TryFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Arguments to `kubectl get po` printing one tab separated line per pod:
+/// name, namespace, phase, reason, deletion time, creation time, IP, node, nominated
+/// node, container names, container statuses as `ready,restarts,reason,started;`,
+/// readiness gates, conditions as `type=status,`, the default container and init
+/// container statuses as `restarts,reason,exit code,signal;`.
+pub fn list_args() -> String {
+    concat!(
+        r#"-o jsonpath='{range .items[*]}"#,
+        r#"{.metadata.name}{"\t"}{.metadata.namespace}{"\t"}{.status.phase}{"\t"}{.status.reason}{"\t"}{.metadata.deletionTimestamp}{"\t"}{.metadata.creationTimestamp}{"\t"}"#,
+        r#"{.status.podIP}{"\t"}{.spec.nodeName}{"\t"}{.status.nominatedNodeName}{"\t"}{.spec.containers[*].name}{"\t"}"#,
+        r#"{range .status.containerStatuses[*]}{.ready}{","}{.restartCount}{","}{.state.waiting.reason}{.state.terminated.reason}{","}{.state.running.startedAt}{";"}{end}{"\t"}"#,
+        r#"{.spec.readinessGates[*].conditionType}{"\t"}{range .status.conditions[*]}{.type}{"="}{.status}{","}{end}{"\t"}"#,
+        r#"{.metadata.annotations.kubectl\.kubernetes\.io/default-container}{"\t"}"#,
+        r#"{range .status.initContainerStatuses[*]}{.restartCount}{","}{.state.waiting.reason}{.state.terminated.reason}{","}{.state.terminated.exitCode}{","}{.state.terminated.signal}{";"}{end}{"\n"}{end}'"#,
+    ).to_string()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerStatus {
+    pub ready: bool,
+    pub restarts: u32,
+    /// Why the container is waiting or terminated, empty while it runs.
+    pub reason: String,
+    pub running: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitStatus {
+    pub restarts: u32,
+    /// Why the container is waiting or terminated, empty while it runs.
+    pub reason: String,
+    /// The exit code once the container terminated.
+    pub exit_code: Option<i32>,
+    pub signal: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pod {
+    pub name: String,
+    pub namespace: String,
+    pub phase: String,
+    /// Set by the kubelet for pods like evicted ones.
+    pub reason: String,
+    pub deleting: bool,
+    /// Seconds since the epoch.
+    pub created: Option<u64>,
+    pub ip: String,
+    pub node: String,
+    pub nominated_node: String,
+    /// Names of the app containers, in the order of the spec.
+    pub containers: Vec<String>,
+    pub statuses: Vec<ContainerStatus>,
+    pub readiness_gates: Vec<String>,
+    /// Conditions which are true.
+    pub true_conditions: Vec<String>,
+    /// Container named by the annotation `kubectl.kubernetes.io/default-container`, empty if none.
+    pub default_container: String,
+    /// Statuses of the init containers, in the order they run.
+    pub init_statuses: Vec<InitStatus>,
+}
+
+fn or_none(value: &str) -> String {
+    if value.is_empty() { "<none>".to_string() } else { value.to_string() }
+}
+
+impl Pod {
+    /// Ready containers of all containers, like `1/2`.
+    pub fn ready(&self) -> String {
+        format!("{}/{}", self.statuses.iter().filter(|status| status.ready).count(), self.containers.len())
+    }
+
+    /// The status column of kubectl: how far initialization got while an init
+    /// container has not succeeded, else the reason of the first container not
+    /// running, else of the pod, else its phase.
+    pub fn status(&self) -> String {
+        let mut status = match self.init_status() {
+            Some(init_status) => init_status,
+            None => self.containers_status(),
+        };
+        if self.deleting && self.reason != "NodeLost" {
+            status = "Terminating".to_string();
+        }
+        status
+    }
+
+    // the status of the first init container which did not succeed, like
+    // `Init:1/2` or `Init:CrashLoopBackOff`, None once all of them succeeded
+    fn init_status(&self) -> Option<String> {
+        for (index, init) in self.init_statuses.iter().enumerate() {
+            return Some(match init.exit_code {
+                Some(0) => continue,
+                Some(_) if !init.reason.is_empty() => format!("Init:{}", init.reason),
+                Some(_) if init.signal != 0 => format!("Init:Signal:{}", init.signal),
+                Some(code) => format!("Init:ExitCode:{}", code),
+                None if !init.reason.is_empty() && init.reason != "PodInitializing" => format!("Init:{}", init.reason),
+                None => format!("Init:{}/{}", index, self.init_statuses.len()),
+            });
+        }
+        None
+    }
+
+    fn containers_status(&self) -> String {
+        let mut status = if self.reason.is_empty() { self.phase.clone() } else { self.reason.clone() };
+        let mut running = false;
+        // kubectl goes backwards, so the first container wins
+        for container in self.statuses.iter().rev() {
+            if !container.reason.is_empty() {
+                status = container.reason.clone();
+            } else if container.ready && container.running {
+                running = true;
+            }
+        }
+        if status == "Completed" && running {
+            status = "Running".to_string();
+        }
+        status
+    }
+
+    /// Restarts of all containers, init containers included.
+    pub fn restarts(&self) -> u32 {
+        self.init_statuses.iter().map(|status| status.restarts).sum::<u32>() + self.statuses.iter().map(|status| status.restarts).sum::<u32>()
+    }
+
+    /// Age like kubectl prints it, `<unknown>` without a creation time.
+    pub fn age(&self, now: u64) -> String {
+        match self.created {
+            Some(created) => human_duration(now.saturating_sub(created)),
+            None => "<unknown>".to_string(),
+        }
+    }
+
+    /// The readiness gates which passed of all of them, like `1/2`.
+    pub fn readiness_gates(&self) -> String {
+        if self.readiness_gates.is_empty() {
+            return "<none>".to_string();
+        }
+        let passed = self.readiness_gates.iter().filter(|gate| self.true_conditions.contains(gate)).count();
+        format!("{}/{}", passed, self.readiness_gates.len())
+    }
+
+    /// The columns of `kubectl get po -owide`.
+    pub fn columns(&self, now: u64) -> [String; 9] {
+        [
+            self.name.clone(),
+            self.ready(),
+            self.status(),
+            self.restarts().to_string(),
+            self.age(now),
+            or_none(&self.ip),
+            or_none(&self.node),
+            or_none(&self.nominated_node),
+            self.readiness_gates(),
+        ]
+    }
+}
+
+/// Seconds since the epoch of a timestamp like `2021-03-01T10:05:00Z`.
+pub fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let (date, time) = timestamp.strip_suffix('Z')?.split_once('T')?;
+    let (year, month, day): (i64, i64, i64) = date.split('-').map(|part| part.parse().ok()).collect::<Option<Vec<i64>>>()?
+        .into_iter().collect_tuple()?;
+    let (hour, minute, second): (u64, u64, u64) = time.split(':').map(|part| part.parse().ok()).collect::<Option<Vec<u64>>>()?
+        .into_iter().collect_tuple()?;
+    // days since the epoch of the civil date, counting years from March
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = u64::try_from(era * 146097 + day_of_era - 719468).ok()?;
+    Some(days * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+#[test]
+fn test_parse_timestamp() {
+    assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+    assert_eq!(parse_timestamp("2021-03-01T10:05:00Z"), Some(1614593100));
+    assert_eq!(parse_timestamp("2024-02-29T23:59:59Z"), Some(1709251199));
+    assert_eq!(parse_timestamp("yesterday"), None);
+}
+
+/// A duration the way kubectl prints ages: precise when short, rounded when long.
+pub fn human_duration(seconds: u64) -> String {
+    let (minutes, hours, days) = (seconds / 60, seconds / 3600, seconds / 86400);
+    let with_rest = |value: u64, unit: &str, rest: u64, rest_unit: &str| {
+        if rest == 0 { format!("{}{}", value, unit) } else { format!("{}{}{}{}", value, unit, rest, rest_unit) }
+    };
+    if seconds < 120 {
+        format!("{}s", seconds)
+    } else if minutes < 10 {
+        with_rest(minutes, "m", seconds % 60, "s")
+    } else if minutes < 180 {
+        format!("{}m", minutes)
+    } else if hours < 8 {
+        with_rest(hours, "h", minutes % 60, "m")
+    } else if hours < 48 {
+        format!("{}h", hours)
+    } else if hours < 192 {
+        with_rest(days, "d", hours % 24, "h")
+    } else if days < 730 {
+        format!("{}d", days)
+    } else if days < 2920 {
+        with_rest(days / 365, "y", days % 365, "d")
+    } else {
+        format!("{}y", days / 365)
+    }
+}
+
+#[test]
+fn test_human_duration() {
+    assert_eq!(human_duration(59), "59s");
+    assert_eq!(human_duration(150), "2m30s");
+    assert_eq!(human_duration(56 * 60), "56m");
+    assert_eq!(human_duration(5 * 3600 + 60), "5h1m");
+    assert_eq!(human_duration(20 * 3600), "20h");
+    assert_eq!(human_duration(3 * 86400), "3d");
+    assert_eq!(human_duration(12 * 86400), "12d");
+}
+
+fn parse_init_status(status: &str) -> Option<InitStatus> {
+    let (restarts, reason, exit_code, signal) = status.split(',').collect_tuple()?;
+    Some(InitStatus {
+        restarts: restarts.parse().unwrap_or(0),
+        reason: reason.to_string(),
+        exit_code: exit_code.parse().ok(),
+        signal: signal.parse().unwrap_or(0),
+    })
+}
+
+fn parse_status(status: &str) -> Option<ContainerStatus> {
+    let (ready, restarts, reason, started) = status.split(',').collect_tuple()?;
+    Some(ContainerStatus {
+        ready: ready == "true",
+        restarts: restarts.parse().unwrap_or(0),
+        reason: reason.to_string(),
+        running: !started.is_empty(),
+    })
+}
+
+/// Parse the output of the template of `list_args`, warning about the lines which
+/// are not pods. Lines without a default container or init container statuses,
+/// cached by an older version, are read too.
+pub fn parse_pods(output: &str) -> Vec<Pod> {
+    output.lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if !(13..=15).contains(&fields.len()) || fields[0].is_empty() {
+                if !line.trim().is_empty() {
+                    log::warn!("skipping line {} of the pods, not a pod: {}", index + 1, line);
+                }
+                return None;
+            }
+            Some(Pod {
+                name: fields[0].to_string(),
+                namespace: fields[1].to_string(),
+                phase: fields[2].to_string(),
+                reason: fields[3].to_string(),
+                deleting: !fields[4].is_empty(),
+                created: parse_timestamp(fields[5]),
+                ip: fields[6].to_string(),
+                node: fields[7].to_string(),
+                nominated_node: fields[8].to_string(),
+                containers: fields[9].split_whitespace().map(str::to_string).collect(),
+                statuses: fields[10].split(';').filter_map(parse_status).collect(),
+                readiness_gates: fields[11].split_whitespace().map(str::to_string).collect(),
+                true_conditions: fields[12].split(',')
+                    .filter_map(|condition| condition.strip_suffix("=True"))
+                    .map(str::to_string)
+                    .collect(),
+                default_container: fields.get(13).unwrap_or(&"").to_string(),
+                init_statuses: fields.get(14).unwrap_or(&"").split(';').filter_map(parse_init_status).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Seconds since the epoch, ages are counted from.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or(0)
+}
+
+#[test]
+fn test_parse_pods() {
+    let output = "kg-1\tsophon\tRunning\t\t\t2021-03-01T10:00:00Z\t172.26.0.124\tkg-node43\t\tkg sidecar\ttrue,4,,2021-03-01T10:01:00Z;true,0,,2021-03-01T10:01:00Z;\t\tReady=True,\tkg\n\
+                  kg-2\tsophon\tRunning\t\t\t2021-02-17T10:00:00Z\t172.26.0.125\tkg-node44\t\tkg\tfalse,23,CrashLoopBackOff,;\t\tReady=False,\n\
+                  kg-3\tsophon\tPending\t\t\t2021-03-01T10:56:00Z\t\t\t\tkg\t\tgate\t\n\
+                  kg-4\tsophon\tFailed\tEvicted\t\t2021-03-01T10:00:00Z\t\tkg-node43\t\tkg\t\t\t\n\
+                  kg-5\tsophon\tRunning\t\t2021-03-01T10:57:00Z\t2021-03-01T10:00:00Z\t172.26.0.126\tkg-node43\t\tkg\ttrue,0,,2021-03-01T10:01:00Z;\t\t\n\
+                  kg-6\tsophon\tPending\t\t\t2021-03-01T10:50:00Z\t172.26.0.127\tkg-node43\t\tkg\tfalse,0,PodInitializing,;\t\t\t\t0,,0,;1,,,;\n\
+                  kg-7\tsophon\tPending\t\t\t2021-03-01T10:50:00Z\t172.26.0.127\tkg-node43\t\tkg\tfalse,0,PodInitializing,;\t\t\t\t5,CrashLoopBackOff,,;\n\
+                  kg-8\tsophon\tPending\t\t\t2021-03-01T10:50:00Z\t172.26.0.127\tkg-node43\t\tkg\tfalse,0,PodInitializing,;\t\t\t\t0,Error,1,0;\n\
+                  kg-9\tsophon\tPending\t\t\t2021-03-01T10:50:00Z\t172.26.0.127\tkg-node43\t\tkg\tfalse,0,PodInitializing,;\t\t\t\t0,,137,9;\n\
+                  error: not a pod\n";
+    let now = parse_timestamp("2021-03-01T10:57:30Z").unwrap();
+    let pods = parse_pods(output);
+    assert_eq!(pods[0].namespace, "sophon");
+    assert_eq!(pods[0].containers, vec!["kg", "sidecar"]);
+    assert_eq!(pods[0].default_container, "kg");
+    assert_eq!(pods[1].default_container, "");
+    let rows: Vec<String> = pods.iter().map(|pod| pod.columns(now).join(" ")).collect();
+    assert_eq!(rows, vec![
+        "kg-1 2/2 Running 4 57m 172.26.0.124 kg-node43 <none> <none>",
+        "kg-2 0/1 CrashLoopBackOff 23 12d 172.26.0.125 kg-node44 <none> <none>",
+        "kg-3 0/1 Pending 0 90s <none> <none> <none> 0/1",
+        "kg-4 0/1 Evicted 0 57m <none> kg-node43 <none> <none>",
+        "kg-5 1/1 Terminating 0 57m 172.26.0.126 kg-node43 <none> <none>",
+        "kg-6 0/1 Init:1/2 1 7m30s 172.26.0.127 kg-node43 <none> <none>",
+        "kg-7 0/1 Init:CrashLoopBackOff 5 7m30s 172.26.0.127 kg-node43 <none> <none>",
+        "kg-8 0/1 Init:Error 0 7m30s 172.26.0.127 kg-node43 <none> <none>",
+        "kg-9 0/1 Init:Signal:9 0 7m30s 172.26.0.127 kg-node43 <none> <none>",
+    ]);
+}
@@ -0,0 +1,65 @@
+// This module hides secrets in the output of `describe` and `logs`, for
+// sharing a screen or pasting output into a ticket. A value is masked when its
+// key contains one of the configured words, case ignored, in lines like
+//   DB_PASSWORD:  hunter2             (environment of describe)
+//   api_token=abc123                  (logs)
+//   {"secretKey": "abc123"}           (JSON logs)
+// Values given by reference, like `<set to the key 'password' in secret 'db'>`,
+// name no secret and are kept.
+
+use regex::{Captures, Regex};
+
+/// Words of the keys whose values are masked, unless `redact_keys` is set.
+pub static DEFAULT_KEYS: [&str; 3] = ["PASSWORD", "TOKEN", "KEY"];
+
+static MASK: &str = "******";
+
+/// Masks the values of keys containing any of `keys`.
+#[derive(Clone)]
+pub struct Redactor {
+    pattern: Regex,
+}
+
+impl Redactor {
+    /// A redactor of `keys`, None when there is no key to mask.
+    pub fn new(keys: &[String]) -> Option<Redactor> {
+        let words: Vec<String> = keys.iter().filter(|key| !key.is_empty()).map(|key| regex::escape(key)).collect();
+        if words.is_empty() {
+            return None;
+        }
+        // the key, quoted or not, its separator, then a quoted value or one up to a blank, comma or brace
+        let pattern = format!(r#"(?i)([\w.-]*(?:{})[\w.-]*"?\s*[:=]\s*)("[^"]*"|[^\s,}}]+)"#, words.join("|"));
+        Some(Redactor {pattern: Regex::new(&pattern).unwrap()})
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        self.pattern
+            .replace_all(text, |caps: &Captures| {
+                let value = &caps[2];
+                if value.starts_with('<') {
+                    caps[0].to_string()
+                } else if value.starts_with('"') {
+                    format!("{}\"{}\"", &caps[1], MASK)
+                } else {
+                    format!("{}{}", &caps[1], MASK)
+                }
+            })
+            .to_string()
+    }
+}
+
+#[test]
+fn test_redact() {
+    let keys: Vec<String> = DEFAULT_KEYS.iter().map(|key| key.to_string()).collect();
+    let redactor = Redactor::new(&keys).unwrap();
+    assert_eq!(
+        redactor.redact("    Environment:\n      DB_HOST:      mysql\n      DB_PASSWORD:  hunter2\n      API_TOKEN:    <set to the key 'token' in secret 'api'>  Optional: false"),
+        "    Environment:\n      DB_HOST:      mysql\n      DB_PASSWORD:  ******\n      API_TOKEN:    <set to the key 'token' in secret 'api'>  Optional: false"
+    );
+    assert_eq!(redactor.redact("login with api_token=abc123, user=admin"), "login with api_token=******, user=admin");
+    assert_eq!(
+        redactor.redact(r#"{"level": "info", "secretKey": "abc 123", "password":"x"}"#),
+        r#"{"level": "info", "secretKey": "******", "password":"******"}"#
+    );
+    assert!(Redactor::new(&[]).is_none());
+}
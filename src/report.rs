@@ -0,0 +1,119 @@
+// This module writes a plain text health report of the pods named like a
+// pattern, short enough to paste into an incident channel: pod counts by
+// status, the most restarted pods, pending pods with the reason the
+// scheduler gave, and the most recent warning events.
+
+use crate::event::Event;
+use crate::pending;
+use itertools::Itertools;
+
+// entries listed in each section of the report
+static REPORT_ENTRIES: usize = 5;
+
+/// What the report needs to know of a pod.
+pub struct PodState {
+    pub name: String,
+    pub status: String,
+    pub restarts: u32,
+}
+
+// why a pending pod is not scheduled, from its newest FailedScheduling event
+fn pending_reason(pod: &str, events: &[Event]) -> String {
+    let message = match events.iter().find(|event| event.pod == pod && event.reason == "FailedScheduling") {
+        Some(event) => &event.message,
+        None => return "no scheduling event".to_string(),
+    };
+    match pending::parse_scheduling_message(message) {
+        Some(failure) => format!(
+            "{} nodes available: {}",
+            failure.available,
+            failure.reasons.iter().map(|(count, reason)| format!("{} {}", count, reason)).join(", ")
+        ),
+        None => message.clone(),
+    }
+}
+
+/// Build the report of the pods, `events` being warning events newest first.
+pub fn build_report(title: &str, pods: &[PodState], events: &[Event]) -> String {
+    let mut lines = vec![format!("Health report of {}: {} pods", title, pods.len())];
+
+    lines.push(String::new());
+    lines.push("Pods by status:".to_string());
+    for (status, count) in pods.iter().map(|pod| &pod.status).counts().into_iter()
+        .sorted_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)))
+    {
+        lines.push(format!("  {}: {}", status, count));
+    }
+
+    let restarters: Vec<&PodState> = pods.iter()
+        .filter(|pod| pod.restarts > 0)
+        .sorted_by(|a, b| b.restarts.cmp(&a.restarts).then_with(|| a.name.cmp(&b.name)))
+        .take(REPORT_ENTRIES)
+        .collect();
+    if !restarters.is_empty() {
+        lines.push(String::new());
+        lines.push("Top restarters:".to_string());
+        for pod in restarters {
+            lines.push(format!("  {} ({} restarts, {})", pod.name, pod.restarts, pod.status));
+        }
+    }
+
+    let pending: Vec<&PodState> = pods.iter().filter(|pod| pod.status == "Pending").collect();
+    if !pending.is_empty() {
+        lines.push(String::new());
+        lines.push("Pending pods:".to_string());
+        for pod in pending {
+            lines.push(format!("  {}: {}", pod.name, pending_reason(&pod.name, events)));
+        }
+    }
+
+    let recent: Vec<&Event> = events.iter()
+        .filter(|event| pods.iter().any(|pod| pod.name == event.pod))
+        .take(REPORT_ENTRIES)
+        .collect();
+    if !recent.is_empty() {
+        lines.push(String::new());
+        lines.push("Recent warnings:".to_string());
+        for event in recent {
+            lines.push(format!("  {} {}", event.pod, event));
+        }
+    }
+    lines.join("\n")
+}
+
+#[test]
+fn test_build_report() {
+    let pod = |name: &str, status: &str, restarts| PodState {name: name.to_string(), status: status.to_string(), restarts};
+    let pods = vec![
+        pod("kg-1", "Running", 0),
+        pod("kg-2", "CrashLoopBackOff", 23),
+        pod("kg-3", "Pending", 0),
+    ];
+    let event = |pod: &str, timestamp: &str, reason: &str, message: &str| Event {
+        pod: pod.to_string(), timestamp: timestamp.to_string(), reason: reason.to_string(), message: message.to_string(),
+    };
+    let events = vec![
+        event("kg-3", "2021-03-01T10:05:00Z", "FailedScheduling", "0/5 nodes are available: 5 Insufficient cpu."),
+        event("kg-2", "2021-03-01T10:04:00Z", "BackOff", "Back-off restarting failed container"),
+        event("ui-1", "2021-03-01T10:03:00Z", "BackOff", "Back-off restarting failed container"),
+    ];
+    assert_eq!(
+        build_report("kg", &pods, &events),
+        "Health report of kg: 3 pods\n\
+         \n\
+         Pods by status:\n  \
+           CrashLoopBackOff: 1\n  \
+           Pending: 1\n  \
+           Running: 1\n\
+         \n\
+         Top restarters:\n  \
+           kg-2 (23 restarts, CrashLoopBackOff)\n\
+         \n\
+         Pending pods:\n  \
+           kg-3: 0/5 nodes available: 5 Insufficient cpu\n\
+         \n\
+         Recent warnings:\n  \
+           kg-3 2021-03-01T10:05:00Z FailedScheduling: 0/5 nodes are available: 5 Insufficient cpu.\n  \
+           kg-2 2021-03-01T10:04:00Z BackOff: Back-off restarting failed container"
+    );
+}
@@ -0,0 +1,64 @@
+// This module tells transient failures of kubectl, like an apiserver
+// restarting or a flaky load balancer, from permanent ones like NotFound or
+// Forbidden. Read-only commands failing transiently are run again after a
+// delay doubling with each attempt.
+
+use std::time::Duration;
+
+// errors kubectl prints when the apiserver could not be reached or failed
+// to answer, which a later attempt may not run into
+static TRANSIENT_ERRORS: [&str; 11] = [
+    "connection refused",
+    "connection reset by peer",
+    "i/o timeout",
+    "TLS handshake timeout",
+    "unexpected EOF",
+    "no route to host",
+    "(ServiceUnavailable)",
+    "(InternalError)",
+    "(TooManyRequests)",
+    "(Timeout)",
+    "the server is currently unable to handle the request",
+];
+
+/// Whether the stderr of kubectl tells of a failure worth another attempt.
+pub fn is_transient(stderr: &str) -> bool {
+    stderr.lines().any(|line| TRANSIENT_ERRORS.iter().any(|error| line.contains(error)))
+}
+
+#[test]
+fn test_is_transient() {
+    assert!(is_transient("The connection to the server 10.0.0.1:6443 was refused - did you specify the right host or port?\n\
+                          dial tcp 10.0.0.1:6443: connect: connection refused"));
+    assert!(is_transient("Error from server (ServiceUnavailable): the server is currently unable to handle the request"));
+    assert!(!is_transient("Error from server (NotFound): pods \"kg-1\" not found"));
+    assert!(!is_transient("Error from server (Forbidden): pods is forbidden"));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts after the first one.
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each next one.
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {retries: 2, backoff: Duration::from_millis(500)}
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the retry following the failed attempt `attempt`, counted from 0.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.backoff * 2u32.saturating_pow(attempt)
+    }
+}
+
+#[test]
+fn test_delay() {
+    let policy = RetryPolicy::default();
+    assert_eq!(policy.delay(0), Duration::from_millis(500));
+    assert_eq!(policy.delay(2), Duration::from_secs(2));
+}
@@ -0,0 +1,70 @@
+// This module remembers which pod the user picked for an ambiguous pattern,
+// so the same replica is offered as the default next time. Choices are kept
+// in the selections file of the data directory, one `pattern<TAB>pod name`
+// line each.
+
+use crate::paths;
+use std::fs;
+
+fn read_selections() -> String {
+    paths::selections_file().and_then(|path| fs::read_to_string(path).ok()).unwrap_or_default()
+}
+
+fn write_selections(content: &str) {
+    if let Some(path) = paths::selections_file() {
+        if let Err(e) = paths::write(&path, content) {
+            log::warn!("failed to save selections to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// The pod picked the last time `pattern` was ambiguous.
+pub fn last_choice(pattern: &str) -> Option<String> {
+    lookup(&read_selections(), pattern)
+}
+
+/// Remember that `pod_name` was picked for `pattern`.
+pub fn remember(pattern: &str, pod_name: &str) {
+    write_selections(&update(&read_selections(), pattern, Some(pod_name)));
+}
+
+/// Forget the pod picked for `pattern`.
+pub fn forget(pattern: &str) {
+    write_selections(&update(&read_selections(), pattern, None));
+}
+
+fn lookup(content: &str, pattern: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let mut fields = line.splitn(2, '\t');
+        match (fields.next(), fields.next()) {
+            (Some(p), Some(pod_name)) if p == pattern => Some(pod_name.to_string()),
+            _ => None,
+        }
+    })
+}
+
+// replace the choice of the pattern, or drop it when pod_name is None
+fn update(content: &str, pattern: &str, pod_name: Option<&str>) -> String {
+    let mut lines: Vec<String> = content
+        .lines()
+        .filter(|line| line.split('\t').next() != Some(pattern))
+        .map(|line| line.to_string())
+        .collect();
+    if let Some(pod_name) = pod_name {
+        lines.push(format!("{}\t{}", pattern, pod_name));
+    }
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+#[test]
+fn test_update_selections() {
+    let content = update("", "kg", Some("sophon-kg-sophon2-bf9769d97-4hqgv"));
+    let content = update(&content, "ui", Some("sophon-ui-sophon2-79c997dd8c-vkths"));
+    let content = update(&content, "kg", Some("sophon-kg-sophon2-bf9769d97-fgpnn"));
+    assert_eq!(lookup(&content, "kg"), Some("sophon-kg-sophon2-bf9769d97-fgpnn".to_string()));
+    assert_eq!(lookup(&content, "ui"), Some("sophon-ui-sophon2-79c997dd8c-vkths".to_string()));
+    assert_eq!(content.lines().count(), 2);
+    let content = update(&content, "kg", None);
+    assert_eq!(lookup(&content, "kg"), None);
+    assert_eq!(content, "ui\tsophon-ui-sophon2-79c997dd8c-vkths\n");
+}
@@ -0,0 +1,58 @@
+// This module caps how many kubectl processes run at once when a command
+// works on many pods, so a bulk operation does not start hundreds of them
+// and flood the apiserver with requests.
+
+use std::sync::{Condvar, Mutex};
+
+pub struct Semaphore {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+/// Held while a process runs, the permit goes back to the semaphore when dropped.
+pub struct Permit<'a> {
+    semaphore: &'a Semaphore,
+}
+
+impl Semaphore {
+    /// A semaphore letting `permits` holders in at once, at least one.
+    pub fn new(permits: usize) -> Self {
+        Semaphore {available: Mutex::new(permits.max(1)), released: Condvar::new()}
+    }
+
+    /// Wait for a permit.
+    pub fn acquire(&self) -> Permit<'_> {
+        let mut available = self.available.lock().unwrap();
+        while *available == 0 {
+            available = self.released.wait(available).unwrap();
+        }
+        *available -= 1;
+        Permit {semaphore: self}
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.semaphore.available.lock().unwrap() += 1;
+        self.semaphore.released.notify_one();
+    }
+}
+
+#[test]
+fn test_semaphore() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    let semaphore = Semaphore::new(3);
+    let (running, most_running) = (AtomicUsize::new(0), AtomicUsize::new(0));
+    std::thread::scope(|scope| {
+        for _ in 0..12 {
+            scope.spawn(|| {
+                let _permit = semaphore.acquire();
+                let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most_running.fetch_max(now_running, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                running.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+    assert_eq!(most_running.load(Ordering::SeqCst), 3);
+}
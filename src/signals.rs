@@ -0,0 +1,178 @@
+// This module stops the kubectl processes rkl started when rkl itself is
+// stopped, by Ctrl-C, a SIGTERM or the terminal closing, instead of leaving
+// port-forwards or followed logs running orphaned. Long-running children run
+// in a process group of their own, which is killed as a whole, while an
+// interactive child like a shell stays in the foreground and handles Ctrl-C
+// itself. Before exiting, the terminal is restored the way rkl found it.
+//
+// A signal handler may do little more than write to a pipe, so the handler
+// only wakes a thread which does the cleaning up.
+
+use std::io;
+use std::process::{Child, Command, ExitStatus};
+use std::time::{Duration, Instant};
+#[cfg(unix)]
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+#[cfg(unix)]
+use std::sync::Mutex;
+
+// write end of the pipe waking the cleaning thread
+#[cfg(unix)]
+static WAKE: AtomicI32 = AtomicI32::new(-1);
+
+// children to stop, by pid, and whether each leads a process group
+#[cfg(unix)]
+static CHILDREN: Mutex<Vec<(u32, bool)>> = Mutex::new(Vec::new());
+
+// interactive children running in the foreground
+#[cfg(unix)]
+static INTERACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(unix)]
+extern "C" fn on_signal(signal: libc::c_int) {
+    let fd = WAKE.load(Ordering::SeqCst);
+    if fd >= 0 {
+        let byte = signal as u8;
+        unsafe { libc::write(fd, &byte as *const u8 as *const libc::c_void, 1) };
+    }
+}
+
+/// Stop the children of rkl, and restore the terminal, when rkl is interrupted.
+#[cfg(unix)]
+pub fn install() {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        log::warn!("cannot handle signals: {}", io::Error::last_os_error());
+        return;
+    }
+    WAKE.store(fds[1], Ordering::SeqCst);
+    let terminal = saved_terminal();
+    std::thread::spawn(move || loop {
+        let mut byte = 0u8;
+        if unsafe { libc::read(fds[0], &mut byte as *mut u8 as *mut libc::c_void, 1) } != 1 {
+            return;
+        }
+        let signal = byte as libc::c_int;
+        // Ctrl-C reached the interactive child too, which decides whether to stop
+        if signal == libc::SIGINT && INTERACTIVE.load(Ordering::SeqCst) > 0 {
+            continue;
+        }
+        stop_children();
+        if let Some(terminal) = &terminal {
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, terminal) };
+        }
+        crate::spinner::clear();
+        io::Write::flush(&mut io::stdout()).ok();
+        std::process::exit(128 + signal);
+    });
+    for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+        unsafe { libc::signal(signal, on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t) };
+    }
+}
+
+#[cfg(not(unix))]
+pub fn install() {}
+
+// the settings of the terminal on stdin, if it is one
+#[cfg(unix)]
+fn saved_terminal() -> Option<libc::termios> {
+    let mut terminal: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut terminal) } == 0 { Some(terminal) } else { None }
+}
+
+#[cfg(unix)]
+fn stop_children() {
+    for &(pid, group) in CHILDREN.lock().unwrap().iter() {
+        let pid = pid as libc::pid_t;
+        unsafe { libc::kill(if group { -pid } else { pid }, libc::SIGTERM) };
+    }
+}
+
+/// Start a command in a process group of its own, stopped with rkl until
+/// `forget` is called once it is waited for.
+pub fn spawn_in_group(command: &mut Command) -> io::Result<Child> {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    let child = command.spawn()?;
+    #[cfg(unix)]
+    CHILDREN.lock().unwrap().push((child.id(), true));
+    Ok(child)
+}
+
+/// Stop tracking a child which exited.
+pub fn forget(child: &Child) {
+    #[cfg(unix)]
+    CHILDREN.lock().unwrap().retain(|&(pid, _)| pid != child.id());
+    #[cfg(not(unix))]
+    let _ = child;
+}
+
+/// Run a long-running command to its end in a process group of its own.
+pub fn run_in_group(command: &mut Command) -> io::Result<ExitStatus> {
+    let mut child = spawn_in_group(command)?;
+    let status = child.wait();
+    forget(&child);
+    status
+}
+
+/// Run an interactive command to its end in the foreground, where it gets
+/// Ctrl-C from the terminal and handles it itself.
+pub fn run_interactive(command: &mut Command) -> io::Result<ExitStatus> {
+    let mut child = command.spawn()?;
+    #[cfg(unix)]
+    {
+        CHILDREN.lock().unwrap().push((child.id(), false));
+        INTERACTIVE.fetch_add(1, Ordering::SeqCst);
+    }
+    let status = child.wait();
+    #[cfg(unix)]
+    INTERACTIVE.fetch_sub(1, Ordering::SeqCst);
+    forget(&child);
+    status
+}
+
+/// Run an interactive command like `run_interactive`, printing `warning`
+/// once `notice` is left before `limit` and stopping the command at `limit`.
+pub fn run_interactive_within(command: &mut Command, limit: Duration, notice: Duration, warning: &str) -> io::Result<ExitStatus> {
+    #[cfg(unix)]
+    let terminal = saved_terminal();
+    let mut child = command.spawn()?;
+    #[cfg(unix)]
+    {
+        CHILDREN.lock().unwrap().push((child.id(), false));
+        INTERACTIVE.fetch_add(1, Ordering::SeqCst);
+    }
+    let started = Instant::now();
+    let mut warned = false;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Ok(status),
+            Ok(None) => {}
+            Err(e) => break Err(e),
+        }
+        let elapsed = started.elapsed();
+        if !warned && elapsed + notice >= limit {
+            // the terminal may be raw, where a line feed does not return the cursor
+            eprint!("\r\n{}\r\n", warning);
+            warned = true;
+        }
+        if elapsed >= limit {
+            #[cfg(unix)]
+            unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) };
+            #[cfg(not(unix))]
+            child.kill().ok();
+            let status = child.wait();
+            // kubectl stopped before it could restore the terminal
+            #[cfg(unix)]
+            if let Some(terminal) = &terminal {
+                unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, terminal) };
+            }
+            break status;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+    #[cfg(unix)]
+    INTERACTIVE.fetch_sub(1, Ordering::SeqCst);
+    forget(&child);
+    status
+}
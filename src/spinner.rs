@@ -0,0 +1,70 @@
+// This module shows a spinner with the elapsed time on stderr while kubectl
+// runs, so rkl does not look hung on a slow cluster. It only shows on a
+// terminal, after a short delay so quick commands print nothing, and its line
+// is cleared once the command is done or anything else is logged.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+static FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+// commands done sooner show no spinner
+static DELAY: Duration = Duration::from_millis(300);
+static TICK: Duration = Duration::from_millis(100);
+
+// whether a spinner line is on screen
+static SHOWN: AtomicBool = AtomicBool::new(false);
+
+/// Spins while held, cleared when dropped.
+pub struct Spinner {
+    done: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    /// Start spinning next to `message` unless stderr is not a terminal.
+    pub fn start(message: &str) -> Spinner {
+        let done = Arc::new((Mutex::new(false), Condvar::new()));
+        if !atty::is(atty::Stream::Stderr) {
+            return Spinner {done, thread: None};
+        }
+        let (thread_done, message) = (done.clone(), message.to_string());
+        let thread = std::thread::spawn(move || {
+            let started = Instant::now();
+            let (lock, tick) = &*thread_done;
+            let mut done = lock.lock().unwrap();
+            for frame in FRAMES.iter().cycle() {
+                done = tick.wait_timeout(done, TICK).unwrap().0;
+                if *done {
+                    break;
+                }
+                if started.elapsed() >= DELAY {
+                    eprint!("\r\x1b[K{} {} {:.1}s", frame, message, started.elapsed().as_secs_f64());
+                    SHOWN.store(true, Ordering::SeqCst);
+                }
+            }
+        });
+        Spinner {done, thread: Some(thread)}
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        let (lock, tick) = &*self.done;
+        *lock.lock().unwrap() = true;
+        tick.notify_one();
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+        clear();
+    }
+}
+
+/// Clear the line of the spinner if it is on screen.
+pub fn clear() {
+    if SHOWN.swap(false, Ordering::SeqCst) {
+        eprint!("\r\x1b[K");
+    }
+}
@@ -0,0 +1,60 @@
+// This module renders rows of cells as a plain text table, the way kubectl
+// prints its own listings.
+
+/// Turn the column titles into the first row of a table.
+pub fn header(titles: &[&str]) -> Vec<String> {
+    titles.iter().map(|t| t.to_string()).collect()
+}
+
+/// Wrap a cell in the escape codes of a terminal color.
+pub fn paint(cell: &str, color: u8) -> String {
+    format!("\x1b[{}m{}\x1b[0m", color, cell)
+}
+
+// the width of a cell on screen, leaving out color escape codes
+fn visible_width(cell: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in cell.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if !in_escape => width += 1,
+            _ => {}
+        }
+    }
+    width
+}
+
+/// Pad every column to its widest cell, separated by two spaces.
+pub fn format_table(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| rows.iter().filter_map(|r| r.get(i)).map(|c| visible_width(c)).max().unwrap_or(0))
+        .collect();
+    rows.iter()
+        .map(|r| {
+            r.iter()
+                .enumerate()
+                .map(|(i, c)| format!("{}{}", c, " ".repeat(widths[i] - visible_width(c))))
+                .collect::<Vec<String>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[test]
+fn test_format_table() {
+    let rows = vec![
+        header(&["NODE", "PODS"]),
+        vec!["kg-node43".to_string(), "14".to_string()],
+        vec!["kg-node44".to_string(), "1".to_string(), "".to_string()],
+    ];
+    assert_eq!(format_table(&rows), "NODE       PODS\nkg-node43  14\nkg-node44  1");
+    assert_eq!(format_table(&[]), "");
+    let rows = vec![header(&["CPU%", "NODE"]), vec![paint("95%", 31), "kg-node43".to_string()]];
+    assert_eq!(format_table(&rows), "CPU%  NODE\n\x1b[31m95%\x1b[0m   kg-node43");
+}
@@ -0,0 +1,169 @@
+// This module lists the usage of pods against their limits. Usage comes from
+// `kubectl top pod`, limits from a jsonpath template printing the limits of
+// every container, both printed by one shell command around a separator.
+// Quantities are parsed into millicores and bytes so pods can be sorted and
+// filtered by how close they are to their limits.
+
+use crate::table::{format_table, header};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Line printed between the output of `kubectl top pod` and the limits.
+pub static USAGE_SEPARATOR: &str = "---";
+
+/// Arguments to `kubectl get po` printing one line per pod: the pod name, a
+/// tab, then `cpu limit,memory limit` of every container separated by semicolons.
+pub fn limits_args() -> String {
+    r#"-o jsonpath='{range .items[*]}{.metadata.name}{"\t"}{range .spec.containers[*]}{.resources.limits.cpu}{","}{.resources.limits.memory}{";"}{end}{"\n"}{end}'"#.to_string()
+}
+
+/// Millicores of a cpu quantity like `250m` or `2`.
+pub fn parse_cpu(quantity: &str) -> Option<u64> {
+    match quantity.strip_suffix('m') {
+        Some(millis) => millis.parse().ok(),
+        None => quantity.parse::<f64>().ok().map(|cores| (cores * 1000.0).round() as u64),
+    }
+}
+
+#[test]
+fn test_parse_cpu() {
+    assert_eq!(parse_cpu("250m"), Some(250));
+    assert_eq!(parse_cpu("2"), Some(2000));
+    assert_eq!(parse_cpu("0.5"), Some(500));
+    assert_eq!(parse_cpu(""), None);
+}
+
+/// Bytes of a memory quantity like `512Mi`, `1G` or `1048576`.
+pub fn parse_memory(quantity: &str) -> Option<u64> {
+    let units: [(&str, u64); 8] = [
+        ("Ki", 1 << 10), ("Mi", 1 << 20), ("Gi", 1 << 30), ("Ti", 1 << 40),
+        ("k", 1_000), ("M", 1_000_000), ("G", 1_000_000_000), ("T", 1_000_000_000_000),
+    ];
+    for (suffix, factor) in &units {
+        if let Some(number) = quantity.strip_suffix(suffix) {
+            return number.parse::<f64>().ok().map(|n| (n * *factor as f64).round() as u64);
+        }
+    }
+    quantity.parse().ok()
+}
+
+#[test]
+fn test_parse_memory() {
+    assert_eq!(parse_memory("512Mi"), Some(512 << 20));
+    assert_eq!(parse_memory("1G"), Some(1_000_000_000));
+    assert_eq!(parse_memory("1048576"), Some(1 << 20));
+    assert_eq!(parse_memory("lots"), None);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PodUsage {
+    pub name: String,
+    pub cpu: u64,
+    pub memory: u64,
+    pub cpu_limit: Option<u64>,
+    pub memory_limit: Option<u64>,
+}
+
+impl PodUsage {
+    /// Cpu usage in percent of the limit, if every container has one.
+    pub fn cpu_percent(&self) -> Option<u64> {
+        self.cpu_limit.filter(|limit| *limit > 0).map(|limit| self.cpu * 100 / limit)
+    }
+
+    /// Memory usage in percent of the limit, if every container has one.
+    pub fn memory_percent(&self) -> Option<u64> {
+        self.memory_limit.filter(|limit| *limit > 0).map(|limit| self.memory * 100 / limit)
+    }
+}
+
+// the sum of the limits of all containers, none if one of them has no limit
+fn sum_limits<'a>(limits: impl Iterator<Item = &'a str>, parse: fn(&str) -> Option<u64>) -> Option<u64> {
+    limits.map(parse).sum()
+}
+
+/// Parse `kubectl top pod --no-headers`, the separator and the output of `limits_args`.
+pub fn parse_pod_usage(output: &str) -> Vec<PodUsage> {
+    let mut lines = output.lines();
+    let usage: Vec<(String, u64, u64)> = lines.by_ref()
+        .take_while(|line| line.trim() != USAGE_SEPARATOR)
+        .filter_map(|line| {
+            let (name, cpu, memory) = line.split_whitespace().collect_tuple()?;
+            Some((name.to_string(), parse_cpu(cpu)?, parse_memory(memory)?))
+        })
+        .collect();
+    let limits: HashMap<&str, (Option<u64>, Option<u64>)> = lines
+        .filter_map(|line| {
+            let (name, containers) = line.split_once('\t')?;
+            let containers: Vec<(&str, &str)> = containers.split(';')
+                .filter(|container| !container.is_empty())
+                .filter_map(|container| container.split_once(','))
+                .collect();
+            let cpu_limit = sum_limits(containers.iter().map(|(cpu, _)| *cpu), parse_cpu);
+            let memory_limit = sum_limits(containers.iter().map(|(_, memory)| *memory), parse_memory);
+            Some((name, (cpu_limit, memory_limit)))
+        })
+        .collect();
+    usage.into_iter()
+        .map(|(name, cpu, memory)| {
+            let (cpu_limit, memory_limit) = limits.get(&name[..]).copied().unwrap_or((None, None));
+            PodUsage {name, cpu, memory, cpu_limit, memory_limit}
+        })
+        .collect()
+}
+
+/// Keep pods using at least `above` percent of their limit, of memory or of
+/// cpu, sorted by that resource, the heaviest first.
+pub fn sort_and_filter(pods: Vec<PodUsage>, by_memory: bool, above: Option<u64>) -> Vec<PodUsage> {
+    pods.into_iter()
+        .filter(|pod| match above {
+            Some(above) => {
+                let percent = if by_memory { pod.memory_percent() } else { pod.cpu_percent() };
+                percent.is_some_and(|percent| percent >= above)
+            }
+            None => true,
+        })
+        .sorted_by(|a, b| {
+            let (a_usage, b_usage) = if by_memory { (a.memory, b.memory) } else { (a.cpu, b.cpu) };
+            b_usage.cmp(&a_usage).then_with(|| a.name.cmp(&b.name))
+        })
+        .collect()
+}
+
+/// Render the usage of pods as a table, in millicores and mebibytes.
+pub fn format_pod_usage(pods: &[PodUsage]) -> String {
+    let percent = |percent: Option<u64>| percent.map(|p| format!("{}%", p)).unwrap_or_else(|| "-".to_string());
+    let mut rows = vec![header(&["NAME", "CPU", "CPU/LIMIT", "MEMORY", "MEMORY/LIMIT"])];
+    for p in pods {
+        rows.push(vec![
+            p.name.clone(),
+            format!("{}m", p.cpu),
+            percent(p.cpu_percent()),
+            format!("{}Mi", p.memory >> 20),
+            percent(p.memory_percent()),
+        ]);
+    }
+    format_table(&rows)
+}
+
+#[test]
+fn test_pod_usage() {
+    let output = "kg-1   900m   300Mi\n\
+                  kg-2   150m   900Mi\n\
+                  ui-1   10m    50Mi\n\
+                  ---\n\
+                  kg-1\t1,1Gi;\n\
+                  kg-2\t500m,512Mi;500m,512Mi;\n\
+                  ui-1\t,;\n";
+    let pods = parse_pod_usage(output);
+    assert_eq!(pods[1].cpu_limit, Some(1000));
+    assert_eq!(pods[2].memory_limit, None);
+    assert_eq!(
+        format_pod_usage(&sort_and_filter(pods.clone(), false, None)),
+        "NAME  CPU   CPU/LIMIT  MEMORY  MEMORY/LIMIT\n\
+         kg-1  900m  90%        300Mi   29%\n\
+         kg-2  150m  15%        900Mi   87%\n\
+         ui-1  10m   -          50Mi    -"
+    );
+    let above: Vec<String> = sort_and_filter(pods, true, Some(80)).into_iter().map(|pod| pod.name).collect();
+    assert_eq!(above, vec!["kg-2"]);
+}
@@ -0,0 +1,65 @@
+// This module reads the versions of kubectl and of the cluster from
+// `kubectl version -o json`, to tell them apart from the version of rkl and
+// warn when kubectl is further from the cluster than the one minor version
+// of skew kubectl supports.
+
+use crate::json;
+
+/// Arguments to `kubectl version` printing both versions as JSON, the
+/// server one missing when the cluster cannot be reached.
+pub static VERSION_ARGS: &str = "version -o json";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Version {
+    /// Like `v1.27.3` or `v1.25.10-gke.1200`.
+    pub git_version: String,
+    pub major: u32,
+    pub minor: u32,
+}
+
+// a version object of the output, whose minor may end with + on managed clusters
+fn parse_version(text: &str) -> Option<Version> {
+    let fields = json::parse_object(text)?;
+    let field = |name: &str| fields.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone());
+    let number = |name: &str| field(name)?.trim_end_matches('+').parse().ok();
+    Some(Version {git_version: field("gitVersion")?, major: number("major")?, minor: number("minor")?})
+}
+
+/// The client and server versions in the output of `VERSION_ARGS`.
+pub fn parse_versions(output: &str) -> (Option<Version>, Option<Version>) {
+    let fields = json::parse_object(output).unwrap_or_default();
+    let version = |name: &str| fields.iter().find(|(key, _)| key == name).and_then(|(_, value)| parse_version(value));
+    (version("clientVersion"), version("serverVersion"))
+}
+
+/// A warning when kubectl is more than one minor version away from the cluster.
+pub fn skew_warning(client: &Version, server: &Version) -> Option<String> {
+    let skew = (client.minor as i64 - server.minor as i64).abs();
+    if client.major == server.major && skew <= 1 {
+        return None;
+    }
+    Some(format!(
+        "kubectl {} is {} minor versions away from the cluster {}, kubectl supports one minor version of skew",
+        client.git_version, skew, server.git_version
+    ))
+}
+
+#[test]
+fn test_parse_versions() {
+    let output = r#"{
+  "clientVersion": {"major": "1", "minor": "27", "gitVersion": "v1.27.3", "platform": "linux/amd64"},
+  "kustomizeVersion": "v5.0.1",
+  "serverVersion": {"major": "1", "minor": "25+", "gitVersion": "v1.25.10-gke.1200", "platform": "linux/amd64"}
+}"#;
+    let (client, server) = parse_versions(output);
+    let (client, server) = (client.unwrap(), server.unwrap());
+    assert_eq!(client.git_version, "v1.27.3");
+    assert_eq!(server.minor, 25);
+    assert_eq!(
+        skew_warning(&client, &server).as_deref(),
+        Some("kubectl v1.27.3 is 2 minor versions away from the cluster v1.25.10-gke.1200, kubectl supports one minor version of skew")
+    );
+    assert_eq!(skew_warning(&client, &Version {minor: 26, ..server}), None);
+    assert_eq!(parse_versions(r#"{"clientVersion": {"major": "1", "minor": "27", "gitVersion": "v1.27.3"}}"#).1, None);
+    assert_eq!(parse_versions("error: unknown flag"), (None, None));
+}
@@ -0,0 +1,143 @@
+// This module resolves the deployment a user means, either by its own name
+// or through the name of one of its pods. Pods of a deployment are named
+// `<deployment>-<replicaset hash>-<pod hash>`.
+
+use crate::table::{format_table, header};
+use itertools::Itertools;
+
+/// Arguments to `kubectl get deploy` printing one deployment name per line.
+pub static DEPLOYMENT_NAMES_ARGS: &str = "--no-headers -o custom-columns=NAME:.metadata.name";
+
+/// Arguments to `kubectl get rs` printing one tab separated line per replica
+/// set: owner, revision, change cause, images separated by spaces and creation time.
+pub fn revisions_args() -> String {
+    r#"-o jsonpath='{range .items[*]}{.metadata.ownerReferences[0].name}{"\t"}{.metadata.annotations.deployment\.kubernetes\.io/revision}{"\t"}{.metadata.annotations.kubernetes\.io/change-cause}{"\t"}{range .spec.template.spec.containers[*]}{.image}{" "}{end}{"\t"}{.metadata.creationTimestamp}{"\n"}{end}'"#.to_string()
+}
+
+/// A revision of a deployment, recorded on the replica set it created.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revision {
+    pub number: u32,
+    pub change_cause: String,
+    pub images: Vec<String>,
+    pub created: String,
+}
+
+/// Revisions of the deployment parsed from the output of `revisions_args`, oldest first.
+pub fn parse_revisions(output: &str, deployment: &str) -> Vec<Revision> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (owner, number, change_cause, images, created) = line.splitn(5, '\t').collect_tuple()?;
+            if owner != deployment {
+                return None;
+            }
+            Some(Revision {
+                number: number.parse().ok()?,
+                change_cause: change_cause.to_string(),
+                images: images.split_whitespace().map(|image| image.to_string()).collect(),
+                created: created.trim().to_string(),
+            })
+        })
+        .sorted_by_key(|revision| revision.number)
+        .collect()
+}
+
+/// Render revisions as an aligned table.
+pub fn format_revisions(revisions: &[Revision]) -> String {
+    let mut rows = vec![header(&["REVISION", "CREATED", "IMAGES", "CHANGE-CAUSE"])];
+    for r in revisions {
+        rows.push(vec![
+            r.number.to_string(),
+            r.created.clone(),
+            r.images.join(","),
+            if r.change_cause.is_empty() { "<none>".to_string() } else { r.change_cause.clone() },
+        ]);
+    }
+    format_table(&rows)
+}
+
+/// The deployment a pod belongs to, judging by its name.
+pub fn deployment_of(pod_name: &str) -> Option<String> {
+    let (_, _, deployment) = pod_name.rsplitn(3, '-').collect_tuple()?;
+    if deployment.is_empty() {
+        None
+    } else {
+        Some(deployment.to_string())
+    }
+}
+
+/// Arguments to `kubectl get po` printing the kind and name of the owner of a pod, tab separated.
+pub static OWNER_ARGS: &str = r#"-o jsonpath='{.metadata.ownerReferences[0].kind}{"\t"}{.metadata.ownerReferences[0].name}'"#;
+
+/// The resource type and name of the workload whose pod template made a pod,
+/// from the owner printed with `OWNER_ARGS`. Pods of a deployment are owned by
+/// one of its replica sets, named `<deployment>-<hash>`.
+pub fn template_owner(owner: &str) -> Option<(&'static str, String)> {
+    let (kind, name) = owner.trim().split_once('\t')?;
+    match kind {
+        "ReplicaSet" => name.rsplit_once('-').map(|(deployment, _)| ("deploy", deployment.to_string())),
+        "StatefulSet" => Some(("sts", name.to_string())),
+        "DaemonSet" => Some(("ds", name.to_string())),
+        "Job" => Some(("job", name.to_string())),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_template_owner() {
+    assert_eq!(template_owner("ReplicaSet\tsophon-kg-sophon2-bf9769d97"), Some(("deploy", "sophon-kg-sophon2".to_string())));
+    assert_eq!(template_owner("StatefulSet\tmysql\n"), Some(("sts", "mysql".to_string())));
+    assert_eq!(template_owner("Node\tkg-node43"), None);
+    assert_eq!(template_owner(""), None);
+}
+
+/// Deployments whose name contains the slice. When there is none, the
+/// deployments of pods whose name contains the slice.
+pub fn match_deployments(deployments: &[String], pod_names: &[&str], slice: &str) -> Vec<String> {
+    let by_name: Vec<String> = deployments.iter().filter(|d| d.contains(slice)).cloned().collect();
+    if !by_name.is_empty() {
+        return by_name;
+    }
+    pod_names
+        .iter()
+        .filter(|pod_name| pod_name.contains(slice))
+        .filter_map(|pod_name| deployment_of(pod_name))
+        .filter(|deployment| deployments.contains(deployment))
+        .unique()
+        .collect()
+}
+
+#[test]
+fn test_deployment_of() {
+    assert_eq!(deployment_of("sophon-kg-sophon2-bf9769d97-4hqgv"), Some("sophon-kg-sophon2".to_string()));
+    assert_eq!(deployment_of("kg-4hqgv"), None);
+    assert_eq!(deployment_of("-bf9769d97-4hqgv"), None);
+}
+
+#[test]
+fn test_match_deployments() {
+    let deployments = vec!["sophon-kg-sophon2".to_string(), "sophon-ui-sophon2".to_string()];
+    let pod_names = vec!["sophon-kg-sophon2-bf9769d97-4hqgv", "sophon-kg-sophon2-bf9769d97-fgpnn", "sophon-ui-sophon2-79c997dd8c-vkths"];
+    assert_eq!(match_deployments(&deployments, &pod_names, "kg"), vec!["sophon-kg-sophon2"]);
+    assert_eq!(match_deployments(&deployments, &pod_names, "sophon2"), deployments);
+    // only the pod name contains the slice
+    assert_eq!(match_deployments(&deployments, &pod_names, "fgpnn"), vec!["sophon-kg-sophon2"]);
+    assert!(match_deployments(&deployments, &pod_names, "notebook").is_empty());
+}
+
+#[test]
+fn test_parse_revisions() {
+    let output = "sophon-kg-sophon2\t3\tkubectl set image deploy/sophon-kg-sophon2 kg=transwarp/sophon-kg:sophon-3.0\ttranswarp/sophon-kg:sophon-3.0 nginx:1.19 \t2021-03-01T10:00:00Z\n\
+                  sophon-ui-sophon2\t1\t\ttranswarp/sophon-ui:sophon-3.0 \t2021-02-01T10:00:00Z\n\
+                  sophon-kg-sophon2\t2\t\ttranswarp/sophon-kg:sophon-2.9 nginx:1.19 \t2021-02-01T10:00:00Z\n";
+    let revisions = parse_revisions(output, "sophon-kg-sophon2");
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[0].number, 2);
+    assert_eq!(revisions[1].images, vec!["transwarp/sophon-kg:sophon-3.0", "nginx:1.19"]);
+    assert_eq!(
+        format_revisions(&revisions[..1]),
+        "REVISION  CREATED               IMAGES                                     CHANGE-CAUSE\n\
+         2         2021-02-01T10:00:00Z  transwarp/sophon-kg:sophon-2.9,nginx:1.19  <none>"
+    );
+}
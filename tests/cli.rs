@@ -0,0 +1,249 @@
+// End to end tests of rkl: each runs the binary with tests/fake-kubectl,
+// listing the pods of tests/fixtures/pods.tsv, types the answers to its
+// prompts on stdin, and checks the kubectl commands it logs, which debug
+// builds log instead of running.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+struct Run {
+    success: bool,
+    stdout: String,
+    stderr: String,
+}
+
+// a directory of its own for the config and data of rkl, away from those of the user
+fn home(test: &str) -> PathBuf {
+    let home = std::env::temp_dir().join(format!("rkl-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&home).unwrap();
+    home
+}
+
+fn rkl(test: &str, args: &[&str], stdin: &str) -> Run {
+    let fake_kubectl = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fake-kubectl");
+    let home = home(test);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rkl"))
+        .arg("--kubectl").arg(&fake_kubectl)
+        .args(args)
+        .env("XDG_CONFIG_HOME", &home)
+        .env("XDG_DATA_HOME", &home)
+        .env("RKL_INTERACTIVE", "1")
+        .env_remove("KUBERNETES_SERVICE_HOST")
+        .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .spawn().expect("failed to run rkl");
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_dir_all(&home).ok();
+    Run {
+        success: output.status.success(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    }
+}
+
+#[test]
+fn test_unique_name() {
+    let run = rkl("unique", &["image", "gateway"], "");
+    assert!(run.success, "{}", run.stderr);
+    assert!(run.stderr.contains("fake-kubectl -n sophon describe po sophon-gateway-sophon2-6dbf875495-dckc4 | grep Image"), "{}", run.stderr);
+    assert!(!run.stderr.contains("possible choices"));
+}
+
+#[test]
+fn test_pick_by_typed_name() {
+    let run = rkl("typed", &["image", "kg"], "4hqgv\n");
+    assert!(run.stderr.contains("possible choices"), "{}", run.stderr);
+    assert!(run.stderr.contains("describe po sophon-kg-sophon2-bf9769d97-4hqgv | grep Image"), "{}", run.stderr);
+    // the candidates and the prompt stay out of redirected output
+    assert_eq!(run.stdout, "");
+}
+
+#[test]
+fn test_pick_default() {
+    // the crashing replica ranks first
+    let run = rkl("default", &["image", "kg"], "\n");
+    assert!(run.stderr.contains("describe po sophon-kg-sophon2-bf9769d97-fgpnn | grep Image"), "{}", run.stderr);
+}
+
+#[test]
+fn test_all_matches() {
+    let run = rkl("all", &["--all-matches", "delete", "kg"], "");
+    assert!(run.success, "{}", run.stderr);
+    assert!(run.stderr.contains("delete po sophon-kg-sophon2-bf9769d97-fgpnn\n"), "{}", run.stderr);
+    assert!(run.stderr.contains("delete po sophon-kg-sophon2-bf9769d97-4hqgv\n"), "{}", run.stderr);
+}
+
+#[test]
+fn test_similar_names() {
+    let run = rkl("similar", &["image", "apimanagr"], "\n");
+    assert!(run.stderr.contains("no such a pod named like apimanagr found, showing similar names"), "{}", run.stderr);
+    assert!(run.stderr.contains("describe po sophon-apimanager-sophon2-58f4b7965-n99hz | grep Image"), "{}", run.stderr);
+}
+
+#[test]
+fn test_default_container() {
+    let run = rkl("default-container", &["logs", "gateway"], "");
+    assert!(run.stderr.contains("logs sophon-gateway-sophon2-6dbf875495-dckc4 -c gateway\n"), "{}", run.stderr);
+    let run = rkl("given-container", &["logs", "gateway", "-c", "nginx"], "");
+    assert!(run.stderr.contains("logs sophon-gateway-sophon2-6dbf875495-dckc4 -c nginx\n"), "{}", run.stderr);
+}
+
+#[test]
+fn test_sidecars() {
+    let run = rkl("sidecar-logs", &["logs", "notebook"], "");
+    assert!(run.stderr.contains("logs sophon-notebook-sophon2-57f5c77786-8lpkw -c notebook\n"), "{}", run.stderr);
+    let run = rkl("sidecar-image", &["image", "notebook"], "");
+    assert!(run.stderr.contains(r#"get po sophon-notebook-sophon2-57f5c77786-8lpkw -o jsonpath='{range .spec.containers[?(@.name=="notebook")]}"#), "{}", run.stderr);
+    let run = rkl("include-sidecars", &["--include-sidecars", "logs", "notebook"], "");
+    assert!(run.stderr.contains("logs sophon-notebook-sophon2-57f5c77786-8lpkw\n"), "{}", run.stderr);
+}
+
+#[test]
+fn test_list_candidates_only() {
+    let run = rkl("list-candidates", &["--list-candidates-only", "image", "kg"], "");
+    assert!(run.success, "{}", run.stderr);
+    let names: Vec<&str> = run.stdout.lines().map(|line| line.split('\t').next().unwrap()).collect();
+    assert_eq!(names, vec!["sophon-kg-sophon2-bf9769d97-fgpnn", "sophon-kg-sophon2-bf9769d97-4hqgv"]);
+    assert!(!run.stderr.contains("possible choices"), "{}", run.stderr);
+    // the name picked is fed back with --select
+    let run = rkl("select-name", &["--select", names[1], "image", "kg"], "");
+    assert!(run.stderr.contains("describe po sophon-kg-sophon2-bf9769d97-4hqgv | grep Image"), "{}", run.stderr);
+}
+
+#[test]
+fn test_list_candidates_output_file() {
+    let path = std::env::temp_dir().join(format!("rkl-candidates-{}.tsv", std::process::id()));
+    let run = rkl("candidates-file", &["--output-file", path.to_str().unwrap(), "--list-candidates-only", "image", "kg"], "");
+    assert!(run.success, "{}", run.stderr);
+    let saved = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(saved.lines().count(), 2, "{}", saved);
+    assert!(saved.starts_with("sophon-kg-sophon2-bf9769d97-fgpnn\t"), "{}", saved);
+}
+
+#[test]
+fn test_kubectl_args() {
+    let run = rkl("kubectl-args", &["describe", "gateway", "--", "--show-events=false"], "");
+    assert!(run.stderr.contains("describe po sophon-gateway-sophon2-6dbf875495-dckc4 --show-events=false\n"), "{}", run.stderr);
+}
+
+#[test]
+fn test_raw() {
+    let run = rkl("raw", &["raw", "gateway", "--", "get", "events", "--field-selector", "involvedObject.name={pod}"], "");
+    assert!(run.success, "{}", run.stderr);
+    assert!(run.stderr.contains("fake-kubectl -n sophon get events --field-selector involvedObject.name=sophon-gateway-sophon2-6dbf875495-dckc4\n"), "{}", run.stderr);
+}
+
+#[test]
+fn test_version() {
+    let run = rkl("version", &["version"], "");
+    assert!(run.success, "{}", run.stderr);
+    assert!(run.stdout.contains("kubectl  v1.27.3\ncluster  v1.25.10-gke.1200"), "{}", run.stdout);
+    assert!(run.stderr.contains("kubectl v1.27.3 is 2 minor versions away from the cluster"), "{}", run.stderr);
+}
+
+#[test]
+fn test_node_containers() {
+    let run = rkl("node-containers", &["node-containers", "gateway", "--template", "ssh root@{node}"], "");
+    assert!(run.success, "{}", run.stderr);
+    assert!(run.stderr.contains("ssh root@kg-node43 'id=$(crictl pods --name '\\''^sophon-gateway-sophon2-6dbf875495-dckc4$'\\''"), "{}", run.stderr);
+}
+
+#[test]
+fn test_read_only() {
+    let run = rkl("read-only-delete", &["--read-only", "delete", "gateway"], "");
+    assert!(!run.success);
+    assert!(run.stderr.contains("delete changes the cluster, refused in read-only mode"), "{}", run.stderr);
+    assert!(!run.stderr.contains("delete po"), "{}", run.stderr);
+    let run = rkl("read-only-describe", &["describe", "gateway", "--read-only"], "");
+    assert!(run.success, "{}", run.stderr);
+    assert!(run.stderr.contains("describe po sophon-gateway-sophon2-6dbf875495-dckc4"), "{}", run.stderr);
+}
+
+#[test]
+fn test_then_unsupported() {
+    let run = rkl("then-follow", &["logs", "kg", "--follow", "--then", "delete"], "");
+    assert!(!run.success);
+    assert!(run.stderr.contains("--then follows commands running on the pods they resolve"), "{}", run.stderr);
+    assert!(!run.stderr.contains("reason"), "{}", run.stderr);
+}
+
+#[test]
+fn test_reason_then_delete() {
+    let run = rkl("reason-then", &["--reason", " ", "describe", "gateway", "--then", "delete"], "");
+    assert!(!run.success);
+    assert!(run.stderr.contains("a reason is required to delete pods"), "{}", run.stderr);
+    assert!(!run.stderr.contains("describe po"), "{}", run.stderr);
+}
+
+#[test]
+fn test_rollback_confirmed() {
+    let run = rkl("rollback", &["rollback", "gateway"], "\nno\n");
+    assert!(!run.success);
+    assert!(!run.stderr.contains("rollout undo"), "{}", run.stderr);
+    let run = rkl("rollback", &["rollback", "gateway"], "2\nyes\n");
+    assert!(run.success, "{}", run.stderr);
+    assert!(run.stderr.contains("rollout undo deployment/sophon-gateway-sophon2 --to-revision=2\n"), "{}", run.stderr);
+}
+
+#[test]
+fn test_all_pages() {
+    let config = home("all-pages").join("rkl");
+    std::fs::create_dir_all(&config).unwrap();
+    std::fs::write(config.join("config"), "candidate_size = 2\n").unwrap();
+    let run = rkl("all-pages", &["describe", "sophon"], "z\n");
+    assert!(run.success, "{}", run.stderr);
+    assert!(run.stderr.contains("z: apply to all 11 candidates"), "{}", run.stderr);
+    assert_eq!(run.stderr.matches("describe po ").count(), 11, "{}", run.stderr);
+}
+
+#[test]
+fn test_reason_after_resolving() {
+    let require_reason = |test: &str| {
+        let config = home(test).join("rkl");
+        std::fs::create_dir_all(&config).unwrap();
+        std::fs::write(config.join("config"), "require_reason = true\n").unwrap();
+    };
+    require_reason("reason-none");
+    let run = rkl("reason-none", &["delete", "xqzjwv"], "");
+    assert!(!run.stderr.contains("reason for deleting"), "{}", run.stderr);
+    require_reason("reason-asked");
+    let run = rkl("reason-asked", &["delete", "gateway"], "rotated certificates\n");
+    assert!(run.success, "{}", run.stderr);
+    let asked = run.stderr.find("reason for deleting sophon-gateway-sophon2-6dbf875495-dckc4").expect(&run.stderr);
+    assert!(asked < run.stderr.find("delete po sophon-gateway-sophon2-6dbf875495-dckc4").unwrap(), "{}", run.stderr);
+}
+
+#[test]
+fn test_delete_selector() {
+    let run = rkl("selector", &["delete", "-l", "app in (kg)", "--all"], "yes\n");
+    assert!(run.success, "{}", run.stderr);
+    assert!(run.stderr.contains("fake-kubectl get po -l 'app in (kg)' -o jsonpath="), "{}", run.stderr);
+}
+
+#[test]
+fn test_check_access() {
+    let run = rkl("check-access", &["--check-access", "delete", "gateway"], "");
+    assert!(run.success, "{}", run.stderr);
+    let check = run.stderr.find("fake-kubectl -n sophon auth can-i delete pods\n").expect(&run.stderr);
+    assert!(check < run.stderr.find("delete po sophon-gateway-sophon2-6dbf875495-dckc4").unwrap(), "{}", run.stderr);
+}
+
+#[test]
+fn test_check_access_stdin() {
+    let run = rkl("check-access-stdin", &["--check-access", "delete", "--stdin"], "gateway\n");
+    assert!(run.success, "{}", run.stderr);
+    let check = run.stderr.find("fake-kubectl -n sophon auth can-i delete pods\n").expect(&run.stderr);
+    assert!(check < run.stderr.find("delete po sophon-gateway-sophon2-6dbf875495-dckc4").unwrap(), "{}", run.stderr);
+}
+
+#[test]
+fn test_find_all_contexts() {
+    let run = rkl("find", &["find", "kg", "--all-contexts"], "");
+    assert!(run.success, "{}", run.stderr);
+    let rows: Vec<Vec<&str>> = run.stdout.lines().map(|line| line.split_whitespace().take(3).collect()).collect();
+    assert_eq!(rows[0], vec!["CONTEXT", "NAMESPACE", "POD"]);
+    assert_eq!(rows[1], vec!["prod", "sophon", "sophon-kg-sophon2-bf9769d97-4hqgv"]);
+    assert_eq!(rows.iter().filter(|row| row[0] == "staging").count(), 2);
+}